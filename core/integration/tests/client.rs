@@ -1,11 +1,39 @@
 use nebula_server::web::routing::auth::signup::SignupRequest;
 use nebula_server::web::routing::auth::AuthResponse;
+use nebula_server::web::routing::auth::login::{LoginRequest, TwoFactorLoginRequest};
+use nebula_server::web::routing::auth::refresh::RefreshTokenRequest;
+use nebula_server::web::routing::auth::password::{ForgotPasswordRequest, ResetPasswordRequest};
+use nebula_server::web::routing::auth::verification::VerifyEmailRequest;
+use nebula_server::mail::MailMessage;
 use nebula_server::web::routing::dto::{RealmDto, RealmEventDto, SelfStatusDto, TaskDto};
-use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::web::routing::realms::calendar::events::{CreateEventRequest, OccurrenceRequest, UpdateEventRequest};
 use nebula_server::web::routing::realms::calendar::RealmEventObject;
 use nebula_server::web::routing::realms::create::CreateRealmPayload;
+use nebula_server::web::routing::realms::discovery::{DiscoverQuery, DiscoverRealmsObject, JoinRequestPayload, RealmJoinRequestsObject};
+use nebula_server::schema::realms::RealmVisibility;
 use nebula_server::web::routing::realms::task::{CreateTaskRequest, TaskObject};
 use nebula_server::web::routing::realms::RealmObject;
+use nebula_server::web::routing::users::sessions::SessionsObject;
+use nebula_server::web::routing::users::identities::IdentitiesObject;
+use nebula_server::web::routing::users::tokens::{AccessTokensObject, CreateAccessTokenRequest, CreatedAccessTokenObject};
+use nebula_server::data::scopes::TokenScope;
+use nebula_server::web::routing::users::account::{ChangeEmailRequest, ChangePasswordRequest, DeleteAccountRequest, UpdateProfileRequest};
+use nebula_server::service::account::OwnedRealmsAction;
+use nebula_server::web::routing::auth::oidc::{AuthorizationObject, OidcCallbackRequest};
+use nebula_server::web::routing::users::totp::{RecoveryCodesObject, TotpCodeRequest, TotpEnrollmentObject};
+use nebula_server::web::routing::realms::invites::{CreateInviteRequest, RealmInviteObject, RealmInvitesObject};
+use nebula_server::web::routing::invites::InvitationsObject;
+use nebula_server::web::routing::realms::members::{RealmMemberObject, RealmMembersObject, UpdateMemberRequest};
+use nebula_server::web::routing::realms::roles::{CreateRoleRequest, RealmRoleObject};
+use nebula_server::web::routing::realms::settings::{DeleteRealmRequest, UpdateRealmRequest};
+use nebula_server::web::routing::realms::transfer::TransferOwnershipRequest;
+use nebula_server::web::routing::realms::audit::{AuditLogObject, AuditLogQuery};
+use nebula_server::web::routing::realms::permissions::EffectivePermissionsObject;
+use nebula_server::web::routing::realms::share::{CreateShareLinkRequest, RealmShareLinkObject, RealmShareLinksObject};
+use nebula_server::web::routing::realms::calendar::feed::CreatedCalendarFeedObject;
+use nebula_server::data::snowflake::Snowflake;
+use nebula_server::data::permissions::RealmPermissions;
+use chrono::{DateTime, Utc};
 use reqwest::{Client, Method, Response};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, RequestBuilder};
 use serde::de::DeserializeOwned;
@@ -31,15 +59,148 @@ impl TestClient {
             .expect("REST_PORT environment variable not set");
         let base_url = format!("http://localhost:{port}");
         let client = ClientBuilder::new(Client::new()).build();
-        let token = login(&client, &base_url).await;
+        let auth = signup(&client, &base_url).await;
 
-        Self::with_token(&token)
+        Self::with_token(&auth.token)
+    }
+
+    pub async fn signup() -> AuthResponse {
+        Self::signup_as(&unique_email()).await
+    }
+
+    pub async fn signup_as(email: &str) -> AuthResponse {
+        let anonymous = Self::with_token("");
+        let signup_data = SignupRequest {
+            name: "Test User".to_string(),
+            email: email.to_string(),
+            password: "password".to_string(),
+        };
+        anonymous.post("api/signup", &signup_data).await
+    }
+
+    pub async fn login_as(email: &str, password: &str) -> Response {
+        let anonymous = Self::with_token("");
+        let login_data = LoginRequest {
+            email: email.to_string(),
+            password: password.to_string(),
+        };
+        anonymous.post_raw("api/login", &login_data).await
+    }
+
+    pub async fn login_with_code(challenge_token: &str, code: &str) -> Response {
+        let anonymous = Self::with_token("");
+        let payload = TwoFactorLoginRequest {
+            challenge_token: challenge_token.to_string(),
+            code: code.to_string(),
+        };
+        anonymous.post_raw("api/login/totp", &payload).await
+    }
+
+    pub async fn enroll_totp(&self) -> TotpEnrollmentObject {
+        self.post("api/users/@me/totp", &()).await
+    }
+
+    pub async fn confirm_totp(&self, code: &str) -> RecoveryCodesObject {
+        let payload = TotpCodeRequest {
+            code: code.to_string(),
+        };
+        self.post("api/users/@me/totp/confirm", &payload).await
+    }
+
+    pub async fn oidc_authorize(&self, provider: &str) -> AuthorizationObject {
+        self.post(&format!("api/oauth/{}/authorize", provider), &()).await
+    }
+
+    pub async fn oidc_callback(&self, provider: &str, code: &str, state: &str) -> Response {
+        let payload = OidcCallbackRequest {
+            code: code.to_string(),
+            state: state.to_string(),
+        };
+        self.post_raw(&format!("api/oauth/{}/callback", provider), &payload).await
+    }
+
+    pub async fn link_identity(&self, provider: &str) -> AuthorizationObject {
+        self.post(&format!("api/users/@me/identities/link/{}", provider), &()).await
+    }
+
+    pub async fn list_identities(&self) -> IdentitiesObject {
+        self.get("api/users/@me/identities").await
+    }
+
+    pub async fn create_access_token(&self, name: &str, scopes: &[TokenScope]) -> CreatedAccessTokenObject {
+        let payload = CreateAccessTokenRequest {
+            name: name.to_string(),
+            scopes: scopes.to_vec(),
+            expires_at: None,
+        };
+        self.post("api/users/@me/tokens", &payload).await
+    }
+
+    pub async fn list_access_tokens(&self) -> AccessTokensObject {
+        self.get("api/users/@me/tokens").await
+    }
+
+    pub async fn delete_access_token(&self, token_id: u64) -> Response {
+        self.delete_raw(&format!("api/users/@me/tokens/{}", token_id)).await
+    }
+
+    pub async fn update_profile(&self, name: &str) -> Response {
+        let payload = UpdateProfileRequest {
+            name: name.to_string(),
+        };
+        self.send_raw(Method::PATCH, "api/users/@me", &payload).await
+    }
+
+    pub async fn change_password(&self, current_password: &str, new_password: &str) -> Response {
+        let payload = ChangePasswordRequest {
+            current_password: Some(current_password.to_string()),
+            new_password: new_password.to_string(),
+        };
+        self.send_raw(Method::PUT, "api/users/@me/password", &payload).await
+    }
+
+    pub async fn change_email(&self, email: &str, password: &str) -> Response {
+        let payload = ChangeEmailRequest {
+            email: email.to_string(),
+            password: Some(password.to_string()),
+        };
+        self.post_raw("api/users/@me/email", &payload).await
+    }
+
+    pub async fn delete_account(&self, password: &str, owned_realms: Option<OwnedRealmsAction>) -> Response {
+        let payload = DeleteAccountRequest {
+            password: Some(password.to_string()),
+            owned_realms,
+        };
+        self.send_raw(Method::DELETE, "api/users/@me", &payload).await
+    }
+
+    pub async fn list_sessions(&self) -> SessionsObject {
+        self.get("api/users/@me/sessions").await
+    }
+
+    pub async fn revoke_session(&self, session_id: u64) -> Response {
+        self.delete_raw(&format!("api/users/@me/sessions/{}", session_id)).await
+    }
+
+    pub async fn logout(&self) -> Response {
+        self.delete_raw("api/users/@me/sessions/@current").await
+    }
+
+    pub async fn refresh_token(&self, refresh_token: &str) -> Response {
+        let payload = RefreshTokenRequest {
+            refresh_token: refresh_token.to_string(),
+        };
+        self.post_raw("api/token/refresh", &payload).await
     }
 
     pub async fn create_test_realm(&self) -> RealmDto {
         let payload = CreateRealmPayload {
             name: "Test Realm".to_string(),
             description: Some("Test realm description".to_string()),
+            parent_id: None,
+            visibility: RealmVisibility::Private,
+            timezone: None,
         };
 
         let realm_obj: RealmObject = self.post("api/realms", &payload).await;
@@ -50,21 +211,131 @@ impl TestClient {
         let payload = CreateRealmPayload {
             name: name.to_string(),
             description: description.map(|s| s.to_string()),
+            parent_id: None,
+            visibility: RealmVisibility::Private,
+            timezone: None,
         };
 
         let realm_obj: RealmObject = self.post("api/realms", &payload).await;
         realm_obj.realm
     }
 
+    pub async fn create_nested_realm(&self, name: &str, parent_id: u64) -> Response {
+        let payload = CreateRealmPayload {
+            name: name.to_string(),
+            description: None,
+            parent_id: Some(Snowflake(parent_id)),
+            visibility: RealmVisibility::Private,
+            timezone: None,
+        };
+        self.post_raw("api/realms", &payload).await
+    }
+
+    pub async fn discover_realms(&self, query: &DiscoverQuery) -> DiscoverRealmsObject {
+        self.get_with_query("api/realms/discover", query).await
+    }
+
+    pub async fn join_realm(&self, realm_id: u64) -> Response {
+        self.post_raw(&format!("api/realms/{}/join", realm_id), &()).await
+    }
+
+    pub async fn request_to_join(&self, realm_id: u64, message: Option<&str>) -> Response {
+        let payload = JoinRequestPayload { message: message.map(|message| message.to_string()) };
+        self.post_raw(&format!("api/realms/{}/join-requests", realm_id), &payload).await
+    }
+
+    pub async fn withdraw_join_request(&self, realm_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/join-requests/@me", realm_id)).await
+    }
+
+    pub async fn list_join_requests(&self, realm_id: u64) -> RealmJoinRequestsObject {
+        self.get(&format!("api/realms/{}/join-requests", realm_id)).await
+    }
+
+    pub async fn approve_join_request(&self, realm_id: u64, request_id: u64) -> Response {
+        self.post_raw(&format!("api/realms/{}/join-requests/{}/approve", realm_id, request_id), &()).await
+    }
+
+    pub async fn reject_join_request(&self, realm_id: u64, request_id: u64) -> Response {
+        self.post_raw(&format!("api/realms/{}/join-requests/{}/reject", realm_id, request_id), &()).await
+    }
+
     pub async fn get_current_status(&self) -> SelfStatusDto {
         let status_obj: SelfStatusDto = self.get("api/users/@me/status").await;
         status_obj
     }
 
+    pub async fn get_archived_status(&self) -> SelfStatusDto {
+        self.get("api/users/@me/status?include_archived=true").await
+    }
+
+    pub async fn archive_realm(&self, realm_id: u64) -> Response {
+        self.post_raw(&format!("api/realms/{}/archive", realm_id), &()).await
+    }
+
+    pub async fn restore_realm(&self, realm_id: u64) -> Response {
+        self.post_raw(&format!("api/realms/{}/restore", realm_id), &()).await
+    }
+
     pub async fn get_realm(&self, realm_id: u64) -> RealmObject {
         self.get(&format!("api/realms/{}", realm_id)).await
     }
 
+    pub async fn update_realm(&self, realm_id: u64, payload: &UpdateRealmRequest) -> Response {
+        self.send_raw(Method::PATCH, &format!("api/realms/{}", realm_id), payload).await
+    }
+
+    pub async fn delete_realm(&self, realm_id: u64, confirm_name: &str) -> Response {
+        let payload = DeleteRealmRequest { confirm_name: confirm_name.to_string() };
+        self.send_raw(Method::DELETE, &format!("api/realms/{}", realm_id), &payload).await
+    }
+
+    pub async fn propose_transfer(&self, realm_id: u64, user_id: u64) -> Response {
+        let payload = TransferOwnershipRequest { user_id: Snowflake(user_id) };
+        self.post_raw(&format!("api/realms/{}/transfer", realm_id), &payload).await
+    }
+
+    pub async fn accept_transfer(&self, realm_id: u64) -> Response {
+        self.post_raw(&format!("api/realms/{}/transfer/accept", realm_id), &()).await
+    }
+
+    pub async fn decline_transfer(&self, realm_id: u64) -> Response {
+        self.post_raw(&format!("api/realms/{}/transfer/decline", realm_id), &()).await
+    }
+
+    pub async fn create_share_link(&self, realm_id: u64, include_tasks: bool, include_descriptions: bool) -> RealmShareLinkObject {
+        let payload = CreateShareLinkRequest { include_tasks, include_descriptions };
+        self.post(&format!("api/realms/{}/share-links", realm_id), &payload).await
+    }
+
+    pub async fn list_share_links(&self, realm_id: u64) -> RealmShareLinksObject {
+        self.get(&format!("api/realms/{}/share-links", realm_id)).await
+    }
+
+    pub async fn revoke_share_link(&self, realm_id: u64, link_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/share-links/{}", realm_id, link_id)).await
+    }
+
+    pub async fn create_calendar_feed(&self, realm_id: u64) -> CreatedCalendarFeedObject {
+        self.post(&format!("api/realms/{}/calendar/feed", realm_id), &()).await
+    }
+
+    pub async fn get_calendar_feed(&self, realm_id: u64) -> Response {
+        self.get_raw(&format!("api/realms/{}/calendar/feed", realm_id)).await
+    }
+
+    pub async fn revoke_calendar_feed(&self, realm_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/calendar/feed", realm_id)).await
+    }
+
+    pub async fn get_my_permissions(&self, realm_id: u64) -> EffectivePermissionsObject {
+        self.get(&format!("api/realms/{}/permissions/@me", realm_id)).await
+    }
+
+    pub async fn get_audit_log(&self, realm_id: u64, query: &AuditLogQuery) -> AuditLogObject {
+        self.get_with_query(&format!("api/realms/{}/audit-log", realm_id), query).await
+    }
+
     pub async fn create_realm_event(&self, realm_id: u64, payload: &CreateEventRequest) -> RealmEventDto {
         let event_obj: RealmEventObject = self
             .post(&format!("api/realms/{}/calendar/events", realm_id), payload)
@@ -72,10 +343,32 @@ impl TestClient {
         event_obj.event
     }
 
+    pub async fn update_realm_event(&self, realm_id: u64, event_id: u64, payload: &UpdateEventRequest) -> Response {
+        self.send_raw(Method::PATCH, &format!("api/realms/{}/calendar/events/{}", realm_id, event_id), payload).await
+    }
+
+    pub async fn cancel_occurrence(&self, realm_id: u64, event_id: u64, occurrence: DateTime<Utc>) -> Response {
+        let payload = OccurrenceRequest { occurrence };
+        self.send_raw(Method::POST, &format!("api/realms/{}/calendar/events/{}/cancellations", realm_id, event_id), &payload).await
+    }
+
+    pub async fn restore_occurrence(&self, realm_id: u64, event_id: u64, occurrence: DateTime<Utc>) -> Response {
+        let payload = OccurrenceRequest { occurrence };
+        self.send_raw(Method::DELETE, &format!("api/realms/{}/calendar/events/{}/cancellations", realm_id, event_id), &payload).await
+    }
+
+    pub async fn delete_event_override(&self, realm_id: u64, event_id: u64, override_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/calendar/events/{}/overrides/{}", realm_id, event_id, override_id)).await
+    }
+
     pub async fn get_realm_schedule<P: Serialize>(&self, realm_id: u64, query: &P) -> nebula_server::web::routing::dto::RealmScheduleDto {
         self.get_with_query(&format!("api/realms/{}/calendar/schedule", realm_id), query).await
     }
 
+    pub async fn get_combined_schedule<P: Serialize>(&self, realm_id: u64, query: &P) -> nebula_server::web::routing::dto::RealmScheduleDto {
+        self.get_with_query(&format!("api/realms/{}/calendar/schedule/combined", realm_id), query).await
+    }
+
     pub async fn create_task(&self, realm_id: u64, payload: &CreateTaskRequest) -> TaskDto {
         let task_obj: TaskObject = self
             .post(&format!("api/realms/{}/tasks", realm_id), payload)
@@ -83,6 +376,71 @@ impl TestClient {
         task_obj.task
     }
 
+    pub async fn create_invite(&self, realm_id: u64, payload: &CreateInviteRequest) -> RealmInviteObject {
+        self.post(&format!("api/realms/{}/invites", realm_id), payload).await
+    }
+
+    pub async fn list_invites(&self, realm_id: u64) -> RealmInvitesObject {
+        self.get(&format!("api/realms/{}/invites", realm_id)).await
+    }
+
+    pub async fn revoke_invite(&self, realm_id: u64, invite_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/invites/{}", realm_id, invite_id)).await
+    }
+
+    pub async fn list_my_invites(&self) -> InvitationsObject {
+        self.get("api/users/@me/invites").await
+    }
+
+    pub async fn accept_invite(&self, code: &str) -> Response {
+        self.post_raw(&format!("api/invites/{}/accept", code), &()).await
+    }
+
+    pub async fn decline_invite(&self, code: &str) -> Response {
+        self.post_raw(&format!("api/invites/{}/decline", code), &()).await
+    }
+
+    pub async fn list_members(&self, realm_id: u64) -> RealmMembersObject {
+        self.get(&format!("api/realms/{}/members", realm_id)).await
+    }
+
+    pub async fn update_member(&self, realm_id: u64, user_id: u64, permissions: i16) -> Response {
+        let payload = UpdateMemberRequest { permissions: Some(RealmPermissions::new(permissions)), denied_permissions: None };
+        self.send_raw(Method::PATCH, &format!("api/realms/{}/members/{}", realm_id, user_id), &payload).await
+    }
+
+    pub async fn kick_member(&self, realm_id: u64, user_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/members/{}", realm_id, user_id)).await
+    }
+
+    pub async fn leave_realm(&self, realm_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/members/@me", realm_id)).await
+    }
+
+    pub async fn create_role(&self, realm_id: u64, name: &str, permissions: i16) -> RealmRoleObject {
+        let payload = CreateRoleRequest {
+            name: name.to_string(),
+            permissions: RealmPermissions::new(permissions),
+            colour: Some(0x3366ff),
+            position: None,
+        };
+        self.post(&format!("api/realms/{}/roles", realm_id), &payload).await
+    }
+
+    pub async fn delete_role(&self, realm_id: u64, role_id: u64) -> Response {
+        self.delete_raw(&format!("api/realms/{}/roles/{}", realm_id, role_id)).await
+    }
+
+    pub async fn assign_role(&self, realm_id: u64, user_id: u64, role_id: u64) -> Response {
+        self.send_raw(Method::PUT, &format!("api/realms/{}/members/{}/roles/{}", realm_id, user_id, role_id), &()).await
+    }
+
+    pub async fn deny_permissions(&self, realm_id: u64, user_id: u64, denied_permissions: i16) -> RealmMemberObject {
+        let payload = UpdateMemberRequest { permissions: None, denied_permissions: Some(RealmPermissions::new(denied_permissions)) };
+        let response = self.send_raw(Method::PATCH, &format!("api/realms/{}/members/{}", realm_id, user_id), &payload).await;
+        response.json().await.expect("Failed to parse member response")
+    }
+
     async fn post<T: Serialize, R: DeserializeOwned>(&self, endpoint: &str, body: &T) -> R {
         let response = self
             .request(Method::POST, endpoint)
@@ -95,6 +453,59 @@ impl TestClient {
         parse_response(endpoint, response).await
     }
 
+    pub async fn forgot_password(&self, email: &str) -> Response {
+        let payload = ForgotPasswordRequest {
+            email: email.to_string(),
+        };
+        self.post_raw("api/password/forgot", &payload).await
+    }
+
+    pub async fn reset_password(&self, token: &str, password: &str) -> Response {
+        let payload = ResetPasswordRequest {
+            token: token.to_string(),
+            password: password.to_string(),
+        };
+        self.post_raw("api/password/reset", &payload).await
+    }
+
+    pub async fn request_verification(&self) -> Response {
+        self.post_raw("api/users/@me/verification", &()).await
+    }
+
+    pub async fn verify_email(&self, token: &str) -> Response {
+        let payload = VerifyEmailRequest {
+            token: token.to_string(),
+        };
+        self.post_raw("api/email/verify", &payload).await
+    }
+
+    pub async fn post_raw<T: Serialize>(&self, endpoint: &str, body: &T) -> Response {
+        self.send_raw(Method::POST, endpoint, body).await
+    }
+
+    pub async fn send_raw<T: Serialize>(&self, method: Method, endpoint: &str, body: &T) -> Response {
+        self.request(method, endpoint)
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn get_raw(&self, endpoint: &str) -> Response {
+        self.request(Method::GET, endpoint)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
+    pub async fn delete_raw(&self, endpoint: &str) -> Response {
+        self.request(Method::DELETE, endpoint)
+            .send()
+            .await
+            .expect("Failed to send request")
+    }
+
     async fn get<R: DeserializeOwned>(&self, endpoint: &str) -> R {
         let response = self
             .request(Method::GET, endpoint)
@@ -123,10 +534,10 @@ impl TestClient {
     }
 }
 
-async fn login(client: &ClientWithMiddleware, base_url: &str) -> String {
+async fn signup(client: &ClientWithMiddleware, base_url: &str) -> AuthResponse {
     let signup_data = SignupRequest {
         name: "Test User".to_string(),
-        email: format!("test{}@example.com", chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)),
+        email: unique_email(),
         password: "password".to_string(),
     };
 
@@ -138,8 +549,36 @@ async fn login(client: &ClientWithMiddleware, base_url: &str) -> String {
         .await
         .expect("Failed to send signup request");
 
-    let auth_response: AuthResponse = response.json().await.expect("Failed to parse auth response");
-    auth_response.token
+    response.json().await.expect("Failed to parse auth response")
+}
+
+pub fn unique_email() -> String {
+    format!("test{}@example.com", chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0))
+}
+
+/// Reads the token out of the newest mail sent to `email` whose subject contains
+/// `subject`, relying on the server running with the file mailer.
+pub fn latest_mail_token(email: &str, subject: &str) -> String {
+    let directory = std::env::var("MAIL_DIR")
+        .expect("MAIL_DIR environment variable not set");
+    let mut messages = std::fs::read_dir(directory)
+        .expect("Failed to read mail directory")
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    messages.sort();
+
+    let message = messages
+        .iter()
+        .rev()
+        .map(|path| std::fs::read_to_string(path).unwrap())
+        .map(|contents| serde_json::from_str::<MailMessage>(&contents).unwrap())
+        .find(|message| message.to == email && message.subject.contains(subject))
+        .unwrap_or_else(|| panic!("No mail was sent to {}", email));
+
+    let (_, token) = message.body
+        .split_once("token=")
+        .expect("Mail does not contain a token");
+    token.split_whitespace().next().unwrap().to_string()
 }
 
 async fn parse_response<R: DeserializeOwned>(endpoint: &str, response: Response) -> R {
@@ -0,0 +1,44 @@
+use crate::client::TestClient;
+use crate::test_with_realm;
+use chrono::{DateTime, Utc};
+use nebula_server::data::scopes::TokenScope;
+use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+
+test_with_realm!(test_access_token_scopes, |ctx, realm| {
+    let created = ctx.client.create_access_token("ci", &[TokenScope::RealmsRead, TokenScope::EventsWrite]).await;
+    assert!(created.secret.starts_with("nbp_"));
+    assert_eq!(created.token.scopes, vec![TokenScope::RealmsRead, TokenScope::EventsWrite]);
+
+    let script = TestClient::with_token(&created.secret);
+    let fetched = script.get_realm(realm.id.0).await;
+    assert_eq!(fetched.realm.id, realm.id);
+
+    let payload = CreateEventRequest {
+        name: "Deploy".to_string(),
+        description: None,
+        location: None,
+        start_time: DateTime::parse_from_rfc3339("2024-06-01T17:00:00Z").unwrap().with_timezone(&Utc),
+        end_time: None,
+        recurrence: None,
+        timezone: None
+    };
+    let response = script.post_raw(&format!("api/realms/{}/calendar/events", realm.id.0), &payload).await;
+    assert_eq!(response.status(), 200);
+
+    let schedule = script.get_raw(&format!(
+        "api/realms/{}/calendar/schedule?start=2024-06-01T00:00:00Z&end=2024-06-02T00:00:00Z",
+        realm.id.0
+    )).await;
+    assert_eq!(schedule.status(), 403);
+    let status = script.get_raw("api/users/@me/status").await;
+    assert_eq!(status.status(), 403);
+
+    let tokens = ctx.client.list_access_tokens().await.tokens;
+    let listed = tokens.iter().find(|t| t.id == created.token.id).unwrap();
+    assert!(listed.last_used_at.is_some());
+
+    let response = ctx.client.delete_access_token(created.token.id.0).await;
+    assert_eq!(response.status(), 204);
+    let revoked = script.get_raw(&format!("api/realms/{}", realm.id.0)).await;
+    assert_eq!(revoked.status(), 401);
+});
@@ -0,0 +1,59 @@
+use crate::client::{latest_mail_token, unique_email, TestClient};
+use crate::test_with_context;
+use nebula_server::web::routing::auth::AuthResponse;
+
+test_with_context!(test_profile_and_password_change, |_ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+    let client = TestClient::with_token(&auth.token);
+
+    let response = client.update_profile("Renamed User").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(client.get_current_status().await.me.name, "Renamed User");
+
+    let other: AuthResponse = TestClient::login_as(&email, "password").await.json().await.unwrap();
+
+    let rejected = client.change_password("wrong-password", "new-password").await;
+    assert_eq!(rejected.status(), 403);
+    let response = client.change_password("password", "new-password").await;
+    assert_eq!(response.status(), 204);
+
+    let revoked = TestClient::with_token(&other.token).get_raw("api/users/@me/status").await;
+    assert_eq!(revoked.status(), 401);
+    assert_eq!(client.get_raw("api/users/@me/status").await.status(), 200);
+    assert_eq!(TestClient::login_as(&email, "new-password").await.status(), 200);
+});
+
+test_with_context!(test_email_change, |ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+    let client = TestClient::with_token(&auth.token);
+
+    let new_email = unique_email();
+    let response = client.change_email(&new_email, "password").await;
+    assert_eq!(response.status(), 204);
+    assert_eq!(TestClient::login_as(&new_email, "password").await.status(), 401);
+
+    let token = latest_mail_token(&new_email, "Confirm");
+    let response = ctx.client.post_raw("api/email/change/confirm", &serde_json::json!({ "token": token })).await;
+    assert_eq!(response.status(), 204);
+
+    assert_eq!(TestClient::login_as(&email, "password").await.status(), 401);
+    assert_eq!(TestClient::login_as(&new_email, "password").await.status(), 200);
+    assert!(client.get_current_status().await.email_verified);
+});
+
+test_with_context!(test_account_deletion, |_ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+    let client = TestClient::with_token(&auth.token);
+    client.create_test_realm().await;
+
+    let rejected = client.delete_account("wrong-password", None).await;
+    assert_eq!(rejected.status(), 403);
+    let response = client.delete_account("password", None).await;
+    assert_eq!(response.status(), 204);
+
+    assert_eq!(client.get_raw("api/users/@me/status").await.status(), 401);
+    assert_eq!(TestClient::login_as(&email, "password").await.status(), 401);
+});
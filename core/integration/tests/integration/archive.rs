@@ -0,0 +1,41 @@
+use crate::integration::member::join;
+use crate::test_with_realm;
+use chrono::{DateTime, Utc};
+use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::web::routing::realms::settings::UpdateRealmRequest;
+
+fn event() -> CreateEventRequest {
+    CreateEventRequest {
+        name: "Term party".to_string(),
+        description: None,
+        location: None,
+        start_time: DateTime::parse_from_rfc3339("2024-06-01T10:00:00Z").unwrap().with_timezone(&Utc),
+        end_time: None,
+        recurrence: None,
+        timezone: None
+    }
+}
+
+test_with_realm!(test_realm_archival, |ctx, realm| {
+    let (_, member) = join(&ctx.client, &realm).await;
+    assert_eq!(member.archive_realm(realm.id.0).await.status(), 403);
+    assert_eq!(ctx.client.archive_realm(realm.id.0).await.status(), 200);
+    assert_eq!(ctx.client.archive_realm(realm.id.0).await.status(), 409);
+
+    // Archived realms can still be read, but not changed.
+    let fetched = ctx.client.get_realm(realm.id.0).await.realm;
+    assert!(fetched.archived_at.is_some());
+    let events_path = format!("api/realms/{}/calendar/events", realm.id.0);
+    assert_eq!(ctx.client.post_raw(&events_path, &event()).await.status(), 423);
+    let rename = UpdateRealmRequest { name: Some("Renamed".to_string()), ..Default::default() };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &rename).await.status(), 423);
+
+    assert!(ctx.client.get_current_status().await.realms.is_empty());
+    assert_eq!(ctx.client.get_archived_status().await.realms.len(), 1);
+
+    assert_eq!(member.restore_realm(realm.id.0).await.status(), 403);
+    assert_eq!(ctx.client.restore_realm(realm.id.0).await.status(), 200);
+    assert_eq!(ctx.client.restore_realm(realm.id.0).await.status(), 409);
+    assert_eq!(ctx.client.post_raw(&events_path, &event()).await.status(), 200);
+    assert_eq!(ctx.client.get_current_status().await.realms.len(), 1);
+});
@@ -0,0 +1,41 @@
+use crate::integration::member::join;
+use crate::test_with_realm;
+use nebula_server::schema::realm_audit_log::{AuditAction, AuditTarget};
+use nebula_server::web::routing::realms::audit::AuditLogQuery;
+use nebula_server::web::routing::realms::settings::UpdateRealmRequest;
+
+test_with_realm!(test_audit_log, |ctx, realm| {
+    let update = UpdateRealmRequest { name: Some("Audited Realm".to_string()), ..Default::default() };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &update).await.status(), 200);
+    let role = ctx.client.create_role(realm.id.0, "Helpers", 0).await.role;
+    let (member_auth, member) = join(&ctx.client, &realm).await;
+
+    // Reading the log takes its own permission.
+    let response = member.get_raw(&format!("api/realms/{}/audit-log", realm.id.0)).await;
+    assert_eq!(response.status(), 403);
+
+    let log = ctx.client.get_audit_log(realm.id.0, &AuditLogQuery { before: None, limit: None }).await;
+    let actions: Vec<_> = log.entries.iter().map(|entry| (entry.action, entry.target_type)).collect();
+    assert!(actions.contains(&(AuditAction::Created, AuditTarget::Realm)));
+    assert!(actions.contains(&(AuditAction::Created, AuditTarget::Role)));
+    assert!(actions.contains(&(AuditAction::Created, AuditTarget::Invite)));
+    assert_eq!(log.entries[0].action, AuditAction::Joined);
+    assert_eq!(log.entries[0].actor_id.map(|id| id.0), Some(member_auth.user.id.0));
+    assert!(log.next.is_none());
+
+    // Updates only keep the fields that changed.
+    let renamed = log.entries.iter()
+        .find(|entry| entry.action == AuditAction::Updated && entry.target_type == AuditTarget::Realm)
+        .expect("Realm update wasn't logged");
+    assert_eq!(renamed.before, Some(serde_json::json!({ "name": realm.name })));
+    assert_eq!(renamed.after, Some(serde_json::json!({ "name": "Audited Realm" })));
+
+    assert_eq!(ctx.client.delete_role(realm.id.0, role.id.0).await.status(), 204);
+
+    let first = ctx.client.get_audit_log(realm.id.0, &AuditLogQuery { before: None, limit: Some(2) }).await;
+    assert_eq!(first.entries.len(), 2);
+    assert_eq!(first.entries[0].action, AuditAction::Deleted);
+    let next = first.next.expect("Expected another page");
+    let second = ctx.client.get_audit_log(realm.id.0, &AuditLogQuery { before: Some(next), limit: Some(2) }).await;
+    assert!(second.entries.iter().all(|entry| entry.id < next));
+});
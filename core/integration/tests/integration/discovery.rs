@@ -0,0 +1,76 @@
+use crate::client::TestClient;
+use crate::test_with_realm;
+use nebula_server::data::permissions::{BitwisePermissions, RealmPermissions};
+use nebula_server::schema::realms::RealmVisibility;
+use nebula_server::web::routing::realms::discovery::{DiscoverQuery, RealmJoinRequestObject};
+use nebula_server::web::routing::realms::settings::UpdateRealmRequest;
+
+async fn outsider() -> TestClient {
+    let auth = TestClient::signup().await;
+    TestClient::with_token(&auth.token)
+}
+
+fn search(q: &str) -> DiscoverQuery {
+    DiscoverQuery { q: Some(q.to_string()), ..Default::default() }
+}
+
+test_with_realm!(test_realm_discovery, |ctx, realm| {
+    // Other tests share the directory, so the realm gets a name only it has.
+    let name = format!("Guild {}", realm.id.0);
+    let listed = UpdateRealmRequest {
+        name: Some(name.clone()),
+        visibility: Some(RealmVisibility::Listed),
+        ..Default::default()
+    };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &listed).await.status(), 200);
+
+    let private = ctx.create_realm(&format!("Hidden {}", realm.id.0), None).await;
+    let visitor = outsider().await;
+    let found = visitor.discover_realms(&search(&name.to_uppercase())).await.realms;
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, realm.id);
+    assert_eq!(found[0].member_count, 1);
+    assert!(visitor.discover_realms(&search(&private.name)).await.realms.is_empty());
+    assert_eq!(visitor.join_realm(private.id.0).await.status(), 404);
+    assert_eq!(visitor.request_to_join(private.id.0, None).await.status(), 404);
+});
+
+test_with_realm!(test_join_requests, |ctx, realm| {
+    let listed = UpdateRealmRequest { visibility: Some(RealmVisibility::Listed), ..Default::default() };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &listed).await.status(), 200);
+
+    let applicant = outsider().await;
+    assert_eq!(applicant.join_realm(realm.id.0).await.status(), 403);
+    assert_eq!(applicant.request_to_join(realm.id.0, Some("Let me in")).await.status(), 200);
+    assert_eq!(applicant.request_to_join(realm.id.0, None).await.status(), 409);
+
+    let requests = ctx.client.list_join_requests(realm.id.0).await.requests;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].message.as_deref(), Some("Let me in"));
+    assert_eq!(applicant.withdraw_join_request(realm.id.0).await.status(), 204);
+    assert!(ctx.client.list_join_requests(realm.id.0).await.requests.is_empty());
+
+    let response = applicant.request_to_join(realm.id.0, None).await;
+    let request = response.json::<RealmJoinRequestObject>().await.expect("Failed to parse join request").request;
+    assert_eq!(ctx.client.reject_join_request(realm.id.0, request.id.0).await.status(), 204);
+    assert_eq!(applicant.get_raw(&format!("api/realms/{}", realm.id.0)).await.status(), 403);
+
+    let response = applicant.request_to_join(realm.id.0, None).await;
+    let request = response.json::<RealmJoinRequestObject>().await.expect("Failed to parse join request").request;
+    assert_eq!(ctx.client.approve_join_request(realm.id.0, request.id.0).await.status(), 200);
+    assert_eq!(ctx.client.approve_join_request(realm.id.0, request.id.0).await.status(), 404);
+    assert_eq!(applicant.get_my_permissions(realm.id.0).await.bits, RealmPermissions::MEMBER_DEFAULT.bits());
+    assert_eq!(applicant.request_to_join(realm.id.0, None).await.status(), 409);
+});
+
+test_with_realm!(test_open_realm_join, |ctx, realm| {
+    let open = UpdateRealmRequest { visibility: Some(RealmVisibility::Open), ..Default::default() };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &open).await.status(), 200);
+
+    let visitor = outsider().await;
+    assert_eq!(visitor.request_to_join(realm.id.0, None).await.status(), 409);
+    assert_eq!(visitor.join_realm(realm.id.0).await.status(), 200);
+    assert_eq!(visitor.join_realm(realm.id.0).await.status(), 409);
+    assert_eq!(visitor.get_my_permissions(realm.id.0).await.bits, RealmPermissions::MEMBER_DEFAULT.bits());
+    assert_eq!(ctx.client.list_members(realm.id.0).await.members.len(), 2);
+});
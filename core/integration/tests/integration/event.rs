@@ -1,6 +1,12 @@
+use crate::integration::member::join;
 use crate::test_with_realm;
 use chrono::{DateTime, Utc};
-use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use nebula_server::web::routing::dto::OccurrenceStatus;
+use nebula_server::web::routing::realms::calendar::events::{CreateEventRequest, EditScope, UpdateEventRequest};
+use nebula_server::web::routing::realms::calendar::occurrences::OccurrenceQuery;
+use nebula_server::web::routing::realms::calendar::RealmEventsObject;
+use rrule::{Frequency, RRule};
 
 test_with_realm!(test_event_creation, |ctx, realm| {
     let payload = CreateEventRequest {
@@ -9,7 +15,8 @@ test_with_realm!(test_event_creation, |ctx, realm| {
         location: Some("Dumbfit".to_string()),
         start_time: DateTime::parse_from_rfc3339("2024-06-01T17:00:00Z").unwrap().with_timezone(&Utc),
         end_time: Some(DateTime::parse_from_rfc3339("2024-06-01T18:00:00Z").unwrap().with_timezone(&Utc)),
-        recurrence: None
+        recurrence: None,
+        timezone: None
     };
 
     let event = ctx.client.create_realm_event(realm.id.0, &payload).await;
@@ -18,4 +25,145 @@ test_with_realm!(test_event_creation, |ctx, realm| {
     assert_eq!(event.description, Some("Workout time".to_string()));
     assert_eq!(event.location, Some("Dumbfit".to_string()));
     assert!(event.id.0 > 0);
-});
\ No newline at end of file
+});
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+test_with_realm!(test_event_update_scopes, |ctx, realm| {
+    let payload = CreateEventRequest {
+        name: "Standup".to_string(),
+        description: None,
+        location: None,
+        start_time: at("2024-06-03T10:00:00Z"),
+        end_time: Some(at("2024-06-03T10:15:00Z")),
+        recurrence: Some(RRule::new(Frequency::Weekly).count(6)),
+        timezone: None
+    };
+    let series = ctx.client.create_realm_event(realm.id.0, &payload).await;
+
+    let (member_auth, member) = join(&ctx.client, &realm).await;
+    let rename = UpdateEventRequest { name: Some("Renamed".to_string()), ..Default::default() };
+    assert_eq!(member.update_realm_event(realm.id.0, series.id.0, &rename).await.status(), 403);
+
+    // A single occurrence gets an override, leaving the series as it was.
+    let this = UpdateEventRequest {
+        scope: EditScope::This,
+        occurrence: Some(at("2024-06-10T10:00:00Z")),
+        name: Some("Moved standup".to_string()),
+        start_time: Some(at("2024-06-11T10:00:00Z")),
+        ..Default::default()
+    };
+    let response = ctx.client.update_realm_event(realm.id.0, series.id.0, &this).await;
+    assert_eq!(response.status(), 200);
+    let updated = response.json::<RealmEventsObject>().await.expect("Failed to parse events");
+    assert_eq!(updated.events.len(), 1);
+    assert_eq!(updated.events[0].name, "Standup");
+    assert_eq!(updated.overrides.len(), 1);
+    assert_eq!(updated.overrides[0].original_start, at("2024-06-10T10:00:00Z"));
+    assert_eq!(updated.overrides[0].name.as_deref(), Some("Moved standup"));
+    let override_id = updated.overrides[0].id.0;
+
+    // Cancelled occurrences can't be edited, and only they can be restored.
+    assert_eq!(ctx.client.cancel_occurrence(realm.id.0, series.id.0, at("2024-06-17T10:00:00Z")).await.status(), 200);
+    assert_eq!(ctx.client.cancel_occurrence(realm.id.0, series.id.0, at("2024-06-17T10:00:00Z")).await.status(), 400);
+    assert_eq!(ctx.client.restore_occurrence(realm.id.0, series.id.0, at("2024-06-03T10:00:00Z")).await.status(), 400);
+    let cancelled = UpdateEventRequest { occurrence: Some(at("2024-06-17T10:00:00Z")), ..this.clone() };
+    assert_eq!(ctx.client.update_realm_event(realm.id.0, series.id.0, &cancelled).await.status(), 400);
+
+    let missing = UpdateEventRequest { scope: EditScope::This, ..Default::default() };
+    assert_eq!(ctx.client.update_realm_event(realm.id.0, series.id.0, &missing).await.status(), 400);
+    let off_series = UpdateEventRequest { occurrence: Some(at("2024-06-12T10:00:00Z")), ..this };
+    assert_eq!(ctx.client.update_realm_event(realm.id.0, series.id.0, &off_series).await.status(), 400);
+
+    // The rest of the series splits off, keeping the occurrences it had left.
+    let following = UpdateEventRequest {
+        scope: EditScope::ThisAndFollowing,
+        occurrence: Some(at("2024-06-24T10:00:00Z")),
+        location: Some(Some("Room 4".to_string())),
+        ..Default::default()
+    };
+    let response = ctx.client.update_realm_event(realm.id.0, series.id.0, &following).await;
+    assert_eq!(response.status(), 200);
+    let events = response.json::<RealmEventsObject>().await.expect("Failed to parse events").events;
+    assert_eq!(events[0].start_time, at("2024-06-24T10:00:00Z"));
+    assert_eq!(events[0].location.as_deref(), Some("Room 4"));
+    assert_eq!(events[0].recurrence.as_ref().and_then(|rule| rule.get_count()), Some(3));
+    let following_id = events[0].id.0;
+    assert!(events[1].recurrence.as_ref().is_some_and(|rule| rule.get_until().is_some()));
+
+    let query = OccurrenceQuery { start: at("2024-06-01T00:00:00Z"), end: at("2024-07-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &query).await;
+    let mut occurrences: Vec<_> = schedule.occurrences.iter()
+        .map(|occurrence| (occurrence.occurrence_start, occurrence.status))
+        .collect();
+    occurrences.sort_by_key(|(start, _)| *start);
+    assert_eq!(occurrences, vec![
+        (at("2024-06-03T10:00:00Z"), OccurrenceStatus::Scheduled),
+        (at("2024-06-11T10:00:00Z"), OccurrenceStatus::Overridden),
+        (at("2024-06-17T10:00:00Z"), OccurrenceStatus::Cancelled),
+        (at("2024-06-24T10:00:00Z"), OccurrenceStatus::Scheduled),
+    ]);
+    let moved = schedule.occurrences.iter().find(|occurrence| occurrence.status == OccurrenceStatus::Overridden).unwrap();
+    assert_eq!(moved.name.as_deref(), Some("Moved standup"));
+    assert_eq!(moved.original_start, Some(at("2024-06-10T10:00:00Z")));
+    assert_eq!(moved.occurrence_end, Some(at("2024-06-11T10:15:00Z")));
+
+    assert_eq!(ctx.client.restore_occurrence(realm.id.0, series.id.0, at("2024-06-17T10:00:00Z")).await.status(), 200);
+    assert_eq!(ctx.client.delete_event_override(realm.id.0, series.id.0, override_id).await.status(), 200);
+    assert_eq!(ctx.client.delete_event_override(realm.id.0, series.id.0, override_id).await.status(), 404);
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &query).await;
+    assert!(schedule.occurrences.iter().all(|occurrence| occurrence.status == OccurrenceStatus::Scheduled));
+
+    let everything = UpdateEventRequest { description: Some(Some("Daily sync".to_string())), ..Default::default() };
+    let response = ctx.client.update_realm_event(realm.id.0, series.id.0, &everything).await;
+    let events = response.json::<RealmEventsObject>().await.expect("Failed to parse events").events;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].description.as_deref(), Some("Daily sync"));
+
+    // Whoever splits a series, its new half still belongs to the creator.
+    let manage_events = RealmPermissions::from_slice(&[RealmPermission::ViewCalendar, RealmPermission::ManageEvents]).bits();
+    assert_eq!(ctx.client.update_member(realm.id.0, member_auth.user.id.0, manage_events).await.status(), 200);
+    let split = UpdateEventRequest { occurrence: Some(at("2024-07-01T10:00:00Z")), ..following };
+    let response = member.update_realm_event(realm.id.0, following_id, &split).await;
+    assert_eq!(response.status(), 200);
+    let events = response.json::<RealmEventsObject>().await.expect("Failed to parse events").events;
+    assert_eq!(events[0].created_by, series.created_by);
+});
+
+test_with_realm!(test_event_series_moves, |ctx, realm| {
+    let payload = CreateEventRequest {
+        name: "Retro".to_string(),
+        description: None,
+        location: None,
+        start_time: at("2024-06-03T15:00:00Z"),
+        end_time: Some(at("2024-06-03T16:00:00Z")),
+        recurrence: Some(RRule::new(Frequency::Weekly).count(4)),
+        timezone: None
+    };
+    let series = ctx.client.create_realm_event(realm.id.0, &payload).await;
+    assert_eq!(ctx.client.cancel_occurrence(realm.id.0, series.id.0, at("2024-06-10T15:00:00Z")).await.status(), 200);
+    let this = UpdateEventRequest {
+        scope: EditScope::This,
+        occurrence: Some(at("2024-06-17T15:00:00Z")),
+        name: Some("Long retro".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(ctx.client.update_realm_event(realm.id.0, series.id.0, &this).await.status(), 200);
+
+    // Moving the whole series leaves its exceptions behind.
+    let later = UpdateEventRequest {
+        start_time: Some(at("2024-06-03T16:00:00Z")),
+        end_time: Some(Some(at("2024-06-03T17:00:00Z"))),
+        ..Default::default()
+    };
+    let response = ctx.client.update_realm_event(realm.id.0, series.id.0, &later).await;
+    assert_eq!(response.status(), 200);
+    let events = response.json::<RealmEventsObject>().await.expect("Failed to parse events").events;
+    assert!(events[0].exdates.is_empty());
+
+    let query = OccurrenceQuery { start: at("2024-06-01T00:00:00Z"), end: at("2024-07-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &query).await;
+    assert_eq!(schedule.occurrences.len(), 4);
+    assert!(schedule.occurrences.iter().all(|occurrence| occurrence.status == OccurrenceStatus::Scheduled));
+});
@@ -0,0 +1,55 @@
+use std::io::{Cursor, Read};
+use crate::client::{unique_email, TestClient};
+use crate::test_with_context;
+use chrono::{DateTime, Utc};
+use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::web::routing::realms::task::CreateTaskRequest;
+
+test_with_context!(test_personal_data_export, |_ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+    let client = TestClient::with_token(&auth.token);
+    let realm = client.create_test_realm().await;
+
+    client.create_realm_event(realm.id.0, &CreateEventRequest {
+        name: "Standup, daily".to_string(),
+        description: None,
+        location: None,
+        start_time: DateTime::parse_from_rfc3339("2024-06-03T09:00:00Z").unwrap().with_timezone(&Utc),
+        end_time: None,
+        recurrence: None,
+        timezone: None
+    }).await;
+    client.create_task(realm.id.0, &CreateTaskRequest {
+        title: "Write report".to_string(),
+        description: None,
+        due_date: None,
+        start_date: None,
+        planned_for: None,
+        priority: None,
+        completed: false
+    }).await;
+
+    let response = client.get_raw("api/users/@me/export").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/zip");
+
+    let bytes = response.bytes().await.unwrap();
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes.to_vec())).unwrap();
+
+    let mut json = String::new();
+    archive.by_name("export.json").unwrap().read_to_string(&mut json).unwrap();
+    let data: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(data["user"]["email"], email.as_str());
+    assert!(data["user"].get("password_hash").is_none());
+    assert_eq!(data["memberships"].as_array().unwrap().len(), 1);
+    assert_eq!(data["events"][0]["name"], "Standup, daily");
+    assert_eq!(data["tasks"][0]["title"], "Write report");
+
+    let mut calendar = String::new();
+    archive.by_name("calendar.ics").unwrap().read_to_string(&mut calendar).unwrap();
+    assert!(calendar.contains("SUMMARY:Standup\\, daily\r\n"));
+    assert!(calendar.contains("BEGIN:VTODO"));
+
+    assert_eq!(client.get_raw("api/users/@me/exports/1").await.status(), 404);
+});
@@ -0,0 +1,107 @@
+use crate::client::TestClient;
+use crate::integration::member::join;
+use crate::test_with_realm;
+use chrono::{DateTime, Utc};
+use nebula_server::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use nebula_server::web::routing::realms::calendar::events::{CreateEventRequest, EditScope, UpdateEventRequest};
+use nebula_server::web::routing::realms::calendar::feed::RealmCalendarFeedObject;
+use nebula_server::web::routing::realms::task::CreateTaskRequest;
+use rrule::{Frequency, RRule};
+
+test_with_realm!(test_calendar_export, |ctx, realm| {
+    let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+    let series = ctx.client.create_realm_event(realm.id.0, &CreateEventRequest {
+        name: "Standup".to_string(),
+        description: None,
+        location: Some("Room 1, upstairs".to_string()),
+        start_time: at("2024-06-03T08:00:00Z"),
+        end_time: Some(at("2024-06-03T08:15:00Z")),
+        recurrence: Some(RRule::new(Frequency::Weekly).count(4)),
+        timezone: Some("Europe/Berlin".to_string())
+    }).await;
+    ctx.client.cancel_occurrence(realm.id.0, series.id.0, at("2024-06-10T08:00:00Z")).await;
+    let moved = UpdateEventRequest {
+        scope: EditScope::This,
+        occurrence: Some(at("2024-06-17T08:00:00Z")),
+        start_time: Some(at("2024-06-18T08:00:00Z")),
+        ..Default::default()
+    };
+    ctx.client.update_realm_event(realm.id.0, series.id.0, &moved).await;
+    ctx.client.create_task(realm.id.0, &CreateTaskRequest {
+        title: "Book the room".to_string(),
+        description: None,
+        due_date: Some(at("2024-06-01T12:00:00Z")),
+        start_date: None,
+        planned_for: None,
+        priority: None,
+        completed: false
+    }).await;
+
+    let path = format!("api/realms/{}/calendar.ics", realm.id.0);
+    let response = ctx.client.get_raw(&path).await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/calendar"));
+    let calendar = response.text().await.expect("Failed to read calendar");
+    assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(calendar.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Berlin\r\n"));
+    assert!(calendar.contains("DTSTART;TZID=Europe/Berlin:20240603T100000\r\n"));
+    assert!(calendar.contains("COUNT=4"));
+    assert!(calendar.contains("LOCATION:Room 1\\, upstairs\r\n"));
+    assert!(calendar.contains("EXDATE;TZID=Europe/Berlin:20240610T100000\r\n"));
+    assert!(calendar.contains("RECURRENCE-ID;TZID=Europe/Berlin:20240617T100000\r\n"));
+    assert!(calendar.contains("DTSTART;TZID=Europe/Berlin:20240618T100000\r\n"));
+    assert!(!calendar.contains("BEGIN:VTODO"));
+
+    let calendar = ctx.client.get_raw(&format!("{path}?tasks=true")).await.text().await.unwrap();
+    assert!(calendar.contains("BEGIN:VTODO\r\n"));
+    assert!(calendar.contains("SUMMARY:Book the room\r\n"));
+
+    let anonymous = TestClient::with_token("");
+    assert_eq!(anonymous.get_raw(&path).await.status(), 401);
+});
+
+test_with_realm!(test_calendar_feeds, |ctx, realm| {
+    let (member_auth, member) = join(&ctx.client, &realm).await;
+    let member_id = member_auth.user.id.0;
+    let view_calendar = RealmPermissions::from_slice(&[RealmPermission::ViewCalendar]).bits();
+    assert_eq!(ctx.client.update_member(realm.id.0, member_id, view_calendar).await.status(), 200);
+    assert_eq!(member.get_calendar_feed(realm.id.0).await.status(), 404);
+
+    // Calendar apps subscribe without an account session.
+    let created = member.create_calendar_feed(realm.id.0).await;
+    assert!(created.path.ends_with(&created.secret));
+    let anonymous = TestClient::with_token("");
+    let feed_path = created.path.trim_start_matches('/');
+    let response = anonymous.get_raw(feed_path).await;
+    assert_eq!(response.status(), 200);
+    assert!(response.text().await.unwrap().contains("X-WR-CALNAME:"));
+    let response = member.get_calendar_feed(realm.id.0).await;
+    let feed: RealmCalendarFeedObject = response.json().await.expect("Failed to parse calendar feed");
+    assert_eq!(feed.feed.id, created.feed.id);
+    assert!(feed.feed.last_used_at.is_some());
+
+    // The token only opens this realm's calendar.
+    let other = ctx.create_realm("Other Realm", None).await;
+    let other_path = format!("api/realms/{}/calendar.ics?token={}", other.id.0, created.secret);
+    assert_eq!(anonymous.get_raw(&other_path).await.status(), 403);
+    let owner_feed = ctx.client.create_calendar_feed(realm.id.0).await;
+    let other_path = format!("api/realms/{}/calendar.ics?token={}", other.id.0, owner_feed.secret);
+    assert_eq!(anonymous.get_raw(&other_path).await.status(), 404);
+    let schedule_path = format!("api/realms/{}/calendar/schedule?token={}", realm.id.0, created.secret);
+    assert_eq!(anonymous.get_raw(&schedule_path).await.status(), 401);
+
+    // Creating a new feed replaces the old URL.
+    let rotated = member.create_calendar_feed(realm.id.0).await;
+    assert_eq!(anonymous.get_raw(feed_path).await.status(), 401);
+    let rotated_path = rotated.path.trim_start_matches('/');
+    assert_eq!(anonymous.get_raw(rotated_path).await.status(), 200);
+
+    assert_eq!(member.revoke_calendar_feed(realm.id.0).await.status(), 204);
+    assert_eq!(anonymous.get_raw(rotated_path).await.status(), 401);
+    assert_eq!(member.revoke_calendar_feed(realm.id.0).await.status(), 404);
+
+    // Leaving the realm revokes the feed.
+    let created = member.create_calendar_feed(realm.id.0).await;
+    assert_eq!(ctx.client.kick_member(realm.id.0, member_id).await.status(), 204);
+    assert_eq!(anonymous.get_raw(created.path.trim_start_matches('/')).await.status(), 401);
+});
@@ -0,0 +1,68 @@
+use crate::client::{unique_email, TestClient};
+use crate::{test_with_context, test_with_realm};
+use nebula_server::data::permissions::RealmPermissions;
+use nebula_server::web::routing::realms::invites::CreateInviteRequest;
+
+fn link_invite(max_uses: Option<i32>) -> CreateInviteRequest {
+    CreateInviteRequest {
+        permissions: RealmPermissions::new(0b0001),
+        max_uses,
+        expires_at: None,
+        user_id: None,
+        email: None,
+    }
+}
+
+test_with_realm!(test_invite_link, |ctx, realm| {
+    let invite = ctx.client.create_invite(realm.id.0, &link_invite(Some(1))).await.invite;
+    assert_eq!(invite.max_uses, Some(1));
+
+    let guest = TestClient::with_token(&TestClient::signup().await.token);
+    assert_eq!(guest.get_raw(&format!("api/realms/{}", realm.id.0)).await.status(), 403);
+
+    assert_eq!(guest.accept_invite(&invite.code).await.status(), 200);
+    assert_eq!(guest.get_realm(realm.id.0).await.realm.id, realm.id);
+    assert_eq!(guest.accept_invite(&invite.code).await.status(), 410);
+
+    // The invite is used up, so nobody else can join with it.
+    let late = TestClient::with_token(&TestClient::signup().await.token);
+    assert_eq!(late.accept_invite(&invite.code).await.status(), 410);
+    assert!(ctx.client.list_invites(realm.id.0).await.invites.is_empty());
+});
+
+test_with_realm!(test_invite_revocation_and_permissions, |ctx, realm| {
+    let invite = ctx.client.create_invite(realm.id.0, &link_invite(None)).await.invite;
+    let response = ctx.client.revoke_invite(realm.id.0, invite.id.0).await;
+    assert_eq!(response.status(), 204);
+
+    let guest = TestClient::with_token(&TestClient::signup().await.token);
+    assert_eq!(guest.accept_invite(&invite.code).await.status(), 410);
+
+    // Members joined through an invite only hold the preset permissions.
+    let invite = ctx.client.create_invite(realm.id.0, &link_invite(None)).await.invite;
+    assert_eq!(guest.accept_invite(&invite.code).await.status(), 200);
+    let response = guest.post_raw(&format!("api/realms/{}/invites", realm.id.0), &link_invite(None)).await;
+    assert_eq!(response.status(), 403);
+});
+
+test_with_context!(test_direct_invites, |ctx| {
+    let realm = ctx.create_test_realm().await;
+    let guest_auth = TestClient::signup_as(&unique_email()).await;
+    let guest = TestClient::with_token(&guest_auth.token);
+
+    let invite = ctx.client.create_invite(realm.id.0, &CreateInviteRequest {
+        user_id: Some(guest_auth.user.id),
+        ..link_invite(None)
+    }).await.invite;
+
+    let stranger = TestClient::with_token(&TestClient::signup().await.token);
+    assert_eq!(stranger.accept_invite(&invite.code).await.status(), 404);
+
+    let pending = guest.list_my_invites().await.invites;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].realm.id, realm.id);
+
+    assert_eq!(guest.decline_invite(&invite.code).await.status(), 204);
+    assert!(guest.list_my_invites().await.invites.is_empty());
+    assert_eq!(guest.accept_invite(&invite.code).await.status(), 404);
+});
@@ -0,0 +1,41 @@
+use crate::client::{latest_mail_token, unique_email, TestClient};
+use crate::test_with_context;
+
+test_with_context!(test_password_reset, |ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+
+    let response = ctx.client.forgot_password(&email).await;
+    assert_eq!(response.status(), 204);
+    let response = ctx.client.forgot_password(&unique_email()).await;
+    assert_eq!(response.status(), 204);
+
+    let token = latest_mail_token(&email, "password");
+    let response = ctx.client.reset_password(&token, "new-password").await;
+    assert_eq!(response.status(), 204);
+    let reused = ctx.client.reset_password(&token, "other-password").await;
+    assert_eq!(reused.status(), 400);
+
+    let revoked = TestClient::with_token(&auth.token).get_raw("api/users/@me/status").await;
+    assert_eq!(revoked.status(), 401);
+    assert_eq!(TestClient::login_as(&email, "password").await.status(), 401);
+    assert_eq!(TestClient::login_as(&email, "new-password").await.status(), 200);
+});
+
+test_with_context!(test_email_verification, |ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+    let client = TestClient::with_token(&auth.token);
+    assert!(!client.get_current_status().await.email_verified);
+
+    let response = client.request_verification().await;
+    assert_eq!(response.status(), 204);
+
+    let token = latest_mail_token(&email, "Verify");
+    let response = ctx.client.verify_email(&token).await;
+    assert_eq!(response.status(), 204);
+    assert!(client.get_current_status().await.email_verified);
+
+    let response = client.request_verification().await;
+    assert_eq!(response.status(), 409);
+});
@@ -0,0 +1,61 @@
+use crate::client::TestClient;
+use crate::test_with_realm;
+use nebula_server::data::permissions::RealmPermissions;
+use nebula_server::web::routing::auth::AuthResponse;
+use nebula_server::web::routing::dto::RealmDto;
+use nebula_server::web::routing::realms::invites::CreateInviteRequest;
+
+const MANAGE_EVENTS: i16 = 0b0001;
+const INVITE_MEMBERS: i16 = 0b0100;
+const MANAGE_MEMBERS: i16 = 0b1000;
+
+pub async fn join(owner: &TestClient, realm: &RealmDto) -> (AuthResponse, TestClient) {
+    let invite = owner.create_invite(realm.id.0, &CreateInviteRequest {
+        permissions: RealmPermissions::new(0),
+        max_uses: None,
+        expires_at: None,
+        user_id: None,
+        email: None,
+    }).await.invite;
+    let auth = TestClient::signup().await;
+    let client = TestClient::with_token(&auth.token);
+    assert_eq!(client.accept_invite(&invite.code).await.status(), 200);
+    (auth, client)
+}
+
+test_with_realm!(test_member_management, |ctx, realm| {
+    let shared = ctx.create_realm("Members Realm", None).await;
+    let owner_id = shared.owner_id.0;
+    let (moderator_auth, moderator) = join(&ctx.client, &shared).await;
+    let (member_auth, member) = join(&ctx.client, &shared).await;
+    let moderator_id = moderator_auth.user.id.0;
+    let member_id = member_auth.user.id.0;
+
+    let members = member.list_members(shared.id.0).await.members;
+    assert_eq!(members.len(), 3);
+    assert!(members.iter().any(|m| m.user.id.0 == member_id && m.permissions == 0));
+
+    // Plain members can't manage anyone.
+    assert_eq!(member.update_member(shared.id.0, moderator_id, MANAGE_EVENTS).await.status(), 403);
+
+    let response = ctx.client.update_member(shared.id.0, moderator_id, MANAGE_MEMBERS | MANAGE_EVENTS).await;
+    assert_eq!(response.status(), 200);
+
+    // The moderator can only pass on bits they hold, and never touch the owner.
+    assert_eq!(moderator.update_member(shared.id.0, member_id, INVITE_MEMBERS).await.status(), 403);
+    assert_eq!(moderator.update_member(shared.id.0, member_id, MANAGE_EVENTS).await.status(), 200);
+    assert_eq!(moderator.update_member(shared.id.0, owner_id, 0).await.status(), 403);
+    assert_eq!(moderator.kick_member(shared.id.0, owner_id).await.status(), 403);
+
+    // Nor kick someone who holds more than they do.
+    assert_eq!(ctx.client.update_member(shared.id.0, member_id, INVITE_MEMBERS | MANAGE_EVENTS).await.status(), 200);
+    assert_eq!(moderator.kick_member(shared.id.0, member_id).await.status(), 403);
+    assert_eq!(ctx.client.update_member(shared.id.0, member_id, MANAGE_EVENTS).await.status(), 200);
+
+    assert_eq!(moderator.kick_member(shared.id.0, member_id).await.status(), 204);
+    assert_eq!(member.get_raw(&format!("api/realms/{}", shared.id.0)).await.status(), 403);
+
+    assert_eq!(moderator.leave_realm(shared.id.0).await.status(), 204);
+    assert_eq!(ctx.client.leave_realm(shared.id.0).await.status(), 409);
+    assert_eq!(ctx.client.list_members(realm.id.0).await.members.len(), 1);
+});
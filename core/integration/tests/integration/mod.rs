@@ -8,6 +8,25 @@ pub mod realm;
 pub mod event;
 pub mod task;
 pub mod schedule;
+pub mod token;
+pub mod session;
+pub mod mail;
+pub mod totp;
+pub mod oidc;
+pub mod access_token;
+pub mod account;
+pub mod export;
+pub mod rate_limit;
+pub mod invite;
+pub mod member;
+pub mod role;
+pub mod audit;
+pub mod permission;
+pub mod share;
+pub mod archive;
+pub mod nested;
+pub mod discovery;
+pub mod feed;
 
 static INIT: Once = Once::new();
 
@@ -0,0 +1,91 @@
+use crate::client::TestClient;
+use crate::integration::member::join;
+use crate::test_with_realm;
+use chrono::{DateTime, Utc};
+use nebula_server::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use nebula_server::data::snowflake::Snowflake;
+use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::web::routing::realms::calendar::occurrences::OccurrenceQuery;
+use nebula_server::web::routing::realms::settings::UpdateRealmRequest;
+use nebula_server::web::routing::realms::RealmObject;
+
+fn event(name: &str) -> CreateEventRequest {
+    CreateEventRequest {
+        name: name.to_string(),
+        description: None,
+        location: None,
+        start_time: DateTime::parse_from_rfc3339("2024-06-01T10:00:00Z").unwrap().with_timezone(&Utc),
+        end_time: None,
+        recurrence: None,
+        timezone: None
+    }
+}
+
+fn june() -> OccurrenceQuery {
+    OccurrenceQuery {
+        start: DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z").unwrap().with_timezone(&Utc),
+        end: DateTime::parse_from_rfc3339("2024-07-01T00:00:00Z").unwrap().with_timezone(&Utc)
+    }
+}
+
+fn move_to(parent_id: Option<u64>) -> UpdateRealmRequest {
+    UpdateRealmRequest { parent_id: Some(parent_id.map(Snowflake)), ..Default::default() }
+}
+
+test_with_realm!(test_nested_realms, |ctx, company| {
+    let response = ctx.client.create_nested_realm("Engineering", company.id.0).await;
+    assert_eq!(response.status(), 200);
+    let department = response.json::<RealmObject>().await.expect("Failed to parse realm").realm;
+    assert_eq!(department.parent_id, Some(company.id));
+
+    // Company members reach the department without joining it.
+    let (member_auth, member) = join(&ctx.client, &company).await;
+    let member_id = member_auth.user.id.0;
+    assert_eq!(member.get_realm(department.id.0).await.realm.id, department.id);
+    let inherited = member.get_my_permissions(department.id.0).await;
+    assert_eq!(inherited.inherited_from, Some(company.id));
+    assert_eq!(inherited.bits, 0);
+    assert_eq!(member.leave_realm(department.id.0).await.status(), 409);
+    assert_eq!(member.get_current_status().await.realms.len(), 2);
+
+    let outsider = TestClient::signup().await;
+    let outsider = TestClient::with_token(&outsider.token);
+    assert_eq!(outsider.get_raw(&format!("api/realms/{}", department.id.0)).await.status(), 403);
+    assert_eq!(outsider.create_nested_realm("Sales", company.id.0).await.status(), 404);
+    assert_eq!(member.create_nested_realm("Sales", company.id.0).await.status(), 403);
+
+    // The department can override what the member inherits.
+    let view_calendar = RealmPermissions::from_slice(&[RealmPermission::ViewCalendar]).bits();
+    let (moderator_auth, moderator) = join(&ctx.client, &company).await;
+    let manage_members = RealmPermissions::from_slice(&[RealmPermission::ManageMembers]).bits();
+    assert_eq!(ctx.client.update_member(company.id.0, moderator_auth.user.id.0, manage_members).await.status(), 200);
+    // A refused override leaves the member inheriting.
+    assert_eq!(moderator.update_member(department.id.0, member_id, view_calendar).await.status(), 403);
+    assert_eq!(member.get_my_permissions(department.id.0).await.inherited_from, Some(company.id));
+    assert_eq!(ctx.client.update_member(department.id.0, member_id, view_calendar).await.status(), 200);
+    let overridden = member.get_my_permissions(department.id.0).await;
+    assert_eq!(overridden.inherited_from, None);
+    assert_eq!(overridden.bits, view_calendar);
+
+    ctx.client.create_realm_event(company.id.0, &event("All hands")).await;
+    ctx.client.create_realm_event(department.id.0, &event("Standup")).await;
+    let combined = ctx.client.get_combined_schedule(company.id.0, &june()).await;
+    assert_eq!(combined.events.len(), 2);
+    assert_eq!(combined.occurrences.len(), 2);
+    let names: Vec<&str> = combined.occurrences.iter()
+        .map(|occurrence| combined.events[occurrence.event_index].name.as_str())
+        .collect();
+    assert!(names.contains(&"All hands") && names.contains(&"Standup"));
+
+    let path = format!("api/realms/{}/calendar/schedule/combined?start=2024-06-01T00:00:00Z&end=2024-07-01T00:00:00Z", company.id.0);
+    assert_eq!(member.get_raw(&path).await.status(), 403);
+    let department_schedule = member.get_combined_schedule(department.id.0, &june()).await;
+    assert_eq!(department_schedule.events.len(), 1);
+
+    // Realms can't end up nested inside themselves.
+    assert_eq!(ctx.client.update_realm(company.id.0, &move_to(Some(department.id.0))).await.status(), 400);
+    assert_eq!(ctx.client.update_realm(department.id.0, &move_to(Some(department.id.0))).await.status(), 400);
+    assert_eq!(ctx.client.update_realm(department.id.0, &move_to(None)).await.status(), 200);
+    assert!(ctx.client.get_realm(department.id.0).await.realm.parent_id.is_none());
+    assert_eq!(member.get_raw(&format!("api/realms/{}/permissions/@me", department.id.0)).await.status(), 200);
+});
@@ -0,0 +1,72 @@
+use crate::client::{unique_email, TestClient};
+use crate::mock_idp;
+use crate::test_with_context;
+use nebula_server::web::routing::auth::AuthResponse;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+
+/// Signs in at the mock provider and returns the code and state it redirects
+/// back with.
+async fn authorize_at_idp(authorization_url: &str, email: &str, email_verified: bool) -> (String, String) {
+    let mut url = Url::parse(authorization_url).unwrap();
+    url.query_pairs_mut()
+        .append_pair("login_hint", email)
+        .append_pair("email_verified", &email_verified.to_string());
+
+    let client = reqwest::Client::builder().redirect(Policy::none()).build().unwrap();
+    let response = client.get(url).send().await.unwrap();
+    assert_eq!(response.status(), 302);
+    let location = response.headers()["location"].to_str().unwrap();
+    let location = Url::parse(location).unwrap();
+    let param = |name: &str| location.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.to_string())
+        .unwrap();
+    (param("code"), param("state"))
+}
+
+test_with_context!(test_oidc_signup_and_login, |ctx| {
+    mock_idp::start();
+    let email = unique_email();
+
+    let authorization = ctx.client.oidc_authorize("mock").await;
+    assert!(authorization.authorization_url.contains("code_challenge_method=S256"));
+    let (code, state) = authorize_at_idp(&authorization.authorization_url, &email, true).await;
+    let response = ctx.client.oidc_callback("mock", &code, &state).await;
+    assert_eq!(response.status(), 200);
+    let first: AuthResponse = response.json().await.unwrap();
+
+    let replayed = ctx.client.oidc_callback("mock", &code, &state).await;
+    assert_eq!(replayed.status(), 400);
+
+    let authorization = ctx.client.oidc_authorize("mock").await;
+    let (code, state) = authorize_at_idp(&authorization.authorization_url, &email, true).await;
+    let second: AuthResponse = ctx.client.oidc_callback("mock", &code, &state).await.json().await.unwrap();
+    assert_eq!(second.user.id, first.user.id);
+
+    let status = TestClient::with_token(&first.token).get_current_status().await;
+    assert!(status.email_verified);
+    let password_login = TestClient::login_as(&email, "password").await;
+    assert_eq!(password_login.status(), 401);
+});
+
+test_with_context!(test_oidc_linking_existing_account, |ctx| {
+    mock_idp::start();
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+
+    let authorization = ctx.client.oidc_authorize("mock").await;
+    let (code, state) = authorize_at_idp(&authorization.authorization_url, &email, true).await;
+    let response = ctx.client.oidc_callback("mock", &code, &state).await;
+    assert_eq!(response.status(), 409);
+
+    let client = TestClient::with_token(&auth.token);
+    let authorization = client.link_identity("mock").await;
+    let (code, state) = authorize_at_idp(&authorization.authorization_url, &email, true).await;
+    let linked: AuthResponse = ctx.client.oidc_callback("mock", &code, &state).await.json().await.unwrap();
+    assert_eq!(linked.user.id, auth.user.id);
+
+    let identities = client.list_identities().await.identities;
+    assert_eq!(identities.len(), 1);
+    assert_eq!(identities[0].provider, "mock");
+});
@@ -0,0 +1,90 @@
+use crate::client::TestClient;
+use crate::test_with_realm;
+use chrono::{DateTime, Utc};
+use nebula_server::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::web::routing::realms::calendar::RealmEventObject;
+use nebula_server::web::routing::realms::task::TaskCompletionRequest;
+
+fn event(name: &str) -> CreateEventRequest {
+    CreateEventRequest {
+        name: name.to_string(),
+        description: None,
+        location: None,
+        start_time: DateTime::parse_from_rfc3339("2024-06-01T10:00:00Z").unwrap().with_timezone(&Utc),
+        end_time: None,
+        recurrence: None,
+        timezone: None
+    }
+}
+
+/// Joins through an invite link that leaves the permissions to the server's default.
+async fn join_with_defaults(owner: &TestClient, realm_id: u64) -> (u64, TestClient) {
+    let response = owner.post_raw(&format!("api/realms/{}/invites", realm_id), &serde_json::json!({})).await;
+    let invite: serde_json::Value = response.json().await.expect("Failed to parse invite response");
+    let code = invite["invite"]["code"].as_str().expect("Invite without a code");
+
+    let auth = TestClient::signup().await;
+    let client = TestClient::with_token(&auth.token);
+    assert_eq!(client.accept_invite(code).await.status(), 200);
+    (auth.user.id.0, client)
+}
+
+test_with_realm!(test_effective_permissions_by_name, |ctx, realm| {
+    let owner = ctx.client.get_my_permissions(realm.id.0).await;
+    assert!(owner.owner);
+    assert_eq!(owner.permissions.to_vec(), RealmPermissions::PERMISSIONS);
+
+    let (member_id, member) = join_with_defaults(&ctx.client, realm.id.0).await;
+    let response = member.get_raw(&format!("api/realms/{}/permissions/@me", realm.id.0)).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse permissions response");
+    assert_eq!(body["permissions"], serde_json::json!(["view_calendar", "view_tasks"]));
+    assert_eq!(body["owner"], false);
+
+    // Names and raw bits are both accepted.
+    let response = ctx.client.send_raw(
+        reqwest::Method::PATCH,
+        &format!("api/realms/{}/members/{}", realm.id.0, member_id),
+        &serde_json::json!({ "permissions": ["view_calendar", "create_events", "complete_tasks"] })
+    ).await;
+    assert_eq!(response.status(), 200);
+    let permissions = member.get_my_permissions(realm.id.0).await.permissions;
+    assert!(permissions.contains(RealmPermission::CreateEvents));
+    assert!(!permissions.contains(RealmPermission::ViewTasks));
+
+    let response = ctx.client.send_raw(
+        reqwest::Method::PATCH,
+        &format!("api/realms/{}/members/{}", realm.id.0, member_id),
+        &serde_json::json!({ "permissions": ["not_a_permission"] })
+    ).await;
+    assert_eq!(response.status(), 422);
+});
+
+test_with_realm!(test_granular_permissions, |ctx, realm| {
+    let (member_id, member) = join_with_defaults(&ctx.client, realm.id.0).await;
+    let events_path = format!("api/realms/{}/calendar/events", realm.id.0);
+    let schedule_path = format!("api/realms/{}/calendar/schedule?start=2024-01-01T00:00:00Z&end=2025-01-01T00:00:00Z", realm.id.0);
+    let completion = TaskCompletionRequest { completed: true };
+    let completion_path = format!("api/realms/{}/tasks/{}/completion", realm.id.0, 1);
+
+    assert_eq!(member.get_raw(&schedule_path).await.status(), 200);
+    assert_eq!(member.post_raw(&events_path, &event("Not allowed")).await.status(), 403);
+    assert_eq!(member.send_raw(reqwest::Method::PUT, &completion_path, &completion).await.status(), 403);
+
+    let allowed = RealmPermissions::MEMBER_DEFAULT
+        .plus(&RealmPermissions::from_slice(&[RealmPermission::CreateEvents, RealmPermission::CompleteTasks]));
+    assert_eq!(ctx.client.update_member(realm.id.0, member_id, allowed.bits()).await.status(), 200);
+    assert_eq!(member.send_raw(reqwest::Method::PUT, &completion_path, &completion).await.status(), 404);
+
+    // Creating events only gives control over one's own.
+    let response = member.post_raw(&events_path, &event("Own event")).await;
+    assert_eq!(response.status(), 200);
+    let own: RealmEventObject = response.json().await.expect("Failed to parse event response");
+    let other = ctx.client.create_realm_event(realm.id.0, &event("Owner event")).await;
+    assert_eq!(member.delete_raw(&format!("{}/{}", events_path, other.id.0)).await.status(), 403);
+    assert_eq!(member.delete_raw(&format!("{}/{}", events_path, own.event.id.0)).await.status(), 204);
+
+    let hidden = RealmPermissions::from_slice(&[RealmPermission::ViewCalendar]);
+    assert_eq!(ctx.client.deny_permissions(realm.id.0, member_id, hidden.bits()).await.member.effective_permissions & hidden.bits(), 0);
+    assert_eq!(member.get_raw(&schedule_path).await.status(), 403);
+});
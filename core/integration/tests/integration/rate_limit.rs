@@ -0,0 +1,24 @@
+use crate::client::{unique_email, TestClient};
+use crate::test_with_context;
+
+test_with_context!(test_login_lockout, |_ctx| {
+    let email = unique_email();
+    TestClient::signup_as(&email).await;
+
+    for _ in 0..5 {
+        let response = TestClient::login_as(&email, "wrong-password").await;
+        assert_eq!(response.status(), 401);
+    }
+
+    let locked = TestClient::login_as(&email, "password").await;
+    assert_eq!(locked.status(), 429);
+    let retry_after: u64 = locked.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!(retry_after > 0 && retry_after <= 30);
+    let body: serde_json::Value = locked.json().await.unwrap();
+    assert_eq!(body["status"], 429);
+
+    // Other accounts aren't affected by the lock.
+    let other = unique_email();
+    TestClient::signup_as(&other).await;
+    assert_eq!(TestClient::login_as(&other, "password").await.status(), 200);
+});
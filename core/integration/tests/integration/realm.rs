@@ -1,4 +1,8 @@
+use crate::client::TestClient;
+use crate::integration::member::join;
 use crate::{test_with_context, test_with_realm};
+use nebula_server::web::routing::realms::settings::UpdateRealmRequest;
+use nebula_server::web::routing::realms::RealmObject;
 
 test_with_context!(test_realm_creation, |ctx| {
     let realm = ctx.create_test_realm().await;
@@ -17,3 +21,50 @@ test_with_realm!(test_realm_retrieval, |ctx, realm| {
     assert_eq!(fetched_realm.realm.name, realm.name);
     assert_eq!(fetched_realm.realm.description, realm.description);
 });
+
+test_with_realm!(test_realm_update_and_deletion, |ctx, realm| {
+    let other = ctx.create_realm("Other Realm", None).await;
+
+    let rename = UpdateRealmRequest { name: Some("Other Realm".to_string()), ..Default::default() };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &rename).await.status(), 409);
+
+    let update = UpdateRealmRequest { name: Some("Renamed Realm".to_string()), description: Some(None), ..Default::default() };
+    let response = ctx.client.update_realm(realm.id.0, &update).await;
+    assert_eq!(response.status(), 200);
+    let updated: RealmObject = response.json().await.expect("Failed to parse realm response");
+    assert_eq!(updated.realm.name, "Renamed Realm");
+    assert_eq!(updated.realm.description, None);
+
+    let (_, member) = join(&ctx.client, &other).await;
+    assert_eq!(member.update_realm(other.id.0, &update).await.status(), 403);
+    assert_eq!(member.delete_realm(other.id.0, "Other Realm").await.status(), 403);
+
+    assert_eq!(ctx.client.delete_realm(other.id.0, "Wrong Name").await.status(), 400);
+    assert_eq!(ctx.client.delete_realm(other.id.0, "Other Realm").await.status(), 204);
+    assert_eq!(ctx.client.get_raw(&format!("api/realms/{}", other.id.0)).await.status(), 404);
+});
+
+test_with_realm!(test_realm_ownership_transfer, |ctx, realm| {
+    let (member_auth, member) = join(&ctx.client, &realm).await;
+    let member_id = member_auth.user.id.0;
+    let outsider = TestClient::signup().await;
+
+    assert_eq!(member.propose_transfer(realm.id.0, member_id).await.status(), 403);
+    assert_eq!(ctx.client.propose_transfer(realm.id.0, outsider.user.id.0).await.status(), 400);
+    assert_eq!(member.accept_transfer(realm.id.0).await.status(), 403);
+
+    assert_eq!(ctx.client.propose_transfer(realm.id.0, member_id).await.status(), 200);
+    assert_eq!(member.decline_transfer(realm.id.0).await.status(), 200);
+    assert_eq!(member.accept_transfer(realm.id.0).await.status(), 403);
+
+    assert_eq!(ctx.client.propose_transfer(realm.id.0, member_id).await.status(), 200);
+    let response = member.accept_transfer(realm.id.0).await;
+    assert_eq!(response.status(), 200);
+    let transferred: RealmObject = response.json().await.expect("Failed to parse realm response");
+    assert_eq!(transferred.realm.owner_id.0, member_id);
+    assert_eq!(transferred.realm.pending_owner_id, None);
+
+    // The previous owner stays on as a regular member.
+    assert_eq!(ctx.client.delete_realm(realm.id.0, &realm.name).await.status(), 403);
+    assert_eq!(ctx.client.leave_realm(realm.id.0).await.status(), 204);
+});
@@ -0,0 +1,55 @@
+use crate::client::TestClient;
+use crate::test_with_realm;
+use chrono::{DateTime, Utc};
+use nebula_server::data::permissions::RealmPermissions;
+use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::web::routing::realms::invites::CreateInviteRequest;
+
+const MANAGE_EVENTS: i16 = 0b0001;
+const MANAGE_TASKS: i16 = 0b0010;
+
+fn event() -> CreateEventRequest {
+    CreateEventRequest {
+        name: "Planning".to_string(),
+        description: None,
+        location: None,
+        start_time: DateTime::parse_from_rfc3339("2024-06-01T10:00:00Z").unwrap().with_timezone(&Utc),
+        end_time: None,
+        recurrence: None,
+        timezone: None
+    }
+}
+
+test_with_realm!(test_roles_grant_effective_permissions, |ctx, realm| {
+    let invite = ctx.client.create_invite(realm.id.0, &CreateInviteRequest {
+        permissions: RealmPermissions::new(0),
+        max_uses: None,
+        expires_at: None,
+        user_id: None,
+        email: None,
+    }).await.invite;
+    let auth = TestClient::signup().await;
+    let member = TestClient::with_token(&auth.token);
+    assert_eq!(member.accept_invite(&invite.code).await.status(), 200);
+
+    let events_path = format!("api/realms/{}/calendar/events", realm.id.0);
+    assert_eq!(member.post_raw(&events_path, &event()).await.status(), 403);
+
+    let organizer = ctx.client.create_role(realm.id.0, "Organizer", MANAGE_EVENTS).await.role;
+    let planner = ctx.client.create_role(realm.id.0, "Planner", MANAGE_TASKS).await.role;
+    assert_eq!(planner.position, organizer.position + 1);
+
+    assert_eq!(ctx.client.assign_role(realm.id.0, auth.user.id.0, organizer.id.0).await.status(), 200);
+    assert_eq!(ctx.client.assign_role(realm.id.0, auth.user.id.0, planner.id.0).await.status(), 200);
+    assert_eq!(member.post_raw(&events_path, &event()).await.status(), 200);
+
+    let members = ctx.client.list_members(realm.id.0).await.members;
+    let listed = members.iter().find(|m| m.user.id == auth.user.id).unwrap();
+    assert_eq!(listed.roles.len(), 2);
+    assert_eq!(listed.effective_permissions, MANAGE_EVENTS | MANAGE_TASKS);
+
+    // A per-member denial wins over what the roles grant.
+    let updated = ctx.client.deny_permissions(realm.id.0, auth.user.id.0, MANAGE_EVENTS).await.member;
+    assert_eq!(updated.effective_permissions, MANAGE_TASKS);
+    assert_eq!(member.post_raw(&events_path, &event()).await.status(), 403);
+});
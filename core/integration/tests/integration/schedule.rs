@@ -1,8 +1,10 @@
 use chrono::{DateTime, Utc, Weekday};
-use rrule::{Frequency, NWeekday, RRule};
-use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use rrule::{Frequency, NWeekday, RRule, Unvalidated};
+use nebula_server::web::routing::realms::calendar::events::{CreateEventRequest, EditScope, UpdateEventRequest};
+use nebula_server::web::routing::realms::calendar::RealmEventsObject;
 use nebula_server::web::routing::realms::calendar::occurrences::OccurrenceQuery;
 use nebula_server::web::routing::realms::task::CreateTaskRequest;
+use nebula_server::web::routing::realms::settings::UpdateRealmRequest;
 use crate::test_with_realm;
 
 test_with_realm!(test_schedule_retrieval, |ctx, realm| {
@@ -19,7 +21,8 @@ test_with_realm!(test_schedule_retrieval, |ctx, realm| {
                     NWeekday::Every(Weekday::Wed),
                     NWeekday::Every(Weekday::Fri),
                 ])
-        )
+        ),
+        timezone: None
     };
     ctx.client.create_realm_event(realm.id.0, &event_payload).await;
 
@@ -47,3 +50,160 @@ test_with_realm!(test_schedule_retrieval, |ctx, realm| {
     assert_eq!(schedule.tasks[0].title, "Finish Integration Tests");
     assert_eq!(schedule.occurrences.len(), 12);
 });
+
+test_with_realm!(test_schedule_timezones, |ctx, realm| {
+    let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+    let berlin = UpdateRealmRequest { timezone: Some("Europe/Berlin".to_string()), ..Default::default() };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &berlin).await.status(), 200);
+    let unknown = UpdateRealmRequest { timezone: Some("Mars/Olympus_Mons".to_string()), ..Default::default() };
+    assert_eq!(ctx.client.update_realm(realm.id.0, &unknown).await.status(), 400);
+
+    // 9:00 in Berlin, which moves from UTC+1 to UTC+2 on March 31st.
+    let payload = CreateEventRequest {
+        name: "Planning".to_string(),
+        description: None,
+        location: None,
+        start_time: at("2024-03-18T08:00:00Z"),
+        end_time: Some(at("2024-03-18T09:00:00Z")),
+        recurrence: Some(RRule::new(Frequency::Weekly).count(3)),
+        timezone: None
+    };
+    let event = ctx.client.create_realm_event(realm.id.0, &payload).await;
+    assert_eq!(event.timezone, "Europe/Berlin");
+
+    let query = OccurrenceQuery { start: at("2024-03-01T00:00:00Z"), end: at("2024-05-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &query).await;
+    assert_eq!(schedule.timezone, "Europe/Berlin");
+    let starts: Vec<_> = schedule.occurrences.iter().map(|occurrence| occurrence.occurrence_start).collect();
+    assert_eq!(starts, vec![
+        at("2024-03-18T08:00:00Z"),
+        at("2024-03-25T08:00:00Z"),
+        at("2024-04-01T07:00:00Z"),
+    ]);
+
+    let invalid = CreateEventRequest { timezone: Some("Europe/Atlantis".to_string()), ..payload };
+    let path = format!("api/realms/{}/calendar/events", realm.id.0);
+    assert_eq!(ctx.client.post_raw(&path, &invalid).await.status(), 400);
+});
+
+test_with_realm!(test_schedule_window, |ctx, realm| {
+    let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+    let event = |name: &str, start: &str, end: &str, recurrence: Option<RRule<Unvalidated>>| CreateEventRequest {
+        name: name.to_string(),
+        description: None,
+        location: None,
+        start_time: at(start),
+        end_time: Some(at(end)),
+        recurrence,
+        timezone: None
+    };
+    let weekly = RRule::new(Frequency::Weekly);
+    let events = [
+        event("Weekly sync", "2023-01-02T09:00:00Z", "2023-01-02T10:00:00Z", Some(weekly.clone())),
+        event("Night shift", "2024-05-31T22:00:00Z", "2024-06-01T02:00:00Z", None),
+        event("Late call", "2024-05-31T23:00:00Z", "2024-06-01T00:00:00Z", None),
+        event("Old course", "2023-01-03T09:00:00Z", "2023-01-03T10:00:00Z", Some(weekly.count(4))),
+    ];
+    for payload in &events {
+        ctx.client.create_realm_event(realm.id.0, payload).await;
+    }
+
+    let june = OccurrenceQuery { start: at("2024-06-01T00:00:00Z"), end: at("2024-07-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &june).await;
+    assert!(!schedule.truncated);
+    let names: Vec<&str> = schedule.events.iter().map(|event| event.name.as_str()).collect();
+    assert_eq!(names, vec!["Weekly sync", "Night shift"]);
+    let mut starts: Vec<_> = schedule.occurrences.iter().map(|occurrence| occurrence.occurrence_start).collect();
+    starts.sort();
+    assert_eq!(starts, vec![
+        at("2024-05-31T22:00:00Z"),
+        at("2024-06-03T09:00:00Z"),
+        at("2024-06-10T09:00:00Z"),
+        at("2024-06-17T09:00:00Z"),
+        at("2024-06-24T09:00:00Z"),
+    ]);
+
+    // A series with exactly as many occurrences as a schedule lists isn't cut.
+    let daily = event("Daily log", "2030-01-01T08:00:00Z", "2030-01-01T08:10:00Z", Some(RRule::new(Frequency::Daily).count(1000)));
+    ctx.client.create_realm_event(realm.id.0, &daily).await;
+    let thirties = OccurrenceQuery { start: at("2030-01-01T00:00:00Z"), end: at("2033-01-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &thirties).await;
+    assert!(!schedule.truncated);
+    let logs = schedule.occurrences.iter().filter(|occurrence| schedule.events[occurrence.event_index].name == "Daily log");
+    assert_eq!(logs.count(), 1000);
+
+    // Twenty years of a weekly series is more than one schedule lists.
+    let decades = OccurrenceQuery { start: at("2023-01-01T00:00:00Z"), end: at("2043-01-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &decades).await;
+    assert!(schedule.truncated);
+});
+
+test_with_realm!(test_schedule_event_indices, |ctx, realm| {
+    let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+    // More events than an i8 index could point at.
+    for i in 0..130 {
+        ctx.client.create_realm_event(realm.id.0, &CreateEventRequest {
+            name: format!("Event {i}"),
+            description: None,
+            location: None,
+            start_time: at("2024-06-01T09:00:00Z") + chrono::Duration::minutes(i),
+            end_time: None,
+            recurrence: None,
+            timezone: None
+        }).await;
+    }
+
+    let june = OccurrenceQuery { start: at("2024-06-01T00:00:00Z"), end: at("2024-07-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &june).await;
+    assert_eq!(schedule.events.len(), 130);
+    for occurrence in &schedule.occurrences {
+        let event = &schedule.events[occurrence.event_index];
+        assert_eq!(event.start_time, occurrence.occurrence_start);
+    }
+});
+
+test_with_realm!(test_schedule_moved_occurrences, |ctx, realm| {
+    let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+    let series = ctx.client.create_realm_event(realm.id.0, &CreateEventRequest {
+        name: "Review".to_string(),
+        description: None,
+        location: None,
+        start_time: at("2024-06-03T09:00:00Z"),
+        end_time: Some(at("2024-06-03T10:00:00Z")),
+        recurrence: Some(RRule::new(Frequency::Weekly).count(2)),
+        timezone: None
+    }).await;
+
+    // The last occurrence moves past where the rule ends.
+    let postpone = UpdateEventRequest {
+        scope: EditScope::This,
+        occurrence: Some(at("2024-06-10T09:00:00Z")),
+        start_time: Some(at("2024-07-15T09:00:00Z")),
+        ..Default::default()
+    };
+    let response = ctx.client.update_realm_event(realm.id.0, series.id.0, &postpone).await;
+    assert_eq!(response.status(), 200);
+    let override_id = response.json::<RealmEventsObject>().await.expect("Failed to parse events").overrides[0].id.0;
+
+    let july = OccurrenceQuery { start: at("2024-07-01T00:00:00Z"), end: at("2024-08-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &july).await;
+    assert_eq!(schedule.occurrences.len(), 1);
+    assert_eq!(schedule.occurrences[0].occurrence_start, at("2024-07-15T09:00:00Z"));
+    assert_eq!(schedule.occurrences[0].original_start, Some(at("2024-06-10T09:00:00Z")));
+
+    assert_eq!(ctx.client.delete_event_override(realm.id.0, series.id.0, override_id).await.status(), 200);
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &july).await;
+    assert!(schedule.occurrences.is_empty());
+
+    // The first occurrence moves to before the series starts.
+    let advance = UpdateEventRequest {
+        occurrence: Some(at("2024-06-03T09:00:00Z")),
+        start_time: Some(at("2024-05-20T09:00:00Z")),
+        ..postpone
+    };
+    assert_eq!(ctx.client.update_realm_event(realm.id.0, series.id.0, &advance).await.status(), 200);
+    let may = OccurrenceQuery { start: at("2024-05-01T00:00:00Z"), end: at("2024-06-01T00:00:00Z") };
+    let schedule = ctx.client.get_realm_schedule(realm.id.0, &may).await;
+    assert_eq!(schedule.occurrences.len(), 1);
+    assert_eq!(schedule.occurrences[0].occurrence_start, at("2024-05-20T09:00:00Z"));
+});
@@ -0,0 +1,30 @@
+use crate::client::{unique_email, TestClient};
+use crate::test_with_context;
+use nebula_server::web::routing::auth::AuthResponse;
+
+test_with_context!(test_session_listing_and_revocation, |_ctx| {
+    let email = unique_email();
+    let first = TestClient::signup_as(&email).await;
+    let second: AuthResponse = TestClient::login_as(&email, "password").await.json().await.unwrap();
+
+    let first_client = TestClient::with_token(&first.token);
+    let second_client = TestClient::with_token(&second.token);
+
+    let sessions = first_client.list_sessions().await.sessions;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions.iter().filter(|s| s.current).count(), 1);
+
+    let other = sessions.iter().find(|s| !s.current).unwrap();
+    let response = first_client.revoke_session(other.id.0).await;
+    assert_eq!(response.status(), 204);
+
+    let rejected = second_client.get_raw("api/users/@me/status").await;
+    assert_eq!(rejected.status(), 401);
+    let refresh = first_client.refresh_token(&second.refresh_token).await;
+    assert_eq!(refresh.status(), 401);
+
+    let response = first_client.logout().await;
+    assert_eq!(response.status(), 204);
+    let rejected = first_client.get_raw("api/users/@me/status").await;
+    assert_eq!(rejected.status(), 401);
+});
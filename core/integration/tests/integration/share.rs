@@ -0,0 +1,52 @@
+use crate::client::TestClient;
+use crate::integration::member::join;
+use crate::test_with_realm;
+use chrono::{DateTime, Utc};
+use nebula_server::web::routing::realms::calendar::events::CreateEventRequest;
+use nebula_server::web::routing::shared::SharedScheduleObject;
+
+const WINDOW: &str = "start=2024-06-01T00:00:00Z&end=2024-07-01T00:00:00Z";
+
+test_with_realm!(test_share_links, |ctx, realm| {
+    ctx.client.create_realm_event(realm.id.0, &CreateEventRequest {
+        name: "Club night".to_string(),
+        description: Some("Members only snacks".to_string()),
+        location: None,
+        start_time: DateTime::parse_from_rfc3339("2024-06-05T19:00:00Z").unwrap().with_timezone(&Utc),
+        end_time: None,
+        recurrence: None,
+        timezone: None
+    }).await;
+
+    let (_, member) = join(&ctx.client, &realm).await;
+    let response = member.post_raw(&format!("api/realms/{}/share-links", realm.id.0), &serde_json::json!({})).await;
+    assert_eq!(response.status(), 403);
+
+    let link = ctx.client.create_share_link(realm.id.0, false, false).await.link;
+    let detailed = ctx.client.create_share_link(realm.id.0, true, true).await.link;
+    assert_eq!(ctx.client.list_share_links(realm.id.0).await.links.len(), 2);
+
+    // No account needed to read a shared schedule.
+    let anonymous = TestClient::with_token("");
+    let response = anonymous.get_raw(&format!("api/shared/{}/schedule?{}", link.token, WINDOW)).await;
+    assert_eq!(response.status(), 200);
+    let shared: SharedScheduleObject = response.json().await.expect("Failed to parse shared schedule");
+    assert_eq!(shared.realm.name, realm.name);
+    assert_eq!(shared.realm.description, None);
+    assert_eq!(shared.schedule.events.len(), 1);
+    assert_eq!(shared.schedule.events[0].description, None);
+    assert_eq!(shared.schedule.events[0].created_by.0, 0);
+
+    let response = anonymous.get_raw(&format!("api/shared/{}/schedule?{}", detailed.token, WINDOW)).await;
+    let shared: SharedScheduleObject = response.json().await.expect("Failed to parse shared schedule");
+    assert_eq!(shared.schedule.events[0].description.as_deref(), Some("Members only snacks"));
+
+    let too_long = "start=2024-01-01T00:00:00Z&end=2026-01-01T00:00:00Z";
+    let response = anonymous.get_raw(&format!("api/shared/{}/schedule?{}", link.token, too_long)).await;
+    assert_eq!(response.status(), 400);
+
+    assert_eq!(ctx.client.revoke_share_link(realm.id.0, link.id.0).await.status(), 204);
+    let response = anonymous.get_raw(&format!("api/shared/{}/schedule?{}", link.token, WINDOW)).await;
+    assert_eq!(response.status(), 404);
+    assert_eq!(ctx.client.list_share_links(realm.id.0).await.links.len(), 1);
+});
@@ -0,0 +1,30 @@
+use crate::client::TestClient;
+use crate::test_with_context;
+use nebula_server::web::routing::auth::AuthResponse;
+
+test_with_context!(test_token_refresh_rotation, |ctx| {
+    let auth = TestClient::signup().await;
+
+    let response = ctx.client.refresh_token(&auth.refresh_token).await;
+    assert_eq!(response.status(), 200);
+    let rotated: AuthResponse = response.json().await.unwrap();
+    assert_ne!(rotated.refresh_token, auth.refresh_token);
+    assert_eq!(rotated.user.id, auth.user.id);
+
+    let status = TestClient::with_token(&rotated.token).get_current_status().await;
+    assert_eq!(status.me.id, auth.user.id);
+});
+
+test_with_context!(test_refresh_token_reuse_revokes_family, |ctx| {
+    let auth = TestClient::signup().await;
+
+    let response = ctx.client.refresh_token(&auth.refresh_token).await;
+    assert_eq!(response.status(), 200);
+    let rotated: AuthResponse = response.json().await.unwrap();
+
+    let reused = ctx.client.refresh_token(&auth.refresh_token).await;
+    assert_eq!(reused.status(), 401);
+
+    let revoked = ctx.client.refresh_token(&rotated.refresh_token).await;
+    assert_eq!(revoked.status(), 401);
+});
@@ -0,0 +1,65 @@
+use crate::client::{unique_email, TestClient};
+use crate::test_with_context;
+use nebula_server::web::routing::auth::AuthResponse;
+use nebula_server::web::routing::auth::login::TwoFactorChallenge;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+fn code_at(secret: &str, offset_secs: i64) -> String {
+    let secret = Secret::Encoded(secret.to_string()).to_bytes().unwrap();
+    let totp = TOTP::new_unchecked(Algorithm::SHA1, 6, 0, 30, secret, None, String::new());
+    totp.generate((chrono::Utc::now().timestamp() + offset_secs) as u64)
+}
+
+test_with_context!(test_totp_login_flow, |_ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+    let client = TestClient::with_token(&auth.token);
+
+    let enrollment = client.enroll_totp().await;
+    assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+    let recovery = client.confirm_totp(&code_at(&enrollment.secret, 0)).await;
+    assert_eq!(recovery.recovery_codes.len(), 10);
+    assert!(client.get_current_status().await.totp_enabled);
+
+    let response = TestClient::login_as(&email, "password").await;
+    assert_eq!(response.status(), 200);
+    let challenge: TwoFactorChallenge = response.json().await.unwrap();
+    assert!(challenge.two_factor_required);
+
+    let rejected = TestClient::login_with_code(&challenge.challenge_token, "000000x").await;
+    assert_eq!(rejected.status(), 401);
+
+    let next_code = code_at(&enrollment.secret, 30);
+    let response = TestClient::login_with_code(&challenge.challenge_token, &next_code).await;
+    assert_eq!(response.status(), 200);
+    let logged_in: AuthResponse = response.json().await.unwrap();
+    assert_eq!(logged_in.user.id, auth.user.id);
+
+    let replayed = TestClient::login_with_code(&challenge.challenge_token, &next_code).await;
+    assert_eq!(replayed.status(), 401);
+
+    let recovery_code = &recovery.recovery_codes[0];
+    let response = TestClient::login_with_code(&challenge.challenge_token, recovery_code).await;
+    assert_eq!(response.status(), 200);
+    let reused = TestClient::login_with_code(&challenge.challenge_token, recovery_code).await;
+    assert_eq!(reused.status(), 401);
+});
+
+test_with_context!(test_totp_lockout, |_ctx| {
+    let email = unique_email();
+    let auth = TestClient::signup_as(&email).await;
+    let client = TestClient::with_token(&auth.token);
+    let enrollment = client.enroll_totp().await;
+    client.confirm_totp(&code_at(&enrollment.secret, 0)).await;
+
+    let challenge: TwoFactorChallenge = TestClient::login_as(&email, "password").await.json().await.unwrap();
+    for _ in 0..5 {
+        let rejected = TestClient::login_with_code(&challenge.challenge_token, "000000x").await;
+        assert_eq!(rejected.status(), 401);
+    }
+
+    // Even the right code waits out the lock, and so does the password step.
+    let locked = TestClient::login_with_code(&challenge.challenge_token, &code_at(&enrollment.secret, 30)).await;
+    assert_eq!(locked.status(), 429);
+    assert_eq!(TestClient::login_as(&email, "password").await.status(), 429);
+});
@@ -1,2 +1,3 @@
 pub mod client;
 pub mod integration;
+pub mod mock_idp;
\ No newline at end of file
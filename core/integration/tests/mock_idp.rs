@@ -0,0 +1,163 @@
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Once;
+
+const KEY_ID: &str = "mock-key";
+const PRIVATE_KEY: &str = include_str!("fixtures/mock_idp_key.pem");
+const MODULUS: &str = "p81U3Kev_iGFaDVUFlr2UnYzS4tgzIvMEJEFmp9u9bQd-4UrreDxJ0_le_Hj_QAmZ4neF7EJUwYfCaz3b388-hxg3zX317Uhu_tJpO_1ZaxcRycwkiRPw06NjRA6ZAlczWzC9_0hicD-9fbcei_58dlxymTEEklanzbzTcGePNDGG6io7S4Nvrtv-wjXr2COXIy1AwrylgOAyrBpSGZQCngVBn61PTo91pTcEQAMPbPAb4BtNU4maVqEtA7aWiLWigd_i2AK9m3YbWUvLf63OQH3wV__7qUsgGgwwL1IpA6r66mgAFst8fFaHNPXs26bLmms1EK6bUWOL2Ef_FO8Yw";
+
+static START: Once = Once::new();
+
+/// A tiny OpenID provider that signs in whoever is named in `login_hint`.
+/// Authorization codes carry the whole request so the provider stays stateless.
+#[derive(Clone)]
+struct MockIdp {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuthorizationCode {
+    sub: String,
+    email: String,
+    email_verified: bool,
+    nonce: String,
+    code_challenge: String,
+    client_id: String,
+    redirect_uri: String,
+}
+
+#[derive(Serialize)]
+struct IdTokenClaims {
+    iss: String,
+    aud: String,
+    sub: String,
+    email: String,
+    email_verified: bool,
+    name: String,
+    nonce: String,
+    iat: i64,
+    exp: i64,
+}
+
+pub fn issuer() -> String {
+    std::env::var("OIDC_MOCK_ISSUER").expect("OIDC_MOCK_ISSUER environment variable not set")
+}
+
+/// Starts the provider on its own runtime so it outlives individual tests.
+pub fn start() {
+    START.call_once(|| {
+        let idp = MockIdp {
+            issuer: issuer(),
+            client_id: std::env::var("OIDC_MOCK_CLIENT_ID").expect("OIDC_MOCK_CLIENT_ID environment variable not set"),
+            client_secret: std::env::var("OIDC_MOCK_CLIENT_SECRET").expect("OIDC_MOCK_CLIENT_SECRET environment variable not set"),
+        };
+        let address = idp.issuer.trim_start_matches("http://").to_string();
+        let listener = std::net::TcpListener::bind(&address).expect("Failed to bind mock IdP");
+        listener.set_nonblocking(true).unwrap();
+
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async move {
+                let router = Router::new()
+                    .route("/.well-known/openid-configuration", get(discovery))
+                    .route("/jwks", get(jwks))
+                    .route("/authorize", get(authorize))
+                    .route("/token", post(token))
+                    .with_state(idp);
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                axum::serve(listener, router).await.unwrap();
+            });
+        });
+    });
+}
+
+async fn discovery(State(idp): State<MockIdp>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "issuer": idp.issuer,
+        "authorization_endpoint": format!("{}/authorize", idp.issuer),
+        "token_endpoint": format!("{}/token", idp.issuer),
+        "jwks_uri": format!("{}/jwks", idp.issuer),
+    }))
+}
+
+async fn jwks() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "keys": [{
+            "kty": "RSA",
+            "use": "sig",
+            "alg": "RS256",
+            "kid": KEY_ID,
+            "n": MODULUS,
+            "e": "AQAB",
+        }]
+    }))
+}
+
+async fn authorize(Query(params): Query<HashMap<String, String>>) -> Response {
+    let email = params["login_hint"].clone();
+    let code = AuthorizationCode {
+        sub: format!("mock|{email}"),
+        email_verified: params.get("email_verified").is_some_and(|v| v == "true"),
+        email,
+        nonce: params["nonce"].clone(),
+        code_challenge: params["code_challenge"].clone(),
+        client_id: params["client_id"].clone(),
+        redirect_uri: params["redirect_uri"].clone(),
+    };
+    let code = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&code).unwrap());
+    let location = format!("{}?code={}&state={}", params["redirect_uri"], code, params["state"]);
+    (StatusCode::FOUND, [(header::LOCATION, location)]).into_response()
+}
+
+async fn token(
+    State(idp): State<MockIdp>,
+    Form(form): Form<HashMap<String, String>>
+) -> Response {
+    let code: AuthorizationCode = match URL_SAFE_NO_PAD.decode(&form["code"]) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap(),
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(form["code_verifier"].as_bytes()));
+    let valid = challenge == code.code_challenge
+        && form["client_id"] == idp.client_id
+        && form.get("client_secret") == Some(&idp.client_secret)
+        && form["redirect_uri"] == code.redirect_uri
+        && code.client_id == idp.client_id;
+    if !valid {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let claims = IdTokenClaims {
+        iss: idp.issuer.clone(),
+        aud: idp.client_id.clone(),
+        sub: code.sub,
+        name: "Mock User".to_string(),
+        email: code.email,
+        email_verified: code.email_verified,
+        nonce: code.nonce,
+        iat: now,
+        exp: now + 300,
+    };
+    let mut header = Header::new(jsonwebtoken::Algorithm::RS256);
+    header.kid = Some(KEY_ID.to_string());
+    let key = EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap();
+    let id_token = jsonwebtoken::encode(&header, &claims, &key).unwrap();
+
+    Json(serde_json::json!({
+        "access_token": "mock-access-token",
+        "token_type": "Bearer",
+        "id_token": id_token,
+    })).into_response()
+}
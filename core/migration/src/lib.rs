@@ -5,6 +5,29 @@ pub mod m20250914_195455_create_realms;
 pub mod m20250919_202303_create_realm_members;
 pub mod m20250921_015955_create_realm_events;
 pub mod m20250926_032701_create_realm_tasks;
+pub mod m20251004_183512_create_refresh_tokens;
+pub mod m20251006_214027_create_sessions;
+pub mod m20251009_120418_create_user_tokens;
+pub mod m20251011_093127_add_user_totp;
+pub mod m20251013_151902_create_user_identities;
+pub mod m20251015_204511_create_personal_access_tokens;
+pub mod m20251017_110236_restrict_realm_owner_deletion;
+pub mod m20251019_162850_create_data_exports;
+pub mod m20251021_094517_create_realm_invites;
+pub mod m20251023_141206_create_realm_roles;
+pub mod m20251025_101532_add_realm_pending_owner;
+pub mod m20251027_083915_create_realm_audit_log;
+pub mod m20251028_190244_grant_member_view_permissions;
+pub mod m20251030_121847_create_realm_share_links;
+pub mod m20251101_173020_add_realm_archived_at;
+pub mod m20251103_092615_add_realm_parent;
+pub mod m20251105_140932_add_realm_visibility;
+pub mod m20251105_141507_create_realm_join_requests;
+pub mod m20251109_163318_create_realm_event_overrides;
+pub mod m20251111_094210_add_event_timezones;
+pub mod m20251113_081734_add_realm_event_series_end;
+pub mod m20251113_084102_fix_realm_task_timestamps;
+pub mod m20251115_102318_create_realm_calendar_feeds;
 
 pub struct Migrator;
 
@@ -15,7 +38,30 @@ impl MigratorTrait for Migrator {
              Box::new(m20250914_195455_create_realms::Migration),
              Box::new(m20250919_202303_create_realm_members::Migration),
              Box::new(m20250921_015955_create_realm_events::Migration),
-             Box::new(m20250926_032701_create_realm_tasks::Migration)
+             Box::new(m20250926_032701_create_realm_tasks::Migration),
+             Box::new(m20251004_183512_create_refresh_tokens::Migration),
+             Box::new(m20251006_214027_create_sessions::Migration),
+             Box::new(m20251009_120418_create_user_tokens::Migration),
+             Box::new(m20251011_093127_add_user_totp::Migration),
+             Box::new(m20251013_151902_create_user_identities::Migration),
+             Box::new(m20251015_204511_create_personal_access_tokens::Migration),
+             Box::new(m20251017_110236_restrict_realm_owner_deletion::Migration),
+             Box::new(m20251019_162850_create_data_exports::Migration),
+             Box::new(m20251021_094517_create_realm_invites::Migration),
+             Box::new(m20251023_141206_create_realm_roles::Migration),
+             Box::new(m20251025_101532_add_realm_pending_owner::Migration),
+             Box::new(m20251027_083915_create_realm_audit_log::Migration),
+             Box::new(m20251028_190244_grant_member_view_permissions::Migration),
+             Box::new(m20251030_121847_create_realm_share_links::Migration),
+             Box::new(m20251101_173020_add_realm_archived_at::Migration),
+             Box::new(m20251103_092615_add_realm_parent::Migration),
+             Box::new(m20251105_140932_add_realm_visibility::Migration),
+             Box::new(m20251105_141507_create_realm_join_requests::Migration),
+             Box::new(m20251109_163318_create_realm_event_overrides::Migration),
+             Box::new(m20251111_094210_add_event_timezones::Migration),
+             Box::new(m20251113_081734_add_realm_event_series_end::Migration),
+             Box::new(m20251113_084102_fix_realm_task_timestamps::Migration),
+             Box::new(m20251115_102318_create_realm_calendar_feeds::Migration)
         ]
     }
 }
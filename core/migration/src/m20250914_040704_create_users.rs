@@ -45,5 +45,10 @@ pub enum Users {
     Name,
     Email,
     PasswordHash,
+    EmailVerified,
+    TotpSecret,
+    TotpEnabled,
+    TotpRecoveryCodes,
+    TotpLastStep,
     UpdatedAt,
 }
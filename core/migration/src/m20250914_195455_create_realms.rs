@@ -48,4 +48,9 @@ pub enum Realms {
     Name,
     Description,
     OwnerId,
+    PendingOwnerId,
+    ArchivedAt,
+    ParentId,
+    Visibility,
+    Timezone,
 }
\ No newline at end of file
@@ -146,10 +146,11 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum RealmMembers {
+pub enum RealmMembers {
     Table,
     Id,
     RealmId,
     UserId,
     Permissions,
+    DeniedPermissions,
 }
\ No newline at end of file
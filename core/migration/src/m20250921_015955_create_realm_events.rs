@@ -149,7 +149,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum RealmEvents {
+pub enum RealmEvents {
     Table,
     Id,
     Name,
@@ -160,4 +160,7 @@ enum RealmEvents {
     StartTime,
     EndTime,
     Recurrence,
+    Exdates,
+    Timezone,
+    SeriesEnd,
 }
@@ -185,7 +185,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum RealmTasks {
+pub enum RealmTasks {
     Table,
     Id,
     RealmId,
@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RefreshTokens::Table)
+                    .if_not_exists()
+                    .col(big_integer(RefreshTokens::Id).primary_key())
+                    .col(big_integer(RefreshTokens::UserId).not_null())
+                    .col(big_integer(RefreshTokens::FamilyId).not_null())
+                    .col(string(RefreshTokens::TokenHash).not_null().unique_key())
+                    .col(big_integer_null(RefreshTokens::ReplacedBy))
+                    .col(
+                        timestamp_with_time_zone(RefreshTokens::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(RefreshTokens::ExpiresAt).not_null())
+                    .col(timestamp_with_time_zone_null(RefreshTokens::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_refresh_tokens_user_id")
+                            .from(RefreshTokens::Table, RefreshTokens::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_refresh_tokens_family_id")
+                    .table(RefreshTokens::Table)
+                    .col(RefreshTokens::FamilyId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_refresh_tokens_user_id")
+                    .table(RefreshTokens::Table)
+                    .col(RefreshTokens::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_refresh_tokens_user_id")
+                    .table(RefreshTokens::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_refresh_tokens_family_id")
+                    .table(RefreshTokens::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RefreshTokens::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RefreshTokens {
+    Table,
+    Id,
+    UserId,
+    FamilyId,
+    TokenHash,
+    ReplacedBy,
+    CreatedAt,
+    ExpiresAt,
+    RevokedAt,
+}
@@ -0,0 +1,97 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(boolean(Users::EmailVerified).not_null().default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserTokens::Table)
+                    .if_not_exists()
+                    .col(big_integer(UserTokens::Id).primary_key())
+                    .col(big_integer(UserTokens::UserId).not_null())
+                    .col(small_integer(UserTokens::Purpose).not_null())
+                    .col(string(UserTokens::TokenHash).not_null().unique_key())
+                    .col(
+                        timestamp_with_time_zone(UserTokens::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(UserTokens::ExpiresAt).not_null())
+                    .col(timestamp_with_time_zone_null(UserTokens::ConsumedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_tokens_user_id")
+                            .from(UserTokens::Table, UserTokens::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_tokens_user_id_purpose")
+                    .table(UserTokens::Table)
+                    .col(UserTokens::UserId)
+                    .col(UserTokens::Purpose)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_tokens_user_id_purpose")
+                    .table(UserTokens::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserTokens::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::EmailVerified)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum UserTokens {
+    Table,
+    Id,
+    UserId,
+    Purpose,
+    TokenHash,
+    CreatedAt,
+    ExpiresAt,
+    ConsumedAt,
+    Payload,
+}
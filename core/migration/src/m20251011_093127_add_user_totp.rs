@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(string_null(Users::TotpSecret))
+                    .add_column(boolean(Users::TotpEnabled).not_null().default(false))
+                    .add_column(json_binary_null(Users::TotpRecoveryCodes))
+                    .add_column(big_integer_null(Users::TotpLastStep))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::TotpSecret)
+                    .drop_column(Users::TotpEnabled)
+                    .drop_column(Users::TotpRecoveryCodes)
+                    .drop_column(Users::TotpLastStep)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,160 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .modify_column(ColumnDef::new(Users::PasswordHash).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserIdentities::Table)
+                    .if_not_exists()
+                    .col(big_integer(UserIdentities::Id).primary_key())
+                    .col(big_integer(UserIdentities::UserId).not_null())
+                    .col(string(UserIdentities::Provider).not_null())
+                    .col(string(UserIdentities::Subject).not_null())
+                    .col(string_null(UserIdentities::Email))
+                    .col(
+                        timestamp_with_time_zone(UserIdentities::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_identities_user_id")
+                            .from(UserIdentities::Table, UserIdentities::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_identities_provider_subject")
+                    .table(UserIdentities::Table)
+                    .col(UserIdentities::Provider)
+                    .col(UserIdentities::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_identities_user_id")
+                    .table(UserIdentities::Table)
+                    .col(UserIdentities::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OidcAuthRequests::Table)
+                    .if_not_exists()
+                    .col(big_integer(OidcAuthRequests::Id).primary_key())
+                    .col(string(OidcAuthRequests::Provider).not_null())
+                    .col(string(OidcAuthRequests::StateHash).not_null().unique_key())
+                    .col(string(OidcAuthRequests::CodeVerifier).not_null())
+                    .col(string(OidcAuthRequests::Nonce).not_null())
+                    .col(big_integer_null(OidcAuthRequests::LinkUserId))
+                    .col(
+                        timestamp_with_time_zone(OidcAuthRequests::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(OidcAuthRequests::ExpiresAt).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_oidc_auth_requests_link_user_id")
+                            .from(OidcAuthRequests::Table, OidcAuthRequests::LinkUserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OidcAuthRequests::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_identities_user_id")
+                    .table(UserIdentities::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_identities_provider_subject")
+                    .table(UserIdentities::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserIdentities::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .modify_column(ColumnDef::new(Users::PasswordHash).string().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserIdentities {
+    Table,
+    Id,
+    UserId,
+    Provider,
+    Subject,
+    Email,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum OidcAuthRequests {
+    Table,
+    Id,
+    Provider,
+    StateHash,
+    CodeVerifier,
+    Nonce,
+    LinkUserId,
+    CreatedAt,
+    ExpiresAt,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PersonalAccessTokens::Table)
+                    .if_not_exists()
+                    .col(big_integer(PersonalAccessTokens::Id).primary_key())
+                    .col(big_integer(PersonalAccessTokens::UserId).not_null())
+                    .col(string(PersonalAccessTokens::Name).not_null())
+                    .col(string(PersonalAccessTokens::TokenHash).not_null().unique_key())
+                    .col(integer(PersonalAccessTokens::Scopes).not_null())
+                    .col(
+                        timestamp_with_time_zone(PersonalAccessTokens::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(PersonalAccessTokens::ExpiresAt))
+                    .col(timestamp_with_time_zone_null(PersonalAccessTokens::LastUsedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_personal_access_tokens_user_id")
+                            .from(PersonalAccessTokens::Table, PersonalAccessTokens::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_personal_access_tokens_user_id")
+                    .table(PersonalAccessTokens::Table)
+                    .col(PersonalAccessTokens::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_personal_access_tokens_user_id")
+                    .table(PersonalAccessTokens::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PersonalAccessTokens::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PersonalAccessTokens {
+    Table,
+    Id,
+    UserId,
+    Name,
+    TokenHash,
+    Scopes,
+    CreatedAt,
+    ExpiresAt,
+    LastUsedAt,
+}
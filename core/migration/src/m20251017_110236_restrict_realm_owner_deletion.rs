@@ -0,0 +1,76 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+use crate::m20250914_195455_create_realms::Realms;
+use crate::m20251009_120418_create_user_tokens::UserTokens;
+
+/// Deleting a user used to cascade into every realm they owned. Owned realms now
+/// have to be transferred or deleted explicitly before the account goes away.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-realms-owner")
+                    .table(Realms::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-realms-owner")
+                    .from(Realms::Table, Realms::OwnerId)
+                    .to(Users::Table, Users::Id)
+                    .on_delete(ForeignKeyAction::Restrict)
+                    .on_update(ForeignKeyAction::NoAction)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserTokens::Table)
+                    .add_column(string_null(UserTokens::Payload))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserTokens::Table)
+                    .drop_column(UserTokens::Payload)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-realms-owner")
+                    .table(Realms::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-realms-owner")
+                    .from(Realms::Table, Realms::OwnerId)
+                    .to(Users::Table, Users::Id)
+                    .on_delete(ForeignKeyAction::Cascade)
+                    .on_update(ForeignKeyAction::NoAction)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DataExports::Table)
+                    .if_not_exists()
+                    .col(big_integer(DataExports::Id).primary_key())
+                    .col(big_integer(DataExports::UserId).not_null())
+                    .col(small_integer(DataExports::Status).not_null())
+                    .col(
+                        timestamp_with_time_zone(DataExports::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(DataExports::CompletedAt))
+                    .col(blob_null(DataExports::Archive))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_data_exports_user_id")
+                            .from(DataExports::Table, DataExports::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_data_exports_user_id")
+                    .table(DataExports::Table)
+                    .col(DataExports::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_data_exports_user_id")
+                    .table(DataExports::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(DataExports::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum DataExports {
+    Table,
+    Id,
+    UserId,
+    Status,
+    CreatedAt,
+    CompletedAt,
+    Archive,
+}
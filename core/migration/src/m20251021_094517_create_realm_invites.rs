@@ -0,0 +1,123 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+use crate::m20250914_195455_create_realms::Realms;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmInvites::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmInvites::Id).primary_key())
+                    .col(big_integer(RealmInvites::RealmId).not_null())
+                    .col(big_integer(RealmInvites::CreatedBy).not_null())
+                    .col(string(RealmInvites::Code).not_null().unique_key())
+                    .col(big_integer_null(RealmInvites::InviteeId))
+                    .col(string_null(RealmInvites::InviteeEmail))
+                    .col(small_integer(RealmInvites::Permissions).not_null())
+                    .col(integer_null(RealmInvites::MaxUses))
+                    .col(integer(RealmInvites::Uses).not_null().default(0))
+                    .col(
+                        timestamp_with_time_zone(RealmInvites::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(RealmInvites::ExpiresAt))
+                    .col(timestamp_with_time_zone_null(RealmInvites::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_invites_realm_id")
+                            .from(RealmInvites::Table, RealmInvites::RealmId)
+                            .to(Realms::Table, Realms::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_invites_created_by")
+                            .from(RealmInvites::Table, RealmInvites::CreatedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_invites_invitee_id")
+                            .from(RealmInvites::Table, RealmInvites::InviteeId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_invites_realm_id")
+                    .table(RealmInvites::Table)
+                    .col(RealmInvites::RealmId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_invites_invitee_id")
+                    .table(RealmInvites::Table)
+                    .col(RealmInvites::InviteeId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_invites_invitee_id")
+                    .table(RealmInvites::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_invites_realm_id")
+                    .table(RealmInvites::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmInvites::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum RealmInvites {
+    Table,
+    Id,
+    RealmId,
+    CreatedBy,
+    Code,
+    InviteeId,
+    InviteeEmail,
+    Permissions,
+    MaxUses,
+    Uses,
+    CreatedAt,
+    ExpiresAt,
+    RevokedAt,
+}
@@ -0,0 +1,133 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_195455_create_realms::Realms;
+use crate::m20250919_202303_create_realm_members::RealmMembers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmRoles::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmRoles::Id).primary_key())
+                    .col(big_integer(RealmRoles::RealmId).not_null())
+                    .col(string(RealmRoles::Name).not_null())
+                    .col(small_integer(RealmRoles::Permissions).not_null())
+                    .col(integer_null(RealmRoles::Colour))
+                    .col(integer(RealmRoles::Position).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_roles_realm_id")
+                            .from(RealmRoles::Table, RealmRoles::RealmId)
+                            .to(Realms::Table, Realms::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_roles_realm_id")
+                    .table(RealmRoles::Table)
+                    .col(RealmRoles::RealmId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmMemberRoles::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmMemberRoles::MemberId).not_null())
+                    .col(big_integer(RealmMemberRoles::RoleId).not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(RealmMemberRoles::MemberId)
+                            .col(RealmMemberRoles::RoleId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_member_roles_member_id")
+                            .from(RealmMemberRoles::Table, RealmMemberRoles::MemberId)
+                            .to(RealmMembers::Table, RealmMembers::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_member_roles_role_id")
+                            .from(RealmMemberRoles::Table, RealmMemberRoles::RoleId)
+                            .to(RealmRoles::Table, RealmRoles::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // `permissions` on a membership now grants bits on top of its roles;
+        // `denied_permissions` takes bits away again.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmMembers::Table)
+                    .add_column(small_integer(RealmMembers::DeniedPermissions).not_null().default(0))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmMembers::Table)
+                    .drop_column(RealmMembers::DeniedPermissions)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmMemberRoles::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_roles_realm_id")
+                    .table(RealmRoles::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmRoles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RealmRoles {
+    Table,
+    Id,
+    RealmId,
+    Name,
+    Permissions,
+    Colour,
+    Position,
+}
+
+#[derive(DeriveIden)]
+enum RealmMemberRoles {
+    Table,
+    MemberId,
+    RoleId,
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+use crate::m20250914_195455_create_realms::Realms;
+
+/// Ownership transfers take two steps: the owner proposes a member, who then
+/// accepts. The proposed member is kept on the realm until then.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .add_column(big_integer_null(Realms::PendingOwnerId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-realms-pending-owner")
+                    .from(Realms::Table, Realms::PendingOwnerId)
+                    .to(Users::Table, Users::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .on_update(ForeignKeyAction::NoAction)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-realms-pending-owner")
+                    .table(Realms::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .drop_column(Realms::PendingOwnerId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
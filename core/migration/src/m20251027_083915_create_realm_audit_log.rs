@@ -0,0 +1,111 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+use crate::m20250914_195455_create_realms::Realms;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmAuditLog::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmAuditLog::Id).primary_key())
+                    .col(big_integer(RealmAuditLog::RealmId).not_null())
+                    .col(big_integer_null(RealmAuditLog::ActorId))
+                    .col(small_integer(RealmAuditLog::Action).not_null())
+                    .col(small_integer(RealmAuditLog::TargetType).not_null())
+                    .col(big_integer_null(RealmAuditLog::TargetId))
+                    .col(json_binary_null(RealmAuditLog::Before))
+                    .col(json_binary_null(RealmAuditLog::After))
+                    .col(
+                        timestamp_with_time_zone(RealmAuditLog::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_audit_log_realm_id")
+                            .from(RealmAuditLog::Table, RealmAuditLog::RealmId)
+                            .to(Realms::Table, Realms::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        // Entries outlive the accounts that made them.
+                        ForeignKey::create()
+                            .name("fk_realm_audit_log_actor_id")
+                            .from(RealmAuditLog::Table, RealmAuditLog::ActorId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_audit_log_realm_id")
+                    .table(RealmAuditLog::Table)
+                    .col(RealmAuditLog::RealmId)
+                    .col(RealmAuditLog::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_audit_log_created_at")
+                    .table(RealmAuditLog::Table)
+                    .col(RealmAuditLog::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_audit_log_created_at")
+                    .table(RealmAuditLog::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_audit_log_realm_id")
+                    .table(RealmAuditLog::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmAuditLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RealmAuditLog {
+    Table,
+    Id,
+    RealmId,
+    ActorId,
+    Action,
+    TargetType,
+    TargetId,
+    Before,
+    After,
+    CreatedAt,
+}
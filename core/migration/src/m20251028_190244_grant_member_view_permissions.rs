@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+use crate::m20250919_202303_create_realm_members::RealmMembers;
+use crate::m20251021_094517_create_realm_invites::RealmInvites;
+
+/// Viewing the calendar and the tasks used to be open to every member. Both are
+/// permission bits now, so existing members and pending invites keep them.
+const VIEW_PERMISSIONS: i16 = 0b00_1100_0000;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(RealmMembers::Table)
+                    .value(RealmMembers::Permissions, Expr::col(RealmMembers::Permissions).bit_or(VIEW_PERMISSIONS))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(RealmInvites::Table)
+                    .value(RealmInvites::Permissions, Expr::col(RealmInvites::Permissions).bit_or(VIEW_PERMISSIONS))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // The bits can't be told apart from ones granted on purpose afterwards.
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+use crate::m20250914_195455_create_realms::Realms;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmShareLinks::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmShareLinks::Id).primary_key())
+                    .col(big_integer(RealmShareLinks::RealmId).not_null())
+                    .col(big_integer(RealmShareLinks::CreatedBy).not_null())
+                    .col(string(RealmShareLinks::Token).not_null().unique_key())
+                    .col(boolean(RealmShareLinks::IncludeTasks).not_null().default(false))
+                    .col(boolean(RealmShareLinks::IncludeDescriptions).not_null().default(false))
+                    .col(
+                        timestamp_with_time_zone(RealmShareLinks::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(RealmShareLinks::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_share_links_realm_id")
+                            .from(RealmShareLinks::Table, RealmShareLinks::RealmId)
+                            .to(Realms::Table, Realms::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_share_links_created_by")
+                            .from(RealmShareLinks::Table, RealmShareLinks::CreatedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_share_links_realm_id")
+                    .table(RealmShareLinks::Table)
+                    .col(RealmShareLinks::RealmId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_share_links_realm_id")
+                    .table(RealmShareLinks::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmShareLinks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RealmShareLinks {
+    Table,
+    Id,
+    RealmId,
+    CreatedBy,
+    Token,
+    IncludeTasks,
+    IncludeDescriptions,
+    CreatedAt,
+    RevokedAt,
+}
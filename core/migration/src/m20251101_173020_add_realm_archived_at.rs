@@ -0,0 +1,31 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_195455_create_realms::Realms;
+
+/// Archived realms stay readable but can't be changed until the owner restores them.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .add_column(timestamp_with_time_zone_null(Realms::ArchivedAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .drop_column(Realms::ArchivedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
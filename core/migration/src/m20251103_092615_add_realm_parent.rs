@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_195455_create_realms::Realms;
+
+/// Realms can be nested, e.g. departments inside a company. Deleting a parent
+/// turns its children into top-level realms.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .add_column(big_integer_null(Realms::ParentId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-realms-parent")
+                    .from(Realms::Table, Realms::ParentId)
+                    .to(Realms::Table, Realms::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .on_update(ForeignKeyAction::NoAction)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realms_parent_id")
+                    .table(Realms::Table)
+                    .col(Realms::ParentId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-realms-parent")
+                    .table(Realms::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .drop_column(Realms::ParentId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_195455_create_realms::Realms;
+
+/// Whether a realm shows up in the directory and whether anyone can join it.
+/// Existing realms stay private.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .add_column(small_integer(Realms::Visibility).not_null().default(0))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .drop_column(Realms::Visibility)
+                    .to_owned(),
+            )
+            .await
+    }
+}
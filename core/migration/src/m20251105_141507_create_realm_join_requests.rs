@@ -0,0 +1,84 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+use crate::m20250914_195455_create_realms::Realms;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmJoinRequests::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmJoinRequests::Id).primary_key())
+                    .col(big_integer(RealmJoinRequests::RealmId).not_null())
+                    .col(big_integer(RealmJoinRequests::UserId).not_null())
+                    .col(string_null(RealmJoinRequests::Message))
+                    .col(
+                        timestamp_with_time_zone(RealmJoinRequests::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_join_requests_realm_id")
+                            .from(RealmJoinRequests::Table, RealmJoinRequests::RealmId)
+                            .to(Realms::Table, Realms::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_join_requests_user_id")
+                            .from(RealmJoinRequests::Table, RealmJoinRequests::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_join_requests_realm_user")
+                    .table(RealmJoinRequests::Table)
+                    .col(RealmJoinRequests::RealmId)
+                    .col(RealmJoinRequests::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_join_requests_realm_user")
+                    .table(RealmJoinRequests::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmJoinRequests::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RealmJoinRequests {
+    Table,
+    Id,
+    RealmId,
+    UserId,
+    Message,
+    CreatedAt,
+}
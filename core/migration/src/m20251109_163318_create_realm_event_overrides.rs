@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250921_015955_create_realm_events::RealmEvents;
+
+/// Changes to a single occurrence of a recurring event, found by the start
+/// time the occurrence originally had, and the start times left out of a
+/// series altogether (RFC 5545 `EXDATE`).
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmEvents::Table)
+                    .add_column(json_binary(RealmEvents::Exdates).not_null().default(Expr::val("[]")))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmEventOverrides::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmEventOverrides::Id).primary_key())
+                    .col(big_integer(RealmEventOverrides::EventId).not_null())
+                    .col(timestamp_with_time_zone(RealmEventOverrides::OriginalStart).not_null())
+                    .col(timestamp_with_time_zone_null(RealmEventOverrides::StartTime))
+                    .col(timestamp_with_time_zone_null(RealmEventOverrides::EndTime))
+                    .col(string_null(RealmEventOverrides::Name))
+                    .col(text_null(RealmEventOverrides::Description))
+                    .col(string_null(RealmEventOverrides::Location))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_event_overrides_event_id")
+                            .from(RealmEventOverrides::Table, RealmEventOverrides::EventId)
+                            .to(RealmEvents::Table, RealmEvents::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_event_overrides_event_start")
+                    .table(RealmEventOverrides::Table)
+                    .col(RealmEventOverrides::EventId)
+                    .col(RealmEventOverrides::OriginalStart)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_event_overrides_event_start")
+                    .table(RealmEventOverrides::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmEventOverrides::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmEvents::Table)
+                    .drop_column(RealmEvents::Exdates)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RealmEventOverrides {
+    Table,
+    Id,
+    EventId,
+    OriginalStart,
+    StartTime,
+    EndTime,
+    Name,
+    Description,
+    Location,
+}
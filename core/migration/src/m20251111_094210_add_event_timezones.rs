@@ -0,0 +1,50 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_195455_create_realms::Realms;
+use crate::m20250921_015955_create_realm_events::RealmEvents;
+
+/// IANA time zone each event recurs in, and the one a realm's new events
+/// default to. Everything so far was expanded in UTC, so that's what existing
+/// rows get.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .add_column(string(Realms::Timezone).not_null().default("UTC"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmEvents::Table)
+                    .add_column(string(RealmEvents::Timezone).not_null().default("UTC"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmEvents::Table)
+                    .drop_column(RealmEvents::Timezone)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Realms::Table)
+                    .drop_column(Realms::Timezone)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250921_015955_create_realm_events::RealmEvents;
+
+/// When an event's last occurrence ends, so schedules can find every series
+/// that reaches into their window. Existing series are treated as endless
+/// until they're next edited; single events get their own end.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmEvents::Table)
+                    .add_column(timestamp_with_time_zone_null(RealmEvents::SeriesEnd))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(RealmEvents::Table)
+                    .value(
+                        RealmEvents::SeriesEnd,
+                        Func::coalesce([Expr::col(RealmEvents::EndTime), Expr::col(RealmEvents::StartTime)])
+                    )
+                    .and_where(Expr::col(RealmEvents::Recurrence).is_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_events_realm_series")
+                    .table(RealmEvents::Table)
+                    .col(RealmEvents::RealmId)
+                    .col(RealmEvents::StartTime)
+                    .col(RealmEvents::SeriesEnd)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_realm_events_realm_series").to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmEvents::Table)
+                    .drop_column(RealmEvents::SeriesEnd)
+                    .to_owned(),
+            )
+            .await
+    }
+}
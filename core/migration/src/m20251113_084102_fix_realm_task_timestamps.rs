@@ -0,0 +1,37 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250926_032701_create_realm_tasks::RealmTasks;
+
+/// The task dates were created as `timestamp` while the server reads them as
+/// UTC instants, so any schedule with a dated task failed to decode. Existing
+/// values were written in UTC.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmTasks::Table)
+                    .modify_column(timestamp_with_time_zone_null(RealmTasks::DueDate))
+                    .modify_column(timestamp_with_time_zone_null(RealmTasks::StartDate))
+                    .modify_column(timestamp_with_time_zone_null(RealmTasks::PlannedFor))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RealmTasks::Table)
+                    .modify_column(timestamp_null(RealmTasks::DueDate))
+                    .modify_column(timestamp_null(RealmTasks::StartDate))
+                    .modify_column(timestamp_null(RealmTasks::PlannedFor))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,87 @@
+use sea_orm_migration::{prelude::*, schema::*};
+use crate::m20250914_040704_create_users::Users;
+use crate::m20250914_195455_create_realms::Realms;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RealmCalendarFeeds::Table)
+                    .if_not_exists()
+                    .col(big_integer(RealmCalendarFeeds::Id).primary_key())
+                    .col(big_integer(RealmCalendarFeeds::RealmId).not_null())
+                    .col(big_integer(RealmCalendarFeeds::UserId).not_null())
+                    .col(string(RealmCalendarFeeds::TokenHash).not_null().unique_key())
+                    .col(
+                        timestamp_with_time_zone(RealmCalendarFeeds::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(RealmCalendarFeeds::LastUsedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_calendar_feeds_realm_id")
+                            .from(RealmCalendarFeeds::Table, RealmCalendarFeeds::RealmId)
+                            .to(Realms::Table, Realms::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_realm_calendar_feeds_user_id")
+                            .from(RealmCalendarFeeds::Table, RealmCalendarFeeds::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Each member has at most one feed per realm.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_realm_calendar_feeds_realm_user")
+                    .table(RealmCalendarFeeds::Table)
+                    .col(RealmCalendarFeeds::RealmId)
+                    .col(RealmCalendarFeeds::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_realm_calendar_feeds_realm_user")
+                    .table(RealmCalendarFeeds::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RealmCalendarFeeds::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RealmCalendarFeeds {
+    Table,
+    Id,
+    RealmId,
+    UserId,
+    TokenHash,
+    CreatedAt,
+    LastUsedAt,
+}
@@ -1,30 +1,42 @@
 use std::fmt::Debug;
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use argon2::password_hash::SaltString;
-use argon2::Argon2;
+use argon2::{Algorithm, Argon2, Params, Version};
 use async_nats::Client;
 use hmac::{Hmac, Mac};
 use sea_orm::DatabaseConnection;
 use sha2::Sha256;
 use tokio::sync::RwLock;
+use crate::mail::SharedMailer;
+use crate::service::oidc::ProviderCache;
+use crate::service::rate_limit::{LoginLockouts, RateLimiter};
 
 #[derive(Clone, Debug)]
 pub struct NebulaApp {
     pub config: AppConfig,
     pub state: SharedState,
     pub cableway: Client,
-    pub db: DatabaseConnection
+    pub db: DatabaseConnection,
+    pub mailer: SharedMailer
 }
 
 #[derive(Debug)]
 pub struct AppState {
+    pub oidc_providers: ProviderCache,
+    pub rate_limiter: RateLimiter,
+    pub login_lockouts: LoginLockouts,
 }
 
 impl AppState {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            oidc_providers: ProviderCache::default(),
+            rate_limiter: RateLimiter::default(),
+            login_lockouts: LoginLockouts::default(),
+        }
     }
 }
 
@@ -36,12 +48,183 @@ pub struct AppConfig {
     pub cableway_addr: SocketAddr,
     pub db_url: String,
     pub db_fresh: bool,
-    pub argon_salt: SaltString,
+    pub legacy_argon_salt: Option<SaltString>,
     pub jwt_key: Hmac<Sha256>,
     pub argon2: Argon2<'static>,
+    pub access_token_ttl: chrono::Duration,
+    pub refresh_token_ttl: chrono::Duration,
+    pub public_url: String,
+    pub mailer: MailerConfig,
+    pub mail_from: String,
+    pub require_verified_email: bool,
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    /// Exports covering more events and tasks than this are built in the background.
+    pub export_sync_limit: u64,
+    pub rate_limits: RateLimitConfig,
+    /// How long realm audit log entries are kept, `None` keeps them forever.
+    pub audit_log_retention: Option<chrono::Duration>,
+}
+
+#[derive(Clone, Debug)]
+pub enum MailerConfig {
+    Smtp {
+        host: String,
+        port: u16,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    File {
+        directory: PathBuf,
+    },
+    Stdout,
+}
+
+impl MailerConfig {
+    fn from_env() -> Self {
+        // Printing mail puts reset and verification links in the logs, so only
+        // debug builds fall back to it.
+        let kind: String = if cfg!(debug_assertions) {
+            get_optional_env("MAILER", "stdout".to_string())
+        } else {
+            get_required_env("MAILER")
+        };
+        match kind.to_lowercase().as_str() {
+            "smtp" => MailerConfig::Smtp {
+                host: get_required_env("SMTP_HOST"),
+                port: get_optional_env("SMTP_PORT", 587),
+                username: std::env::var("SMTP_USERNAME").ok(),
+                password: std::env::var("SMTP_PASSWORD").ok(),
+            },
+            "file" => MailerConfig::File {
+                directory: get_required_env("MAIL_DIR"),
+            },
+            "stdout" => MailerConfig::Stdout,
+            other => panic!("Unknown MAILER backend: {}", other),
+        }
+    }
+}
+
+/// A number of requests allowed per window, written as `requests/seconds`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub window: std::time::Duration,
+}
+
+impl RateLimit {
+    pub const fn new(requests: u32, window_secs: u64) -> Self {
+        RateLimit { requests, window: std::time::Duration::from_secs(window_secs) }
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (requests, window) = value.split_once('/')
+            .ok_or_else(|| format!("Expected `requests/seconds`, got `{}`", value))?;
+        let requests = requests.trim().parse().map_err(|_| format!("Invalid request count `{}`", requests))?;
+        let window: u64 = window.trim().parse().map_err(|_| format!("Invalid window `{}`", window))?;
+        if window == 0 {
+            return Err("Rate limit window can't be zero".to_string());
+        }
+        Ok(RateLimit::new(requests, window))
+    }
+}
+
+/// Request budgets and login lockouts. Limits are read as `requests/seconds`
+/// and durations in seconds:
+///
+/// - `RATE_LIMIT_ENABLED`: `false` turns off every limit and the lockout (default `true`)
+/// - `RATE_LIMIT_AUTH`: per-IP budget on credential routes (default `20/60`)
+/// - `RATE_LIMIT_API`: per-IP budget on every other route (default `600/60`)
+/// - `RATE_LIMIT_USER`: per-user budget on authenticated routes (default `300/60`)
+/// - `RATE_LIMIT_EXEMPT_LOOPBACK`: skips the per-IP budgets for local clients,
+///   such as the integration tests (default `true` in debug builds only)
+/// - `LOGIN_LOCKOUT_THRESHOLD`: failed logins before an account locks (default `5`)
+/// - `LOGIN_LOCKOUT_BASE`, `LOGIN_LOCKOUT_MAX`: first and longest lock (default `30` and `900`)
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Per-IP limit on credential endpoints (login, signup, password reset, ...).
+    pub auth: RateLimit,
+    /// Per-IP limit on every other route.
+    pub api: RateLimit,
+    /// Per-user limit on authenticated routes.
+    pub user: RateLimit,
+    /// Whether loopback addresses skip the per-IP limits. Off in release
+    /// builds, where a local reverse proxy would otherwise lift them for
+    /// everyone.
+    pub exempt_loopback: bool,
+    /// Failed logins allowed on an account before it gets locked.
+    pub lockout_threshold: u32,
+    /// First lockout length; every further failure doubles it up to `lockout_max`.
+    pub lockout_base: std::time::Duration,
+    pub lockout_max: std::time::Duration,
+}
+
+impl RateLimitConfig {
+    fn from_env() -> Self {
+        RateLimitConfig {
+            enabled: std::env::var("RATE_LIMIT_ENABLED")
+                .unwrap_or_else(|_| "true".to_string())
+                .to_lowercase() == "true",
+            auth: get_optional_env("RATE_LIMIT_AUTH", RateLimit::new(20, 60)),
+            api: get_optional_env("RATE_LIMIT_API", RateLimit::new(600, 60)),
+            user: get_optional_env("RATE_LIMIT_USER", RateLimit::new(300, 60)),
+            exempt_loopback: get_optional_env("RATE_LIMIT_EXEMPT_LOOPBACK", cfg!(debug_assertions)),
+            lockout_threshold: get_optional_env("LOGIN_LOCKOUT_THRESHOLD", 5),
+            lockout_base: std::time::Duration::from_secs(get_optional_env("LOGIN_LOCKOUT_BASE", 30)),
+            lockout_max: std::time::Duration::from_secs(get_optional_env("LOGIN_LOCKOUT_MAX", 15 * 60)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OidcProviderConfig {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: String,
+    pub redirect_url: String,
+}
+
+impl OidcProviderConfig {
+    /// Reads the providers listed in `OIDC_PROVIDERS`, each configured through
+    /// `OIDC_<NAME>_*` variables.
+    fn from_env(public_url: &str) -> Vec<Self> {
+        let names: String = get_optional_env("OIDC_PROVIDERS", String::new());
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                let prefix = format!("OIDC_{}", name.to_uppercase());
+                OidcProviderConfig {
+                    name: name.to_lowercase(),
+                    issuer: get_required_env::<String>(&format!("{prefix}_ISSUER"))
+                        .trim_end_matches('/')
+                        .to_string(),
+                    client_id: get_required_env(&format!("{prefix}_CLIENT_ID")),
+                    client_secret: std::env::var(format!("{prefix}_CLIENT_SECRET")).ok(),
+                    scopes: get_optional_env(&format!("{prefix}_SCOPES"), "openid email profile".to_string()),
+                    redirect_url: get_optional_env(
+                        &format!("{prefix}_REDIRECT_URL"),
+                        format!("{}/oauth/{}/callback", public_url, name.to_lowercase())
+                    ),
+                }
+            })
+            .collect()
+    }
 }
 
 impl AppConfig {
+    pub fn oidc_provider(&self, name: &str) -> Option<&OidcProviderConfig> {
+        self.oidc_providers.iter().find(|provider| provider.name == name)
+    }
+
+
     pub fn from_env() -> Self {
         let rest_host: IpAddr = get_required_env("REST_HOST");
         let rest_port: u16 = get_required_env("REST_PORT");
@@ -51,10 +234,18 @@ impl AppConfig {
 
         let db_url: String = get_required_env("DATABASE_URL");
 
-        let argon_salt_str: String = get_required_env("ARGON_SALT");
-        let argon_salt = SaltString::from_b64(&argon_salt_str)
-            .expect("Failed to create Argon2 salt from environment variable");
-        
+        let legacy_argon_salt = std::env::var("ARGON_SALT")
+            .ok()
+            .map(|salt| SaltString::from_b64(&salt)
+                .expect("Failed to create Argon2 salt from environment variable"));
+
+        let argon2_params = Params::new(
+            get_optional_env("ARGON2_MEMORY_KIB", Params::DEFAULT_M_COST),
+            get_optional_env("ARGON2_ITERATIONS", Params::DEFAULT_T_COST),
+            get_optional_env("ARGON2_PARALLELISM", Params::DEFAULT_P_COST),
+            None
+        ).expect("Invalid Argon2 parameters");
+
         let jwt_secret: String = get_required_env("JWT_SECRET");
         let jwt_key = Hmac::<Sha256>::new_from_slice(jwt_secret.as_bytes())
             .expect("Failed to create JWT key from environment variable");
@@ -63,14 +254,36 @@ impl AppConfig {
             .unwrap_or_else(|_| "false".to_string())
             .to_lowercase() == "true";
 
+        let require_verified_email = std::env::var("REQUIRE_VERIFIED_EMAIL")
+            .unwrap_or_else(|_| "false".to_string())
+            .to_lowercase() == "true";
+
+        let public_url: String = get_optional_env("PUBLIC_URL", "http://localhost:3000".to_string());
+        let oidc_providers = OidcProviderConfig::from_env(&public_url);
+
+        let access_token_ttl: i64 = get_optional_env("ACCESS_TOKEN_TTL", 15 * 60);
+        let refresh_token_ttl: i64 = get_optional_env("REFRESH_TOKEN_TTL", 30 * 24 * 60 * 60);
+        let audit_log_retention_days: i64 = get_optional_env("AUDIT_LOG_RETENTION_DAYS", 365);
+
         AppConfig {
             rest_addr: SocketAddr::new(rest_host, rest_port),
             cableway_addr: SocketAddr::new(cableway_host, cableway_port),
             db_url,
             db_fresh,
-            argon_salt,
+            legacy_argon_salt,
             jwt_key,
-            argon2: Argon2::default(),
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params),
+            access_token_ttl: chrono::Duration::seconds(access_token_ttl),
+            refresh_token_ttl: chrono::Duration::seconds(refresh_token_ttl),
+            public_url,
+            mailer: MailerConfig::from_env(),
+            mail_from: get_optional_env("MAIL_FROM", "Nebula <no-reply@nebula.local>".to_string()),
+            require_verified_email,
+            oidc_providers,
+            export_sync_limit: get_optional_env("EXPORT_SYNC_LIMIT", 1000),
+            rate_limits: RateLimitConfig::from_env(),
+            audit_log_retention: (audit_log_retention_days > 0)
+                .then(|| chrono::Duration::days(audit_log_retention_days)),
         }
     }
 }
@@ -80,4 +293,13 @@ fn get_required_env<T : FromStr>(var: &str) -> T where <T as FromStr>::Err: Debu
         .expect(&format!("Missing required environment variable: {}", var))
         .parse()
         .expect(&format!("Environment variable formated incorrectly: {}", var))
+}
+
+fn get_optional_env<T : FromStr>(var: &str, default: T) -> T where <T as FromStr>::Err: Debug {
+    match std::env::var(var) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("Environment variable formated incorrectly: {}", var)),
+        Err(_) => default
+    }
 }
\ No newline at end of file
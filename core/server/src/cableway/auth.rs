@@ -1,11 +1,11 @@
 use crate::app::AppConfig;
-use crate::data::permissions::{BitwisePermissions, RealmPermissions};
+use crate::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
 use crate::data::snowflake::Snowflake;
-use crate::schema::realm_members;
+use crate::schema::{realm_members, realms};
 use async_nats::Client;
 use async_std::prelude::StreamExt;
 use sea_orm::ColumnTrait;
-use sea_orm::DatabaseConnection;
+use sea_orm::{DatabaseConnection, DbErr};
 use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
 use std::collections::HashMap;
@@ -34,23 +34,13 @@ pub async fn open_authentication_channel(config: &AppConfig, client: &Client, db
                     .unwrap_or_else(|_| "".to_string());
                 let auth_result = crate::service::auth::authenticate(&config, &db, token).await;
                 let response = if auth_result.is_ok() {
-                    let allowed_topics = realm_members::Entity::find()
-                        .filter(realm_members::Column::UserId.eq(auth_result.as_ref().unwrap().id))
-                        .all(&db)
+                    let allowed_topics = allowed_topics(&db, auth_result.as_ref().unwrap().user.id)
                         .await
-                        .expect("Failed to query realm memberships")
-                        .into_iter()
-                        .map(|m|
-                            (m.realm_id, generate_topics_for_permissions(
-                                m.realm_id,
-                                RealmPermissions(m.permissions)
-                            ))
-                        )
-                        .collect();
+                        .expect("Failed to query realm memberships");
 
                     AuthResponse {
                         success: true,
-                        user_id: Some(auth_result.unwrap().id),
+                        user_id: Some(auth_result.unwrap().user.id),
                         allowed_topics: Some(allowed_topics),
                     }
                 } else {
@@ -71,16 +61,64 @@ pub async fn open_authentication_channel(config: &AppConfig, client: &Client, db
     });
 }
 
+/// Topics for every realm the user is in, based on their effective permissions.
+/// Realms nested below those are covered too, with the permissions inherited
+/// from the nearest realm the user is a member of.
+async fn allowed_topics(db: &DatabaseConnection, user_id: Snowflake) -> Result<HashMap<Snowflake, Vec<String>>, DbErr> {
+    let memberships = realm_members::Entity::find()
+        .filter(realm_members::Column::UserId.eq(user_id))
+        .find_also_related(realms::Entity)
+        .all(db)
+        .await?;
+    let member_ids: Vec<Snowflake> = memberships.iter().map(|(membership, _)| membership.id).collect();
+    let mut roles = crate::service::role::roles_by_member(db, &member_ids).await?;
+
+    let permissions: HashMap<Snowflake, RealmPermissions> = memberships.into_iter()
+        .filter_map(|(membership, realm)| {
+            let realm = realm?;
+            let roles = roles.remove(&membership.id).unwrap_or_default();
+            let permissions = crate::service::role::combine_permissions(&realm, &membership, &roles);
+            Some((realm.id, permissions))
+        })
+        .collect();
+
+    let realm_ids: Vec<Snowflake> = permissions.keys().copied().collect();
+    let descendants = crate::service::realm::descendants(db, &realm_ids).await?;
+    let parents: HashMap<Snowflake, Option<Snowflake>> = descendants.iter()
+        .map(|realm| (realm.id, realm.parent_id))
+        .collect();
+
+    let mut topics: HashMap<Snowflake, Vec<String>> = permissions.iter()
+        .map(|(realm_id, permissions)| (*realm_id, generate_topics_for_permissions(*realm_id, *permissions)))
+        .collect();
+    for realm in &descendants {
+        if permissions.contains_key(&realm.id) {
+            continue;
+        }
+        let mut ancestor = realm.parent_id;
+        while let Some(id) = ancestor {
+            if let Some(inherited) = permissions.get(&id) {
+                topics.insert(realm.id, generate_topics_for_permissions(realm.id, *inherited));
+                break;
+            }
+            ancestor = parents.get(&id).copied().flatten();
+        }
+    }
+    Ok(topics)
+}
+
 pub fn generate_topics_for_permissions(realm_id: Snowflake, realm_permissions: RealmPermissions) -> Vec<String> {
     if realm_permissions.is_all() {
         return vec![format!("realm.{realm_id}.>")];
     }
 
-    let topics = vec![
-        format!("realm.{realm_id}.calendar.*"),
+    let mut topics = vec![
+        format!("realm.{realm_id}.members.*"),
+        format!("realm.{realm_id}.realm.*"),
     ];
-
-    // todo: add restricted topics here
+    if realm_permissions.contains(RealmPermission::ViewCalendar) {
+        topics.push(format!("realm.{realm_id}.calendar.*"));
+    }
 
     topics
 }
\ No newline at end of file
@@ -2,7 +2,7 @@ use async_nats::Client;
 use serde::{Deserialize, Serialize};
 use crate::cableway::events::send_event;
 use crate::data::snowflake::Snowflake;
-use crate::web::routing::dto::RealmEventDto;
+use crate::web::routing::dto::{RealmEventDto, RealmEventOverrideDto};
 
 #[derive(Serialize, Deserialize)]
 struct CalendarEventCreated {
@@ -28,4 +28,23 @@ pub async fn send_event_deleted(
 ) -> Result<(), async_nats::Error> {
     let message = CalendarEventDeleted { event_id };
     send_event(cableway, "event_deleted", format!("realm.{}.calendar.event_deleted", message.event_id), message).await
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize)]
+struct CalendarEventUpdated {
+    pub events: Vec<RealmEventDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<RealmEventOverrideDto>
+}
+
+/// Editing part of a series touches more than one event, so they're sent together
+/// along with every override those events now have.
+pub async fn send_event_updated(
+    cableway: &Client,
+    realm_id: Snowflake,
+    events: Vec<RealmEventDto>,
+    overrides: Vec<RealmEventOverrideDto>
+) -> Result<(), async_nats::Error> {
+    let message = CalendarEventUpdated { events, overrides };
+    send_event(cableway, "event_updated", format!("realm.{}.calendar.event_updated", realm_id), message).await
+}
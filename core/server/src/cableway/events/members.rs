@@ -0,0 +1,49 @@
+use async_nats::Client;
+use serde::{Deserialize, Serialize};
+use crate::cableway::events::send_event;
+use crate::data::snowflake::Snowflake;
+use crate::web::routing::dto::RealmMemberDto;
+
+#[derive(Serialize, Deserialize)]
+struct MemberJoined {
+    pub member: RealmMemberDto
+}
+
+pub async fn send_member_joined(
+    cableway: &Client,
+    realm_id: Snowflake,
+    member: RealmMemberDto
+) -> Result<(), async_nats::Error> {
+    let message = MemberJoined { member };
+    send_event(cableway, "member_joined", format!("realm.{}.members.member_joined", realm_id), message).await
+}
+
+#[derive(Serialize, Deserialize)]
+struct MemberUpdated {
+    pub member: RealmMemberDto
+}
+
+pub async fn send_member_updated(
+    cableway: &Client,
+    realm_id: Snowflake,
+    member: RealmMemberDto
+) -> Result<(), async_nats::Error> {
+    let message = MemberUpdated { member };
+    send_event(cableway, "member_updated", format!("realm.{}.members.member_updated", realm_id), message).await
+}
+
+#[derive(Serialize, Deserialize)]
+struct MemberLeft {
+    pub user_id: Snowflake,
+    pub kicked: bool
+}
+
+pub async fn send_member_left(
+    cableway: &Client,
+    realm_id: Snowflake,
+    user_id: Snowflake,
+    kicked: bool
+) -> Result<(), async_nats::Error> {
+    let message = MemberLeft { user_id, kicked };
+    send_event(cableway, "member_left", format!("realm.{}.members.member_left", realm_id), message).await
+}
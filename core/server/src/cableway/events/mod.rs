@@ -4,6 +4,8 @@ use serde::Serialize;
 use crate::cableway::send_message;
 
 pub mod calendar;
+pub mod members;
+pub mod realm;
 
 #[derive(Serialize)]
 struct EventEnvelope<T : Serialize> {
@@ -0,0 +1,68 @@
+use async_nats::Client;
+use serde::{Deserialize, Serialize};
+use crate::cableway::events::send_event;
+use crate::data::snowflake::Snowflake;
+use crate::web::routing::dto::RealmDto;
+
+#[derive(Serialize, Deserialize)]
+struct RealmUpdated {
+    pub realm: RealmDto
+}
+
+pub async fn send_realm_updated(
+    cableway: &Client,
+    realm: RealmDto
+) -> Result<(), async_nats::Error> {
+    let message = RealmUpdated { realm };
+    send_event(cableway, "realm_updated", format!("realm.{}.realm.realm_updated", message.realm.id), message).await
+}
+
+#[derive(Serialize, Deserialize)]
+struct RealmDeleted {
+    pub realm_id: Snowflake
+}
+
+pub async fn send_realm_deleted(
+    cableway: &Client,
+    realm_id: Snowflake
+) -> Result<(), async_nats::Error> {
+    let message = RealmDeleted { realm_id };
+    send_event(cableway, "realm_deleted", format!("realm.{}.realm.realm_deleted", realm_id), message).await
+}
+
+#[derive(Serialize, Deserialize)]
+struct OwnershipTransferChanged {
+    pub realm_id: Snowflake,
+    pub owner_id: Snowflake,
+    pub pending_owner_id: Option<Snowflake>
+}
+
+/// Fires when a transfer is proposed, cancelled or declined.
+pub async fn send_ownership_transfer_changed(
+    cableway: &Client,
+    realm: &RealmDto
+) -> Result<(), async_nats::Error> {
+    let message = OwnershipTransferChanged {
+        realm_id: realm.id,
+        owner_id: realm.owner_id,
+        pending_owner_id: realm.pending_owner_id
+    };
+    send_event(cableway, "ownership_transfer_changed", format!("realm.{}.realm.ownership_transfer_changed", realm.id), message).await
+}
+
+#[derive(Serialize, Deserialize)]
+struct OwnerChanged {
+    pub realm_id: Snowflake,
+    pub previous_owner_id: Snowflake,
+    pub owner_id: Snowflake
+}
+
+pub async fn send_owner_changed(
+    cableway: &Client,
+    realm_id: Snowflake,
+    previous_owner_id: Snowflake,
+    owner_id: Snowflake
+) -> Result<(), async_nats::Error> {
+    let message = OwnerChanged { realm_id, previous_owner_id, owner_id };
+    send_event(cableway, "owner_changed", format!("realm.{}.realm.owner_changed", realm_id), message).await
+}
@@ -1,4 +1,5 @@
 pub mod permissions;
+pub mod scopes;
 pub mod snowflake;
 
 pub const LOCAL_EPOCH: u64 = 1_700_000_000;
\ No newline at end of file
@@ -1,3 +1,4 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use core::ops::{BitAnd, BitOr, BitXor, Not};
 
@@ -8,10 +9,13 @@ pub trait BitwisePermissions: Sized {
     + BitXor<Output = Self::Bits>
     + Not<Output = Self::Bits>
     + PartialEq;
-    type Permission: Copy;
+    /// Serializes to the permission's public name.
+    type Permission: Copy + Serialize + DeserializeOwned + 'static;
 
     const EMPTY: Self::Bits;
     const ALL: Self::Bits;
+    /// Every permission, in the order they're listed by name.
+    const PERMISSIONS: &'static [Self::Permission];
 
     fn bits(&self) -> Self::Bits;
     fn set_bits(&mut self, bits: Self::Bits);
@@ -70,6 +74,20 @@ pub trait BitwisePermissions: Sized {
         self.bits() == Self::ALL
     }
 
+    fn to_vec(&self) -> Vec<Self::Permission> {
+        Self::PERMISSIONS
+            .iter()
+            .copied()
+            .filter(|perm| self.contains(*perm))
+            .collect()
+    }
+
+    fn from_permissions<I: IntoIterator<Item = Self::Permission>>(perms: I) -> Self {
+        let mut set = Self::empty();
+        perms.into_iter().for_each(|perm| set.insert(perm));
+        set
+    }
+
     #[inline]
     fn plus(&self, other: &Self) -> Self {
         Self::from_bits(self.bits() | other.bits())
@@ -82,14 +100,27 @@ pub trait BitwisePermissions: Sized {
 }
 
 #[repr(transparent)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RealmPermissions(pub i16);
 
-#[repr(u8)]
+#[repr(u16)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RealmPermission {
-    ManageEvents  = 0b0001,
-    ManageTasks   = 0b0010,
+    /// Edit and delete any event, not just one's own.
+    ManageEvents  = 0b00_0000_0001,
+    /// Create, edit and delete tasks.
+    ManageTasks   = 0b00_0000_0010,
+    InviteMembers = 0b00_0000_0100,
+    ManageMembers = 0b00_0000_1000,
+    ManageRealm   = 0b00_0001_0000,
+    ViewAuditLog  = 0b00_0010_0000,
+    ViewCalendar  = 0b00_0100_0000,
+    ViewTasks     = 0b00_1000_0000,
+    /// Create events and manage the ones one created.
+    CreateEvents  = 0b01_0000_0000,
+    /// Mark tasks as done or not done, without editing them otherwise.
+    CompleteTasks = 0b10_0000_0000,
 }
 
 impl BitwisePermissions for RealmPermissions {
@@ -98,6 +129,18 @@ impl BitwisePermissions for RealmPermissions {
 
     const EMPTY: i16 = 0;
     const ALL: i16 = i16::MAX;
+    const PERMISSIONS: &'static [RealmPermission] = &[
+        RealmPermission::ViewCalendar,
+        RealmPermission::CreateEvents,
+        RealmPermission::ManageEvents,
+        RealmPermission::ViewTasks,
+        RealmPermission::CompleteTasks,
+        RealmPermission::ManageTasks,
+        RealmPermission::InviteMembers,
+        RealmPermission::ManageMembers,
+        RealmPermission::ManageRealm,
+        RealmPermission::ViewAuditLog,
+    ];
 
     #[inline] fn bits(&self) -> i16 { self.0 }
     #[inline] fn set_bits(&mut self, bits: i16) { self.0 = bits; }
@@ -106,6 +149,12 @@ impl BitwisePermissions for RealmPermissions {
 }
 
 impl RealmPermissions {
+    /// What members get when nothing else is granted: they can see the realm,
+    /// but not change it.
+    pub const MEMBER_DEFAULT: RealmPermissions = RealmPermissions(
+        RealmPermission::ViewCalendar as i16 | RealmPermission::ViewTasks as i16
+    );
+
     #[inline] pub const fn new(value: i16) -> Self { Self(value) }
 
     #[inline]
@@ -114,4 +163,68 @@ impl RealmPermissions {
         for &p in perms { bits |= p as i16; }
         Self(bits)
     }
-}
\ No newline at end of file
+
+    #[inline]
+    pub fn contains_any(&self, perms: &[RealmPermission]) -> bool {
+        perms.iter().any(|perm| self.contains(*perm))
+    }
+}
+
+/// Serializes a permission set as a list of names, for use with `#[serde(with = "named")]`.
+/// Raw bits are still accepted when deserializing.
+pub mod named {
+    use super::BitwisePermissions;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr<P> {
+        Bits(i64),
+        Names(Vec<P>),
+    }
+
+    pub fn serialize<P: BitwisePermissions, S: Serializer>(perms: &P, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(perms.to_vec())
+    }
+
+    pub fn deserialize<'de, P, D>(deserializer: D) -> Result<P, D::Error>
+    where
+        P: BitwisePermissions,
+        P::Bits: TryFrom<i64>,
+        D: Deserializer<'de>,
+    {
+        match Repr::<P::Permission>::deserialize(deserializer)? {
+            Repr::Bits(bits) => P::Bits::try_from(bits)
+                .map(P::from_bits)
+                .map_err(|_| serde::de::Error::custom("permission bits out of range")),
+            Repr::Names(names) => Ok(P::from_permissions(names)),
+        }
+    }
+
+    /// The same for optional fields.
+    pub mod option {
+        use super::super::BitwisePermissions;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<P: BitwisePermissions, S: Serializer>(perms: &Option<P>, serializer: S) -> Result<S::Ok, S::Error> {
+            match perms {
+                Some(perms) => super::serialize(perms, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, P, D>(deserializer: D) -> Result<Option<P>, D::Error>
+        where
+            P: BitwisePermissions,
+            P::Bits: TryFrom<i64>,
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Named<P: BitwisePermissions>(#[serde(with = "super")] P)
+            where
+                P::Bits: TryFrom<i64>;
+
+            Ok(Option::<Named<P>>::deserialize(deserializer)?.map(|Named(perms)| perms))
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use crate::data::permissions::BitwisePermissions;
+
+/// What a personal access token is allowed to do, on top of the permissions
+/// its owner has in each realm.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenScopes(pub i32);
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenScope {
+    #[serde(rename = "realms:read")]
+    RealmsRead  = 0b00001,
+    #[serde(rename = "events:read")]
+    EventsRead  = 0b00010,
+    #[serde(rename = "events:write")]
+    EventsWrite = 0b00100,
+    #[serde(rename = "tasks:read")]
+    TasksRead   = 0b01000,
+    #[serde(rename = "tasks:write")]
+    TasksWrite  = 0b10000,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 5] = [
+        TokenScope::RealmsRead,
+        TokenScope::EventsRead,
+        TokenScope::EventsWrite,
+        TokenScope::TasksRead,
+        TokenScope::TasksWrite,
+    ];
+}
+
+impl BitwisePermissions for TokenScopes {
+    type Bits = i32;
+    type Permission = TokenScope;
+
+    const EMPTY: i32 = 0;
+    const ALL: i32 = 0b11111;
+    const PERMISSIONS: &'static [TokenScope] = &TokenScope::ALL;
+
+    #[inline] fn bits(&self) -> i32 { self.0 }
+    #[inline] fn set_bits(&mut self, bits: i32) { self.0 = bits; }
+    #[inline] fn from_bits(bits: i32) -> Self { Self(bits) }
+    #[inline] fn mask(p: TokenScope) -> i32 { p as i32 }
+}
+
+impl TokenScopes {
+    #[inline] pub const fn new(value: i32) -> Self { Self(value) }
+
+    #[inline]
+    pub fn from_slice(scopes: &[TokenScope]) -> Self {
+        let mut bits = 0i32;
+        for &s in scopes { bits |= s as i32; }
+        Self(bits)
+    }
+}
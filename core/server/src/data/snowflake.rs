@@ -61,7 +61,10 @@ impl TryFromU64 for Snowflake {
 
 impl TryGetable for Snowflake {
     fn try_get_by<I: ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
-        let raw: i64 = res.try_get_by(index)?;
+        let raw: Option<i64> = res.try_get_by(index)?;
+        let Some(raw) = raw else {
+            return Err(TryGetError::Null(format!("{index:?}")));
+        };
         if raw < 0 {
             return Err(TryGetError::DbErr(sea_orm::DbErr::Type(format!("negative value for u64 column"))));
         }
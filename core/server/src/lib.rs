@@ -3,6 +3,7 @@ pub mod app;
 pub mod schema;
 pub mod cableway;
 pub mod database;
+pub mod mail;
 pub mod service;
 pub mod data;
 pub mod util;
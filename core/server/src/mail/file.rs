@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+use async_trait::async_trait;
+use crate::mail::{MailError, MailMessage, Mailer};
+use crate::service::snowflake::next_snowflake;
+
+/// Writes every message as a JSON file, meant for local development and tests.
+#[derive(Debug)]
+pub struct FileMailer {
+    directory: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(directory: PathBuf) -> Self {
+        std::fs::create_dir_all(&directory)
+            .expect("Failed to create mail directory");
+        Self { directory }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), MailError> {
+        let path = self.directory.join(format!("{}.json", next_snowflake()));
+        let contents = serde_json::to_vec_pretty(&message)
+            .map_err(|err| MailError(err.to_string()))?;
+        tokio::fs::write(path, contents)
+            .await
+            .map_err(|err| MailError(err.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub struct StdoutMailer;
+
+#[async_trait]
+impl Mailer for StdoutMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), MailError> {
+        println!("To: {}\nSubject: {}\n\n{}\n", message.to, message.subject, message.body);
+        Ok(())
+    }
+}
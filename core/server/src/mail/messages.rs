@@ -0,0 +1,67 @@
+use crate::app::AppConfig;
+use crate::mail::MailMessage;
+
+pub fn password_reset(config: &AppConfig, to: &str, token: &str) -> MailMessage {
+    MailMessage {
+        to: to.to_string(),
+        subject: "Reset your Nebula password".to_string(),
+        body: format!(
+            "Someone asked to reset the password of your Nebula account.\n\n\
+            Use the link below to choose a new one. It expires in one hour.\n\n\
+            {}/reset-password?token={token}\n\n\
+            If this wasn't you, you can ignore this email.",
+            config.public_url
+        ),
+    }
+}
+
+pub fn email_verification(config: &AppConfig, to: &str, token: &str) -> MailMessage {
+    MailMessage {
+        to: to.to_string(),
+        subject: "Verify your Nebula email".to_string(),
+        body: format!(
+            "Confirm this address for your Nebula account by opening the link below.\n\n\
+            {}/verify-email?token={token}",
+            config.public_url
+        ),
+    }
+}
+
+pub fn email_change(config: &AppConfig, to: &str, token: &str) -> MailMessage {
+    MailMessage {
+        to: to.to_string(),
+        subject: "Confirm your new Nebula email".to_string(),
+        body: format!(
+            "Someone asked to use this address for a Nebula account.\n\n\
+            Open the link below to confirm the change. It expires in 24 hours.\n\n\
+            {}/confirm-email-change?token={token}\n\n\
+            If this wasn't you, you can ignore this email.",
+            config.public_url
+        ),
+    }
+}
+
+pub fn email_changed_notice(to: &str, new_email: &str) -> MailMessage {
+    MailMessage {
+        to: to.to_string(),
+        subject: "Your Nebula email was changed".to_string(),
+        body: format!(
+            "The email of your Nebula account was changed to {new_email}.\n\n\
+            If this wasn't you, reset your password and contact support."
+        ),
+    }
+}
+
+
+pub fn realm_invite(config: &AppConfig, to: &str, inviter: &str, realm: &str, code: &str) -> MailMessage {
+    MailMessage {
+        to: to.to_string(),
+        subject: format!("{inviter} invited you to {realm} on Nebula"),
+        body: format!(
+            "{inviter} invited you to join the realm \"{realm}\" on Nebula.\n\n\
+            Sign in with this address and open the link below to accept.\n\n\
+            {}/invite/{code}",
+            config.public_url
+        ),
+    }
+}
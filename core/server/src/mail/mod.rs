@@ -0,0 +1,39 @@
+pub mod file;
+pub mod messages;
+pub mod smtp;
+
+use std::fmt::Debug;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::app::{AppConfig, MailerConfig};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+#[async_trait]
+pub trait Mailer: Send + Sync + Debug {
+    async fn send(&self, message: MailMessage) -> Result<(), MailError>;
+}
+
+pub type SharedMailer = Arc<dyn Mailer>;
+
+pub fn from_config(config: &AppConfig) -> SharedMailer {
+    match &config.mailer {
+        MailerConfig::Smtp { host, port, username, password } => Arc::new(
+            smtp::SmtpMailer::new(host, *port, username.clone(), password.clone(), &config.mail_from)
+        ),
+        MailerConfig::File { directory } => Arc::new(file::FileMailer::new(directory.clone())),
+        MailerConfig::Stdout => {
+            eprintln!("WARNING: MAILER is stdout. No mail is sent, and password reset and verification links are printed to the logs.");
+            Arc::new(file::StdoutMailer)
+        },
+    }
+}
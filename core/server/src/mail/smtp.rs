@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use crate::mail::{MailError, MailMessage, Mailer};
+
+#[derive(Debug)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: Option<String>,
+        password: Option<String>,
+        from: &str
+    ) -> Self {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .expect("Failed to configure SMTP relay")
+            .port(port);
+        if let (Some(username), Some(password)) = (username, password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Self {
+            transport: builder.build(),
+            from: from.parse().expect("Invalid MAIL_FROM address"),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), MailError> {
+        let to: Mailbox = message.to.parse()
+            .map_err(|err| MailError(format!("Invalid recipient: {err}")))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject)
+            .body(message.body)
+            .map_err(|err| MailError(err.to_string()))?;
+
+        self.transport.send(email)
+            .await
+            .map(|_| ())
+            .map_err(|err| MailError(err.to_string()))
+    }
+}
@@ -8,6 +8,7 @@ pub mod app;
 pub mod schema;
 pub mod cableway;
 pub mod database;
+pub mod mail;
 pub mod service;
 pub mod data;
 pub mod util;
@@ -23,11 +24,16 @@ pub async fn main() {
     ));
 
     let cableway_client = cableway::start(&config, &db).await;
+    let mailer = mail::from_config(&config);
+    if let Some(retention) = config.audit_log_retention {
+        tokio::spawn(service::audit::run_retention(db.clone(), retention));
+    }
     let app = NebulaApp {
         config,
         cableway: cableway_client,
         state,
-        db
+        db,
+        mailer
     };
 
     web::serve(app).await
@@ -0,0 +1,49 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "data_exports")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub user_id: Snowflake,
+    pub status: ExportStatus,
+    pub created_at: DateTimeUtc,
+    pub completed_at: Option<DateTimeUtc>,
+    #[serde(skip)]
+    #[sea_orm(column_type = "Blob", nullable)]
+    pub archive: Option<Vec<u8>>,
+}
+
+#[derive(EnumIter, DeriveActiveEnum, Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[sea_orm(rs_type = "i16", db_type = "SmallInteger")]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    #[sea_orm(num_value = 0)]
+    Pending,
+    #[sea_orm(num_value = 1)]
+    Ready,
+    #[sea_orm(num_value = 2)]
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,4 +2,19 @@ pub mod users;
 pub mod realms;
 pub mod realm_members;
 pub mod realm_events;
-pub mod realm_tasks;
\ No newline at end of file
+pub mod realm_tasks;
+pub mod refresh_tokens;
+pub mod sessions;
+pub mod user_tokens;
+pub mod user_identities;
+pub mod oidc_auth_requests;
+pub mod personal_access_tokens;
+pub mod data_exports;
+pub mod realm_invites;
+pub mod realm_roles;
+pub mod realm_member_roles;
+pub mod realm_audit_log;
+pub mod realm_share_links;
+pub mod realm_join_requests;
+pub mod realm_event_overrides;
+pub mod realm_calendar_feeds;
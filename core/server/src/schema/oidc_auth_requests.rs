@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+/// A pending authorization-code flow, looked up by the `state` parameter when
+/// the provider sends the user back.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "oidc_auth_requests")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub provider: String,
+    pub state_hash: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub link_user_id: Option<Snowflake>,
+    pub created_at: DateTimeUtc,
+    pub expires_at: DateTimeUtc,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::LinkUserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    LinkUser,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LinkUser.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
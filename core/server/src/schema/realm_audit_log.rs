@@ -0,0 +1,120 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "realm_audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    /// `None` once the acting account has been deleted.
+    pub actor_id: Option<Snowflake>,
+    pub action: AuditAction,
+    pub target_type: AuditTarget,
+    pub target_id: Option<Snowflake>,
+    /// Fields of the target that changed, as they were before and after.
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub before: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub after: Option<Json>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(EnumIter, DeriveActiveEnum, Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[sea_orm(rs_type = "i16", db_type = "SmallInteger")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    #[sea_orm(num_value = 0)]
+    Created,
+    #[sea_orm(num_value = 1)]
+    Updated,
+    #[sea_orm(num_value = 2)]
+    Deleted,
+    #[sea_orm(num_value = 3)]
+    Joined,
+    #[sea_orm(num_value = 4)]
+    Left,
+    #[sea_orm(num_value = 5)]
+    Kicked,
+    #[sea_orm(num_value = 6)]
+    Revoked,
+    #[sea_orm(num_value = 7)]
+    RoleAssigned,
+    #[sea_orm(num_value = 8)]
+    RoleUnassigned,
+    #[sea_orm(num_value = 9)]
+    TransferProposed,
+    #[sea_orm(num_value = 10)]
+    TransferCancelled,
+    #[sea_orm(num_value = 11)]
+    TransferDeclined,
+    #[sea_orm(num_value = 12)]
+    TransferAccepted,
+    #[sea_orm(num_value = 13)]
+    Archived,
+    #[sea_orm(num_value = 14)]
+    Restored,
+    #[sea_orm(num_value = 15)]
+    Approved,
+    #[sea_orm(num_value = 16)]
+    Rejected,
+}
+
+#[derive(EnumIter, DeriveActiveEnum, Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[sea_orm(rs_type = "i16", db_type = "SmallInteger")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditTarget {
+    #[sea_orm(num_value = 0)]
+    Realm,
+    #[sea_orm(num_value = 1)]
+    Event,
+    #[sea_orm(num_value = 2)]
+    Task,
+    #[sea_orm(num_value = 3)]
+    Member,
+    #[sea_orm(num_value = 4)]
+    Invite,
+    #[sea_orm(num_value = 5)]
+    Role,
+    #[sea_orm(num_value = 6)]
+    ShareLink,
+    #[sea_orm(num_value = 7)]
+    JoinRequest,
+    #[sea_orm(num_value = 8)]
+    EventOverride,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::realms::Entity",
+        from = "Column::RealmId",
+        to = "super::realms::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Realm,
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::ActorId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Actor,
+}
+
+impl Related<super::realms::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Realm.def()
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Actor.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+/// A member's secret calendar subscription URL, for apps that can't send an
+/// `Authorization` header. Only the token's hash is kept.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "realm_calendar_feeds")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub user_id: Snowflake,
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    pub created_at: DateTimeUtc,
+    pub last_used_at: Option<DateTimeUtc>,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::realms::Entity",
+        from = "Column::RealmId",
+        to = "super::realms::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Realm,
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+/// Replaces fields of one occurrence of a recurring event. Unset fields keep
+/// the series' value.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "realm_event_overrides")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub event_id: Snowflake,
+    /// Where the occurrence falls in the series, even if it was moved.
+    pub original_start: DateTimeUtc,
+    pub start_time: Option<DateTimeUtc>,
+    pub end_time: Option<DateTimeUtc>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::realm_events::Entity",
+        from = "Column::EventId",
+        to = "super::realm_events::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Event,
+}
+
+impl Related<super::realm_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Event.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,7 +2,7 @@ use sea_orm::EntityTrait;
 use sea_orm::PrimaryKeyTrait;
 use sea_orm::DerivePrimaryKey;
 use chrono::{DateTime, Utc};
-use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter, Related, RelationTrait};
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter, FromJsonQueryResult, Related, RelationTrait};
 use serde::{Deserialize, Serialize};
 use crate::data::snowflake::Snowflake;
 
@@ -18,9 +18,20 @@ pub struct Model {
     pub realm_id: Snowflake,
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
-    pub recurrence: Option<String>
+    pub recurrence: Option<String>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub exdates: Exdates,
+    /// IANA time zone the series recurs in, so it keeps its wall-clock time
+    /// across daylight saving changes.
+    pub timezone: String,
+    /// When the last occurrence ends, or `None` while the series has no end.
+    pub series_end: Option<DateTime<Utc>>,
 }
 
+/// Start times left out of a recurring event's series.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct Exdates(pub Vec<DateTime<Utc>>);
+
 #[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
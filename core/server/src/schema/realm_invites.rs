@@ -0,0 +1,64 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+/// An invite link, or a direct invite when `invitee_id` or `invitee_email` is set.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "realm_invites")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub created_by: Snowflake,
+    #[sea_orm(unique)]
+    pub code: String,
+    pub invitee_id: Option<Snowflake>,
+    pub invitee_email: Option<String>,
+    pub permissions: i16,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub created_at: DateTimeUtc,
+    pub expires_at: Option<DateTimeUtc>,
+    pub revoked_at: Option<DateTimeUtc>,
+}
+
+impl Model {
+    pub fn is_direct(&self) -> bool {
+        self.invitee_id.is_some() || self.invitee_email.is_some()
+    }
+
+    /// Whether the invite can still be accepted.
+    pub fn is_usable(&self) -> bool {
+        self.revoked_at.is_none()
+            && self.expires_at.is_none_or(|expires_at| expires_at > chrono::Utc::now())
+            && self.max_uses.is_none_or(|max_uses| self.uses < max_uses)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::realms::Entity",
+        from = "Column::RealmId",
+        to = "super::realms::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Realm,
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::CreatedBy",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Creator,
+}
+
+impl Related<super::realms::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Realm.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
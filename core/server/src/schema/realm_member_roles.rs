@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "realm_member_roles")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub member_id: Snowflake,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub role_id: Snowflake,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::realm_members::Entity",
+        from = "Column::MemberId",
+        to = "super::realm_members::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Member,
+    #[sea_orm(
+        belongs_to = "super::realm_roles::Entity",
+        from = "Column::RoleId",
+        to = "super::realm_roles::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Role,
+}
+
+impl Related<super::realm_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Member.def()
+    }
+}
+
+impl Related<super::realm_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Role.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,5 @@
 use sea_orm::PaginatorTrait;
-use sea_orm::{DatabaseConnection, DbErr, EntityTrait, QuerySelect, RelationTrait, ColumnTrait, QueryFilter};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QuerySelect, RelationTrait, ColumnTrait, QueryFilter};
 use sea_orm::{PrimaryKeyTrait, Related};
 use sea_orm::DerivePrimaryKey;
 use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
@@ -14,7 +14,10 @@ pub struct Model {
     pub id: Snowflake,
     pub realm_id: Snowflake,
     pub user_id: Snowflake,
-    pub permissions: i16
+    /// Granted on top of the member's roles.
+    pub permissions: i16,
+    /// Taken away from whatever the roles and `permissions` grant.
+    pub denied_permissions: i16
 }
 
 #[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
@@ -52,8 +55,8 @@ impl Related<super::users::Entity> for Entity {
 impl ActiveModelBehavior for ActiveModel {}
 
 impl Entity {
-    pub async fn find_membership(
-        db: &DatabaseConnection,
+    pub async fn find_membership<C: ConnectionTrait>(
+        db: &C,
         realm_id: Snowflake,
         user_id: Snowflake,
     ) -> Result<Option<Model>, DbErr> {
@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "realm_roles")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub name: String,
+    pub permissions: i16,
+    /// `0xRRGGBB`
+    pub colour: Option<i32>,
+    /// Display order, lowest first.
+    pub position: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::realms::Entity",
+        from = "Column::RealmId",
+        to = "super::realms::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Realm,
+    #[sea_orm(has_many = "super::realm_member_roles::Entity")]
+    MemberRoles,
+}
+
+impl Related<super::realms::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Realm.def()
+    }
+}
+
+impl Related<super::realm_member_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MemberRoles.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
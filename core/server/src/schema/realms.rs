@@ -10,6 +10,31 @@ pub struct Model {
     pub name: String,
     pub description: Option<String>,
     pub owner_id: Snowflake,
+    /// Member the owner offered the realm to, until they accept or decline.
+    pub pending_owner_id: Option<Snowflake>,
+    /// Set while the realm is archived and read-only.
+    pub archived_at: Option<DateTimeUtc>,
+    /// The realm this one is nested in. Its members can reach this realm too.
+    pub parent_id: Option<Snowflake>,
+    pub visibility: RealmVisibility,
+    /// IANA time zone new events in the realm recur in unless they pick one.
+    pub timezone: String,
+}
+
+#[derive(EnumIter, DeriveActiveEnum, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[sea_orm(rs_type = "i16", db_type = "SmallInteger")]
+#[serde(rename_all = "snake_case")]
+pub enum RealmVisibility {
+    /// Only reachable through invites.
+    #[default]
+    #[sea_orm(num_value = 0)]
+    Private,
+    /// Shows up in the directory, joining takes a request the realm approves.
+    #[sea_orm(num_value = 1)]
+    Listed,
+    /// Shows up in the directory and anyone can join.
+    #[sea_orm(num_value = 2)]
+    Open,
 }
 
 #[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
@@ -19,9 +44,17 @@ pub enum Relation {
         from = "Column::OwnerId",
         to = "super::users::Column::Id",
         on_update = "NoAction",
-        on_delete = "Cascade"
+        on_delete = "Restrict"
     )]
     Owner,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Parent,
     #[sea_orm(
         has_many = "super::realm_members::Entity",
         from = "Column::Id",
@@ -0,0 +1,49 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::snowflake::Snowflake;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Snowflake,
+    pub user_id: Snowflake,
+    pub purpose: Purpose,
+    pub token_hash: String,
+    pub created_at: DateTimeUtc,
+    pub expires_at: DateTimeUtc,
+    pub consumed_at: Option<DateTimeUtc>,
+    /// Extra data bound to the token, such as the address an email change targets.
+    pub payload: Option<String>,
+}
+
+#[derive(EnumIter, DeriveActiveEnum, Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[sea_orm(rs_type = "i16", db_type = "SmallInteger")]
+pub enum Purpose {
+    #[sea_orm(num_value = 0)]
+    PasswordReset,
+    #[sea_orm(num_value = 1)]
+    EmailVerification,
+    #[sea_orm(num_value = 2)]
+    EmailChange,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
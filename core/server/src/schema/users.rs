@@ -1,4 +1,5 @@
 use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
 use serde::{Deserialize, Serialize};
 use crate::data::snowflake::Snowflake;
 
@@ -9,10 +10,20 @@ pub struct Model {
     pub id: Snowflake,
     pub name: String,
     pub email: String,
-    pub password_hash: String,
+    pub password_hash: Option<String>,
+    pub email_verified: bool,
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub totp_recovery_codes: Option<RecoveryCodes>,
+    pub totp_last_step: Option<i64>,
     pub updated_at: chrono::NaiveDateTime,
 }
 
+/// Hashes of the recovery codes that have not been used yet.
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct RecoveryCodes(pub Vec<String>);
+
 #[derive(Clone, Debug, PartialEq, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::realms::Entity")]
@@ -0,0 +1,107 @@
+use crate::data::scopes::TokenScopes;
+use crate::data::snowflake::Snowflake;
+use crate::schema::{personal_access_tokens, users};
+use crate::service::snowflake::next_snowflake;
+use crate::util::token::{generate_token, hash_token};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+
+/// Prefix that tells personal access tokens apart from session JWTs.
+pub const TOKEN_PREFIX: &str = "nbp_";
+const LAST_USED_GRANULARITY_SECS: i64 = 60;
+
+pub struct CreatedAccessToken {
+    pub model: personal_access_tokens::Model,
+    pub token: String,
+}
+
+pub struct TokenAuthentication {
+    pub user: users::Model,
+    pub scopes: TokenScopes,
+}
+
+pub fn is_personal_access_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+pub async fn create_token(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    name: String,
+    scopes: TokenScopes,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>
+) -> Result<CreatedAccessToken, DbErr> {
+    let token = format!("{TOKEN_PREFIX}{}", generate_token());
+    let model = personal_access_tokens::ActiveModel {
+        id: Set(next_snowflake()),
+        user_id: Set(user_id),
+        name: Set(name),
+        token_hash: Set(hash_token(&token)),
+        scopes: Set(scopes.0),
+        created_at: Set(chrono::Utc::now()),
+        expires_at: Set(expires_at),
+        last_used_at: Set(None),
+    };
+    let model = model.insert(db).await?;
+    Ok(CreatedAccessToken { model, token })
+}
+
+pub async fn list_tokens(
+    db: &DatabaseConnection,
+    user_id: Snowflake
+) -> Result<Vec<personal_access_tokens::Model>, DbErr> {
+    personal_access_tokens::Entity::find()
+        .filter(personal_access_tokens::Column::UserId.eq(user_id))
+        .order_by_desc(personal_access_tokens::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+pub async fn delete_token(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    token_id: Snowflake
+) -> Result<bool, DbErr> {
+    let deleted = personal_access_tokens::Entity::delete_many()
+        .filter(personal_access_tokens::Column::Id.eq(token_id))
+        .filter(personal_access_tokens::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(deleted.rows_affected > 0)
+}
+
+/// Resolves a personal access token to its owner, recording when it was used.
+pub async fn authenticate(
+    db: &DatabaseConnection,
+    token: &str
+) -> Result<Option<TokenAuthentication>, DbErr> {
+    let access_token = personal_access_tokens::Entity::find()
+        .filter(personal_access_tokens::Column::TokenHash.eq(hash_token(token)))
+        .one(db)
+        .await?;
+    let Some(access_token) = access_token else {
+        return Ok(None);
+    };
+    let now = chrono::Utc::now();
+    if access_token.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Ok(None);
+    }
+
+    let recently_used = access_token.last_used_at
+        .is_some_and(|last_used| (now - last_used).num_seconds() < LAST_USED_GRANULARITY_SECS);
+    if !recently_used {
+        personal_access_tokens::Entity::update_many()
+            .col_expr(personal_access_tokens::Column::LastUsedAt, Expr::value(now))
+            .filter(personal_access_tokens::Column::Id.eq(access_token.id))
+            .exec(db)
+            .await?;
+    }
+
+    let user = users::Entity::find_by_id(access_token.user_id)
+        .one(db)
+        .await?;
+    Ok(user.map(|user| TokenAuthentication {
+        user,
+        scopes: TokenScopes::new(access_token.scopes),
+    }))
+}
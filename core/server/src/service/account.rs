@@ -0,0 +1,155 @@
+use crate::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realm_events, realm_members, realm_tasks, realms, users};
+use crate::service;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, DatabaseTransaction, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+/// What happens to realms owned by an account that is being deleted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnedRealmsAction {
+    /// Hand each realm over to its most privileged remaining member.
+    Transfer,
+    Delete,
+}
+
+#[derive(Debug)]
+pub enum DeleteAccountError {
+    /// The user owns realms other people are in and didn't say what to do with them.
+    SharedRealms,
+    Database(DbErr),
+}
+
+impl From<DbErr> for DeleteAccountError {
+    fn from(err: DbErr) -> Self {
+        DeleteAccountError::Database(err)
+    }
+}
+
+/// Deletes a user and everything tied only to them. Realms they own are
+/// transferred or deleted according to `action`, and whatever they authored in
+/// realms that survive is handed to the realm owner so it isn't lost with them.
+pub async fn delete_account(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    action: Option<OwnedRealmsAction>
+) -> Result<(), DeleteAccountError> {
+    let txn = db.begin().await?;
+
+    let owned_realms = realms::Entity::find()
+        .filter(realms::Column::OwnerId.eq(user_id))
+        .all(&txn)
+        .await?;
+    for realm in owned_realms {
+        let successor = find_successor(&txn, &realm, user_id).await?;
+        match (successor, action) {
+            (Some(successor), Some(OwnedRealmsAction::Transfer)) => {
+                transfer_realm(&txn, realm.id, successor).await?;
+            },
+            (Some(_), None) => return Err(DeleteAccountError::SharedRealms),
+            (None, _) | (Some(_), Some(OwnedRealmsAction::Delete)) => {
+                realms::Entity::delete_by_id(realm.id).exec(&txn).await?;
+            },
+        }
+    }
+
+    reassign_authored_content(&txn, user_id).await?;
+    users::Entity::delete_by_id(user_id).exec(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// The remaining member with the most authority once roles and denied bits
+/// are applied: realm managers first, then member managers, then whoever holds
+/// the most permissions. Ties go to the longest-standing member.
+async fn find_successor(
+    txn: &DatabaseTransaction,
+    realm: &realms::Model,
+    user_id: Snowflake
+) -> Result<Option<realm_members::Model>, DbErr> {
+    let members = realm_members::Entity::find()
+        .filter(realm_members::Column::RealmId.eq(realm.id))
+        .filter(realm_members::Column::UserId.ne(user_id))
+        .order_by_asc(realm_members::Column::Id)
+        .all(txn)
+        .await?;
+    let member_ids: Vec<Snowflake> = members.iter().map(|member| member.id).collect();
+    let roles = service::role::roles_by_member(txn, &member_ids).await?;
+
+    let rank = |member: &realm_members::Model| {
+        let member_roles = roles.get(&member.id).map_or(&[][..], Vec::as_slice);
+        let permissions = service::role::combine_permissions(realm, member, member_roles);
+        (
+            permissions.contains(RealmPermission::ManageRealm),
+            permissions.contains(RealmPermission::ManageMembers),
+            permissions.bits().count_ones(),
+        )
+    };
+    // `max_by_key` keeps the last of equal elements, so walk newest first.
+    Ok(members.into_iter().rev().max_by_key(rank))
+}
+
+async fn transfer_realm(
+    txn: &DatabaseTransaction,
+    realm_id: Snowflake,
+    successor: realm_members::Model
+) -> Result<(), DbErr> {
+    realms::Entity::update_many()
+        .col_expr(realms::Column::OwnerId, Expr::value(successor.user_id))
+        .col_expr(realms::Column::PendingOwnerId, Expr::value(Option::<Snowflake>::None))
+        .filter(realms::Column::Id.eq(realm_id))
+        .exec(txn)
+        .await?;
+    realm_members::Entity::update_many()
+        .col_expr(realm_members::Column::Permissions, Expr::value(RealmPermissions::all().bits()))
+        .filter(realm_members::Column::Id.eq(successor.id))
+        .exec(txn)
+        .await?;
+    Ok(())
+}
+
+async fn reassign_authored_content(
+    txn: &DatabaseTransaction,
+    user_id: Snowflake
+) -> Result<(), DbErr> {
+    let mut realm_ids: Vec<Snowflake> = realm_events::Entity::find()
+        .select_only()
+        .column(realm_events::Column::RealmId)
+        .filter(realm_events::Column::CreatedBy.eq(user_id))
+        .distinct()
+        .into_tuple()
+        .all(txn)
+        .await?;
+    let task_realm_ids: Vec<Snowflake> = realm_tasks::Entity::find()
+        .select_only()
+        .column(realm_tasks::Column::RealmId)
+        .filter(realm_tasks::Column::AuthorId.eq(user_id))
+        .distinct()
+        .into_tuple()
+        .all(txn)
+        .await?;
+    realm_ids.extend(task_realm_ids);
+    realm_ids.sort_by_key(|id| id.0);
+    realm_ids.dedup();
+
+    for realm_id in realm_ids {
+        let Some(realm) = realms::Entity::find_by_id(realm_id).one(txn).await? else {
+            continue;
+        };
+        realm_events::Entity::update_many()
+            .col_expr(realm_events::Column::CreatedBy, Expr::value(realm.owner_id))
+            .filter(realm_events::Column::RealmId.eq(realm.id))
+            .filter(realm_events::Column::CreatedBy.eq(user_id))
+            .exec(txn)
+            .await?;
+        realm_tasks::Entity::update_many()
+            .col_expr(realm_tasks::Column::AuthorId, Expr::value(realm.owner_id))
+            .filter(realm_tasks::Column::RealmId.eq(realm.id))
+            .filter(realm_tasks::Column::AuthorId.eq(user_id))
+            .exec(txn)
+            .await?;
+    }
+    Ok(())
+}
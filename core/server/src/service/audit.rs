@@ -0,0 +1,123 @@
+use std::time::Duration;
+use crate::data::snowflake::Snowflake;
+use crate::schema::realm_audit_log;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::snowflake::next_snowflake;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// How often entries older than the retention window are purged.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// A change to record. `before` and `after` take the target's DTO; only the
+/// fields that differ end up in the log.
+pub struct AuditEntry {
+    pub action: AuditAction,
+    pub target_type: AuditTarget,
+    pub target_id: Option<Snowflake>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, target_type: AuditTarget, target_id: Snowflake) -> Self {
+        AuditEntry { action, target_type, target_id: Some(target_id), before: None, after: None }
+    }
+
+    pub fn before<T: Serialize>(mut self, value: &T) -> Self {
+        self.before = Some(serde_json::to_value(value).expect("Audit snapshots must serialize"));
+        self
+    }
+
+    pub fn after<T: Serialize>(mut self, value: &T) -> Self {
+        self.after = Some(serde_json::to_value(value).expect("Audit snapshots must serialize"));
+        self
+    }
+}
+
+/// Strips the fields both snapshots agree on, so an update only keeps what changed.
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    let (Some(Value::Object(before)), Some(Value::Object(after))) = (&before, &after) else {
+        return (before, after);
+    };
+
+    let mut old = Map::new();
+    let mut new = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        let (from, to) = (before.get(key), after.get(key));
+        if from != to && !old.contains_key(key) && !new.contains_key(key) {
+            old.insert(key.clone(), from.cloned().unwrap_or(Value::Null));
+            new.insert(key.clone(), to.cloned().unwrap_or(Value::Null));
+        }
+    }
+    (Some(Value::Object(old)), Some(Value::Object(new)))
+}
+
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    realm_id: Snowflake,
+    actor_id: Snowflake,
+    entry: AuditEntry
+) -> Result<realm_audit_log::Model, DbErr> {
+    let (before, after) = diff(entry.before, entry.after);
+    let model = realm_audit_log::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm_id),
+        actor_id: Set(Some(actor_id)),
+        action: Set(entry.action),
+        target_type: Set(entry.target_type),
+        target_id: Set(entry.target_id),
+        before: Set(before),
+        after: Set(after),
+        created_at: Set(Utc::now()),
+    };
+    model.insert(db).await
+}
+
+/// A page of the realm's log, newest first. `before` is the id of the last
+/// entry of the previous page.
+pub async fn list_entries(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    before: Option<Snowflake>,
+    limit: u64,
+    retention: Option<chrono::Duration>
+) -> Result<Vec<realm_audit_log::Model>, DbErr> {
+    let mut query = realm_audit_log::Entity::find()
+        .filter(realm_audit_log::Column::RealmId.eq(realm_id));
+    if let Some(before) = before {
+        query = query.filter(realm_audit_log::Column::Id.lt(before));
+    }
+    // Expired entries may still be waiting for the next purge.
+    if let Some(retention) = retention {
+        query = query.filter(realm_audit_log::Column::CreatedAt.gte(Utc::now() - retention));
+    }
+    query
+        .order_by_desc(realm_audit_log::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await
+}
+
+pub async fn purge_expired(db: &DatabaseConnection, retention: chrono::Duration) -> Result<u64, DbErr> {
+    let result = realm_audit_log::Entity::delete_many()
+        .filter(realm_audit_log::Column::CreatedAt.lt(Utc::now() - retention))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Runs for the lifetime of the server, dropping entries past the retention window.
+pub async fn run_retention(db: DatabaseConnection, retention: chrono::Duration) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match purge_expired(&db, retention).await {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!("Purged {} expired audit log entries", purged),
+            Err(err) => tracing::error!("Failed to purge audit log: {:?}", err),
+        }
+    }
+}
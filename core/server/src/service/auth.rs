@@ -1,33 +1,144 @@
 use crate::app::AppConfig;
-use crate::schema::users;
-use jwt::VerifyWithKey;
-use sea_orm::{DatabaseConnection, EntityTrait};
-use std::collections::BTreeMap;
+use crate::data::snowflake::Snowflake;
+use crate::schema::{refresh_tokens, sessions, users};
+use crate::service;
+use crate::service::snowflake::next_snowflake;
+use crate::util::token::{generate_token, hash_token};
+use jwt::{SignWithKey, VerifyWithKey};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccessTokenClaims {
+    pub user_id: String,
+    pub sid: String,
+    pub iat: i64,
+    pub exp: i64,
+    pub jti: String,
+}
+
+const TWO_FACTOR_CHALLENGE_PURPOSE: &str = "2fa";
+const TWO_FACTOR_CHALLENGE_TTL_SECS: i64 = 300;
+
+/// Claims of the token handed out after a correct password when the account
+/// still needs a second factor. It can't be used as an access token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TwoFactorChallengeClaims {
+    pub user_id: String,
+    pub purpose: String,
+    pub exp: i64,
+    pub jti: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Authentication {
+    pub user: users::Model,
+    pub session: sessions::Model,
+}
+
+pub struct IssuedRefreshToken {
+    pub user_id: Snowflake,
+    pub family_id: Snowflake,
+    pub token: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug)]
+pub enum RefreshError {
+    Invalid,
+    Reused,
+    Database(DbErr),
+}
+
+impl From<DbErr> for RefreshError {
+    fn from(err: DbErr) -> Self {
+        RefreshError::Database(err)
+    }
+}
+
+pub fn sign_access_token(
+    config: &AppConfig,
+    user_id: Snowflake,
+    session_id: Snowflake
+) -> (String, chrono::DateTime<chrono::Utc>) {
+    let issued_at = chrono::Utc::now();
+    let expires_at = issued_at + config.access_token_ttl;
+    let claims = AccessTokenClaims {
+        user_id: user_id.to_string(),
+        sid: session_id.to_string(),
+        iat: issued_at.timestamp(),
+        exp: expires_at.timestamp(),
+        jti: next_snowflake().to_string(),
+    };
+    let token = claims.sign_with_key(&config.jwt_key)
+        .expect("Failed to sign JWT");
+    (token, expires_at)
+}
+
+pub fn sign_two_factor_challenge(
+    config: &AppConfig,
+    user_id: Snowflake
+) -> (String, chrono::DateTime<chrono::Utc>) {
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(TWO_FACTOR_CHALLENGE_TTL_SECS);
+    let claims = TwoFactorChallengeClaims {
+        user_id: user_id.to_string(),
+        purpose: TWO_FACTOR_CHALLENGE_PURPOSE.to_string(),
+        exp: expires_at.timestamp(),
+        jti: next_snowflake().to_string(),
+    };
+    let token = claims.sign_with_key(&config.jwt_key)
+        .expect("Failed to sign JWT");
+    (token, expires_at)
+}
+
+pub fn verify_two_factor_challenge(config: &AppConfig, token: &str) -> Option<Snowflake> {
+    let claims: TwoFactorChallengeClaims = token.verify_with_key(&config.jwt_key).ok()?;
+    if claims.purpose != TWO_FACTOR_CHALLENGE_PURPOSE || claims.exp <= chrono::Utc::now().timestamp() {
+        return None;
+    }
+    claims.user_id.parse::<u64>().ok().map(Snowflake)
+}
 
 pub async fn authenticate(
     config: &AppConfig,
     db: &DatabaseConnection,
     token: String
-) -> Result<users::Model, ()> {
-    let claims: Result<BTreeMap<String, String>, jwt::error::Error> = token
+) -> Result<Authentication, ()> {
+    let claims: Result<AccessTokenClaims, jwt::error::Error> = token
         .verify_with_key(&config.jwt_key);
     if claims.is_err() {
         return Err(());
     }
     let claims = claims.unwrap();
+    if claims.exp <= chrono::Utc::now().timestamp() {
+        return Err(());
+    }
 
-    let user_id_str: Option<&String> = claims.get("user_id");
-    if user_id_str.is_none() {
+    let user_id = claims.user_id.parse::<u64>();
+    let session_id = claims.sid.parse::<u64>();
+    if user_id.is_err() || session_id.is_err() {
+        return Err(());
+    }
+    let user_id = Snowflake(user_id.unwrap());
+
+    let session = service::session::find_active_session(db, Snowflake(session_id.unwrap()), user_id).await;
+    if session.is_err() {
+        return Err(());
+    }
+    let session = session.unwrap();
+    if session.is_none() {
         return Err(());
     }
-    let user_id = user_id_str.unwrap().parse::<u64>();
-    if user_id.is_err() {
+    let session = session.unwrap();
+    if service::session::touch_session(db, &session).await.is_err() {
         return Err(());
     }
-    let user_query = users::Entity::find_by_id(user_id.unwrap())
+
+    let user_query = users::Entity::find_by_id(user_id)
         .one(db)
         .await;
-    
+
     if user_query.is_err() {
         return Err(());
     }
@@ -35,5 +146,105 @@ pub async fn authenticate(
     if user.is_none() {
         return Err(());
     }
-    Ok(user.unwrap())
-}
\ No newline at end of file
+    Ok(Authentication {
+        user: user.unwrap(),
+        session,
+    })
+}
+
+pub async fn issue_refresh_token(
+    config: &AppConfig,
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    family_id: Snowflake
+) -> Result<IssuedRefreshToken, DbErr> {
+    insert_refresh_token(config, db, next_snowflake(), user_id, family_id).await
+}
+
+/// Exchanges a refresh token for a new one in the same family. Presenting a token
+/// that was already rotated is treated as theft and revokes the whole session.
+pub async fn rotate_refresh_token(
+    config: &AppConfig,
+    db: &DatabaseConnection,
+    token: &str
+) -> Result<IssuedRefreshToken, RefreshError> {
+    let existing = refresh_tokens::Entity::find()
+        .filter(refresh_tokens::Column::TokenHash.eq(hash_token(token)))
+        .one(db)
+        .await?;
+    let Some(existing) = existing else {
+        return Err(RefreshError::Invalid);
+    };
+
+    if existing.replaced_by.is_some() {
+        service::session::revoke_session(db, existing.family_id).await?;
+        return Err(RefreshError::Reused);
+    }
+    if existing.revoked_at.is_some() || existing.expires_at <= chrono::Utc::now() {
+        return Err(RefreshError::Invalid);
+    }
+
+    let replacement_id = next_snowflake();
+    let claimed = refresh_tokens::Entity::update_many()
+        .col_expr(refresh_tokens::Column::ReplacedBy, Expr::value(replacement_id))
+        .filter(refresh_tokens::Column::Id.eq(existing.id))
+        .filter(refresh_tokens::Column::ReplacedBy.is_null())
+        .filter(refresh_tokens::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    if claimed.rows_affected == 0 {
+        service::session::revoke_session(db, existing.family_id).await?;
+        return Err(RefreshError::Reused);
+    }
+
+    let issued = insert_refresh_token(
+        config,
+        db,
+        replacement_id,
+        existing.user_id,
+        existing.family_id
+    ).await?;
+    Ok(issued)
+}
+
+pub async fn revoke_refresh_family(
+    db: &DatabaseConnection,
+    family_id: Snowflake
+) -> Result<(), DbErr> {
+    refresh_tokens::Entity::update_many()
+        .col_expr(refresh_tokens::Column::RevokedAt, Expr::value(chrono::Utc::now()))
+        .filter(refresh_tokens::Column::FamilyId.eq(family_id))
+        .filter(refresh_tokens::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+async fn insert_refresh_token(
+    config: &AppConfig,
+    db: &DatabaseConnection,
+    id: Snowflake,
+    user_id: Snowflake,
+    family_id: Snowflake
+) -> Result<IssuedRefreshToken, DbErr> {
+    let token = generate_token();
+    let now = chrono::Utc::now();
+    let expires_at = now + config.refresh_token_ttl;
+    let model = refresh_tokens::ActiveModel {
+        id: Set(id),
+        user_id: Set(user_id),
+        family_id: Set(family_id),
+        token_hash: Set(hash_token(&token)),
+        replaced_by: Set(None),
+        created_at: Set(now),
+        expires_at: Set(expires_at),
+        revoked_at: Set(None),
+    };
+    model.insert(db).await?;
+    Ok(IssuedRefreshToken {
+        user_id,
+        family_id,
+        token,
+        expires_at,
+    })
+}
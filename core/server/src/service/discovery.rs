@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use crate::data::permissions::{BitwisePermissions, RealmPermissions};
+use crate::data::snowflake::Snowflake;
+use crate::schema::realms::RealmVisibility;
+use crate::schema::{realm_join_requests, realm_members, realms, users};
+use crate::service::snowflake::next_snowflake;
+use sea_orm::sea_query::extension::postgres::PgExpr;
+use sea_orm::sea_query::{Expr, ExprTrait};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait};
+
+#[derive(Debug)]
+pub enum JoinError {
+    /// The realm doesn't exist, is private or archived.
+    NotFound,
+    /// Listed realms take a join request instead.
+    NotOpen,
+    /// Open realms can be joined directly.
+    NotListed,
+    AlreadyMember,
+    AlreadyRequested,
+    Database(DbErr),
+}
+
+impl From<DbErr> for JoinError {
+    fn from(err: DbErr) -> Self {
+        JoinError::Database(err)
+    }
+}
+
+/// `%` and `_` in the search are matched literally.
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Listed and open realms, oldest first, optionally matching `search` in their
+/// name or description.
+pub async fn discover(
+    db: &DatabaseConnection,
+    search: Option<&str>,
+    after: Option<Snowflake>,
+    limit: u64
+) -> Result<Vec<realms::Model>, DbErr> {
+    let mut query = realms::Entity::find()
+        .filter(realms::Column::Visibility.ne(RealmVisibility::Private))
+        .filter(realms::Column::ArchivedAt.is_null());
+    if let Some(search) = search.map(str::trim).filter(|search| !search.is_empty()) {
+        let pattern = like_pattern(search);
+        query = query.filter(
+            Expr::col(realms::Column::Name).ilike(pattern.as_str())
+                .or(Expr::col(realms::Column::Description).ilike(pattern.as_str()))
+        );
+    }
+    if let Some(after) = after {
+        query = query.filter(realms::Column::Id.gt(after));
+    }
+    query
+        .order_by_asc(realms::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await
+}
+
+pub async fn member_counts(
+    db: &DatabaseConnection,
+    realm_ids: &[Snowflake]
+) -> Result<HashMap<Snowflake, u64>, DbErr> {
+    let counts: Vec<(Snowflake, i64)> = realm_members::Entity::find()
+        .select_only()
+        .column(realm_members::Column::RealmId)
+        .column_as(realm_members::Column::Id.count(), "count")
+        .filter(realm_members::Column::RealmId.is_in(realm_ids.iter().copied()))
+        .group_by(realm_members::Column::RealmId)
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(counts.into_iter().map(|(realm_id, count)| (realm_id, count as u64)).collect())
+}
+
+/// A realm that outsiders can see: listed or open, and not archived.
+pub async fn find_discoverable(
+    db: &DatabaseConnection,
+    realm_id: Snowflake
+) -> Result<realms::Model, JoinError> {
+    realms::Entity::find_by_id(realm_id)
+        .one(db)
+        .await?
+        .filter(|realm| realm.visibility != RealmVisibility::Private && realm.archived_at.is_none())
+        .ok_or(JoinError::NotFound)
+}
+
+/// Members of a parent realm already have access, so they can't join or ask to.
+async fn ensure_outsider(
+    db: &DatabaseConnection,
+    realm: &realms::Model,
+    user_id: Snowflake
+) -> Result<(), JoinError> {
+    match crate::service::realm::resolve_access(db, realm, user_id).await? {
+        Some(_) => Err(JoinError::AlreadyMember),
+        None => Ok(()),
+    }
+}
+
+fn new_membership(realm_id: Snowflake, user_id: Snowflake) -> realm_members::ActiveModel {
+    realm_members::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm_id),
+        user_id: Set(user_id),
+        permissions: Set(RealmPermissions::MEMBER_DEFAULT.bits()),
+        denied_permissions: Set(0),
+    }
+}
+
+/// Joins an open realm with the default member permissions.
+pub async fn join_open_realm(
+    db: &DatabaseConnection,
+    realm: &realms::Model,
+    user_id: Snowflake
+) -> Result<realm_members::Model, JoinError> {
+    if realm.visibility != RealmVisibility::Open {
+        return Err(JoinError::NotOpen);
+    }
+    ensure_outsider(db, realm, user_id).await?;
+
+    let txn = db.begin().await?;
+    // A request made while the realm was still only listed isn't needed anymore.
+    realm_join_requests::Entity::delete_many()
+        .filter(realm_join_requests::Column::RealmId.eq(realm.id))
+        .filter(realm_join_requests::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    let membership = new_membership(realm.id, user_id).insert(&txn).await?;
+    txn.commit().await?;
+    Ok(membership)
+}
+
+pub async fn request_to_join(
+    db: &DatabaseConnection,
+    realm: &realms::Model,
+    user_id: Snowflake,
+    message: Option<String>
+) -> Result<realm_join_requests::Model, JoinError> {
+    if realm.visibility != RealmVisibility::Listed {
+        return Err(JoinError::NotListed);
+    }
+    ensure_outsider(db, realm, user_id).await?;
+    if find_request_by_user(db, realm.id, user_id).await?.is_some() {
+        return Err(JoinError::AlreadyRequested);
+    }
+
+    let request = realm_join_requests::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm.id),
+        user_id: Set(user_id),
+        message: Set(message),
+        created_at: Set(chrono::Utc::now()),
+    };
+    Ok(request.insert(db).await?)
+}
+
+pub async fn find_request_by_user(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    user_id: Snowflake
+) -> Result<Option<realm_join_requests::Model>, DbErr> {
+    realm_join_requests::Entity::find()
+        .filter(realm_join_requests::Column::RealmId.eq(realm_id))
+        .filter(realm_join_requests::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+}
+
+pub async fn find_request(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    request_id: Snowflake
+) -> Result<Option<(realm_join_requests::Model, users::Model)>, DbErr> {
+    let request = realm_join_requests::Entity::find_by_id(request_id)
+        .filter(realm_join_requests::Column::RealmId.eq(realm_id))
+        .find_also_related(users::Entity)
+        .one(db)
+        .await?;
+    Ok(request.and_then(|(request, user)| user.map(|user| (request, user))))
+}
+
+/// Pending requests with the user behind each, oldest first.
+pub async fn list_requests(
+    db: &DatabaseConnection,
+    realm_id: Snowflake
+) -> Result<Vec<(realm_join_requests::Model, users::Model)>, DbErr> {
+    let requests = realm_join_requests::Entity::find()
+        .filter(realm_join_requests::Column::RealmId.eq(realm_id))
+        .find_also_related(users::Entity)
+        .order_by_asc(realm_join_requests::Column::Id)
+        .all(db)
+        .await?;
+    Ok(requests.into_iter()
+        .filter_map(|(request, user)| user.map(|user| (request, user)))
+        .collect())
+}
+
+/// Lets the requester in with the default member permissions.
+pub async fn approve_request(
+    db: &DatabaseConnection,
+    request: &realm_join_requests::Model
+) -> Result<realm_members::Model, JoinError> {
+    let txn = db.begin().await?;
+    realm_join_requests::Entity::delete_by_id(request.id).exec(&txn).await?;
+    // They may have been invited in the meantime.
+    if realm_members::Entity::find_membership(&txn, request.realm_id, request.user_id).await?.is_some() {
+        txn.commit().await?;
+        return Err(JoinError::AlreadyMember);
+    }
+    let membership = new_membership(request.realm_id, request.user_id).insert(&txn).await?;
+    txn.commit().await?;
+    Ok(membership)
+}
+
+/// Rejecting and withdrawing both just drop the request.
+pub async fn remove_request(
+    db: &DatabaseConnection,
+    request: &realm_join_requests::Model
+) -> Result<(), DbErr> {
+    realm_join_requests::Entity::delete_by_id(request.id).exec(db).await?;
+    Ok(())
+}
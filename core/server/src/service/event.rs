@@ -0,0 +1,436 @@
+use std::str::FromStr;
+use chrono::{DateTime, Duration, Utc};
+use rrule::{RRule, Tz};
+use crate::data::snowflake::Snowflake;
+use std::collections::HashMap;
+use crate::schema::{realm_event_overrides, realm_events};
+use crate::schema::realm_events::Exdates;
+use crate::service::snowflake::next_snowflake;
+use sea_orm::sea_query::{Expr, ExprTrait};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait, TryIntoModel};
+
+/// Fields of an event to change. Nested options can clear the field.
+#[derive(Clone, Debug, Default)]
+pub struct EventChanges {
+    pub name: Option<String>,
+    pub description: Option<Option<String>>,
+    pub location: Option<Option<String>>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<Option<DateTime<Utc>>>,
+    pub timezone: Option<String>,
+    /// Encoded `RRULE` value, without the `RRULE:` prefix.
+    pub recurrence: Option<Option<String>>,
+}
+
+#[derive(Debug)]
+pub enum EventEditError {
+    /// The given start time isn't one of the series' occurrences.
+    NotAnOccurrence,
+    /// The stored or requested rule can't be expanded.
+    InvalidRule,
+    Database(DbErr),
+}
+
+impl From<DbErr> for EventEditError {
+    fn from(err: DbErr) -> Self {
+        EventEditError::Database(err)
+    }
+}
+
+fn apply(active: &mut realm_events::ActiveModel, changes: EventChanges) {
+    if let Some(name) = changes.name {
+        active.name = Set(name);
+    }
+    if let Some(description) = changes.description {
+        active.description = Set(description);
+    }
+    if let Some(location) = changes.location {
+        active.location = Set(location);
+    }
+    if let Some(start_time) = changes.start_time {
+        active.start_time = Set(start_time);
+    }
+    if let Some(end_time) = changes.end_time {
+        active.end_time = Set(end_time);
+    }
+    if let Some(recurrence) = changes.recurrence {
+        active.recurrence = Set(recurrence);
+    }
+    if let Some(timezone) = changes.timezone {
+        active.timezone = Set(timezone);
+    }
+}
+
+/// When the event's last occurrence ends, or `None` while the series has no
+/// end. Overrides count too, since they can move an occurrence past the
+/// rule's last one. Rules that can't be expanded count as endless, so
+/// schedules still find them and can report them.
+pub fn series_end(event: &realm_events::Model, overrides: &[realm_event_overrides::Model]) -> Option<DateTime<Utc>> {
+    let duration = event.end_time.map_or(Duration::zero(), |end| end - event.start_time).max(Duration::zero());
+    let rule_end = match &event.recurrence {
+        None => event.start_time + duration,
+        Some(encoded) => {
+            let rule = RRule::from_str(encoded).ok()?;
+            if rule.get_count().is_none() && rule.get_until().is_none() {
+                return None;
+            }
+            let result = rule.build(event.start_time.with_timezone(&timezone_of(event))).ok()?.all(u16::MAX);
+            if result.limited {
+                return None;
+            }
+            result.dates.last().map(|last| last.with_timezone(&Utc) + duration)?
+        },
+    };
+    let override_ends = overrides.iter().map(|change| {
+        let start = change.start_time.unwrap_or(change.original_start);
+        change.end_time.unwrap_or(start + duration).max(start)
+    });
+    override_ends.chain(std::iter::once(rule_end)).max()
+}
+
+/// Recomputes `series_end` once the fields it depends on are all set.
+pub fn refresh_series_end(active: &mut realm_events::ActiveModel, overrides: &[realm_event_overrides::Model]) {
+    if let Ok(event) = active.clone().try_into_model() {
+        active.series_end = Set(series_end(&event, overrides));
+    }
+}
+
+/// Stores the event's `series_end` again after its overrides changed.
+async fn sync_series_end<C: ConnectionTrait>(db: &C, event_id: Snowflake) -> Result<(), DbErr> {
+    let Some(event) = realm_events::Entity::find_by_id(event_id).one(db).await? else {
+        return Ok(());
+    };
+    let overrides = overrides_by_event(db, &[event_id]).await?.remove(&event_id).unwrap_or_default();
+    let end = series_end(&event, &overrides);
+    if end != event.series_end {
+        realm_events::Entity::update_many()
+            .col_expr(realm_events::Column::SeriesEnd, Expr::value(end))
+            .filter(realm_events::Column::Id.eq(event_id))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// The zone an event recurs in. Names are validated on the way in, so UTC is
+/// only a fallback for rows that somehow aren't.
+pub fn timezone_of(event: &realm_events::Model) -> Tz {
+    event.timezone.parse::<chrono_tz::Tz>().map(Tz::Tz).unwrap_or(Tz::UTC)
+}
+
+/// Edits the event itself, which for a recurring event means every occurrence.
+/// Moving the series or changing its rule drops its exceptions, as they'd no
+/// longer line up with its occurrences.
+pub async fn update_event(
+    db: &DatabaseConnection,
+    event: realm_events::Model,
+    changes: EventChanges
+) -> Result<realm_events::Model, DbErr> {
+    let shifted = changes.start_time.is_some_and(|start| start != event.start_time)
+        || changes.recurrence.as_ref().is_some_and(|rule| *rule != event.recurrence)
+        || changes.timezone.as_ref().is_some_and(|timezone| *timezone != event.timezone);
+    let overrides = match shifted {
+        true => vec![],
+        false => overrides_by_event(db, &[event.id]).await?.remove(&event.id).unwrap_or_default(),
+    };
+    let event_id = event.id;
+    let mut active = event.into_active_model();
+    apply(&mut active, changes);
+    if shifted {
+        active.exdates = Set(Exdates::default());
+    }
+    refresh_series_end(&mut active, &overrides);
+
+    let txn = db.begin().await?;
+    if shifted {
+        realm_event_overrides::Entity::delete_many()
+            .filter(realm_event_overrides::Column::EventId.eq(event_id))
+            .exec(&txn)
+            .await?;
+    }
+    let event = active.update(&txn).await?;
+    txn.commit().await?;
+    Ok(event)
+}
+
+/// Occurrences of the series up to and including `until`, ignoring its
+/// exdates, since those don't use up a `COUNT`.
+fn occurrences_until(event: &realm_events::Model, until: DateTime<Utc>) -> Result<Vec<DateTime<Utc>>, EventEditError> {
+    let Some(encoded) = &event.recurrence else {
+        return Ok(vec![event.start_time]);
+    };
+    let rule = RRule::from_str(encoded).map_err(|_| EventEditError::InvalidRule)?;
+    let set = rule
+        .build(event.start_time.with_timezone(&timezone_of(event)))
+        .map_err(|_| EventEditError::InvalidRule)?;
+    Ok(set
+        .before(until.with_timezone(&timezone_of(event)))
+        .all(u16::MAX)
+        .dates
+        .into_iter()
+        .map(|date| date.with_timezone(&Utc))
+        .collect())
+}
+
+/// How many occurrences the series has before `occurrence`, which has to be
+/// one of them.
+fn position_of(event: &realm_events::Model, occurrence: DateTime<Utc>) -> Result<usize, EventEditError> {
+    let occurrences = occurrences_until(event, occurrence)?;
+    let excluded = event.exdates.0.contains(&occurrence);
+    match occurrences.last() {
+        Some(last) if *last == occurrence && !excluded => Ok(occurrences.len() - 1),
+        _ => Err(EventEditError::NotAnOccurrence),
+    }
+}
+
+/// Whether `at` is a start time the series produces, cancelled or not.
+pub fn is_occurrence(event: &realm_events::Model, at: DateTime<Utc>) -> bool {
+    occurrences_until(event, at).is_ok_and(|occurrences| occurrences.last() == Some(&at))
+}
+
+/// Replaces the rule's `COUNT` and `UNTIL` parts with `end`.
+fn with_end(rule: &str, end: &str) -> String {
+    rule.split(';')
+        .filter(|part| !part.starts_with("COUNT=") && !part.starts_with("UNTIL="))
+        .chain(std::iter::once(end))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn count_of(rule: &str) -> Option<usize> {
+    rule.split(';')
+        .find_map(|part| part.strip_prefix("COUNT="))
+        .and_then(|count| count.parse().ok())
+}
+
+/// A copy of the series moved to `start`, as the base for the second half of
+/// a split.
+fn copy_of(event: &realm_events::Model, start: DateTime<Utc>) -> realm_events::ActiveModel {
+    let duration: Option<Duration> = event.end_time.map(|end| end - event.start_time);
+    realm_events::ActiveModel {
+        id: Set(next_snowflake()),
+        name: Set(event.name.clone()),
+        description: Set(event.description.clone()),
+        location: Set(event.location.clone()),
+        created_by: Set(event.created_by),
+        realm_id: Set(event.realm_id),
+        start_time: Set(start),
+        end_time: Set(duration.map(|duration| start + duration)),
+        recurrence: Set(None),
+        exdates: Set(Exdates::default()),
+        timezone: Set(event.timezone.clone()),
+        series_end: Set(None),
+    }
+}
+
+/// Edits one occurrence of a series through its override, creating it the
+/// first time. Clearing a field hands it back to the series.
+pub async fn override_occurrence(
+    db: &DatabaseConnection,
+    event: &realm_events::Model,
+    occurrence: DateTime<Utc>,
+    changes: EventChanges
+) -> Result<realm_event_overrides::Model, EventEditError> {
+    position_of(event, occurrence)?;
+
+    let existing = realm_event_overrides::Entity::find()
+        .filter(realm_event_overrides::Column::EventId.eq(event.id))
+        .filter(realm_event_overrides::Column::OriginalStart.eq(occurrence))
+        .one(db)
+        .await?;
+    let exists = existing.is_some();
+    let mut active = match existing {
+        Some(existing) => existing.into_active_model(),
+        None => realm_event_overrides::ActiveModel {
+            id: Set(next_snowflake()),
+            event_id: Set(event.id),
+            original_start: Set(occurrence),
+            start_time: Set(None),
+            end_time: Set(None),
+            name: Set(None),
+            description: Set(None),
+            location: Set(None),
+        },
+    };
+    if let Some(name) = changes.name {
+        active.name = Set(Some(name));
+    }
+    if let Some(description) = changes.description {
+        active.description = Set(description);
+    }
+    if let Some(location) = changes.location {
+        active.location = Set(location);
+    }
+    if let Some(start_time) = changes.start_time {
+        active.start_time = Set(Some(start_time));
+    }
+    if let Some(end_time) = changes.end_time {
+        active.end_time = Set(end_time);
+    }
+    let txn = db.begin().await?;
+    let change = if exists {
+        active.update(&txn).await?
+    } else {
+        active.insert(&txn).await?
+    };
+    sync_series_end(&txn, event.id).await?;
+    txn.commit().await?;
+    Ok(change)
+}
+
+/// Overrides of many events at once, keyed by event id.
+pub async fn overrides_by_event<C: ConnectionTrait>(
+    db: &C,
+    event_ids: &[Snowflake]
+) -> Result<HashMap<Snowflake, Vec<realm_event_overrides::Model>>, DbErr> {
+    let overrides = realm_event_overrides::Entity::find()
+        .filter(realm_event_overrides::Column::EventId.is_in(event_ids.iter().copied()))
+        .order_by_asc(realm_event_overrides::Column::OriginalStart)
+        .all(db)
+        .await?;
+    let mut by_event: HashMap<Snowflake, Vec<realm_event_overrides::Model>> = HashMap::new();
+    for change in overrides {
+        by_event.entry(change.event_id).or_default().push(change);
+    }
+    Ok(by_event)
+}
+
+pub async fn find_override(
+    db: &DatabaseConnection,
+    event_id: Snowflake,
+    override_id: Snowflake
+) -> Result<Option<realm_event_overrides::Model>, DbErr> {
+    realm_event_overrides::Entity::find_by_id(override_id)
+        .filter(realm_event_overrides::Column::EventId.eq(event_id))
+        .one(db)
+        .await
+}
+
+pub async fn delete_override(
+    db: &DatabaseConnection,
+    change: realm_event_overrides::Model
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    realm_event_overrides::Entity::delete_by_id(change.id).exec(&txn).await?;
+    sync_series_end(&txn, change.event_id).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Leaves one occurrence out of the series with an `EXDATE`, dropping any
+/// override it had.
+pub async fn cancel_occurrence(
+    db: &DatabaseConnection,
+    event: realm_events::Model,
+    occurrence: DateTime<Utc>
+) -> Result<realm_events::Model, EventEditError> {
+    position_of(&event, occurrence)?;
+
+    let mut exdates = event.exdates.0.clone();
+    exdates.push(occurrence);
+    exdates.sort();
+    let remaining: Vec<_> = overrides_by_event(db, &[event.id])
+        .await?
+        .remove(&event.id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|change| change.original_start != occurrence)
+        .collect();
+    let mut active = event.into_active_model();
+    active.exdates = Set(Exdates(exdates));
+    refresh_series_end(&mut active, &remaining);
+
+    let txn = db.begin().await?;
+    realm_event_overrides::Entity::delete_many()
+        .filter(realm_event_overrides::Column::EventId.eq(*active.id.as_ref()))
+        .filter(realm_event_overrides::Column::OriginalStart.eq(occurrence))
+        .exec(&txn)
+        .await?;
+    let event = active.update(&txn).await?;
+    txn.commit().await?;
+    Ok(event)
+}
+
+/// Brings a cancelled occurrence back.
+pub async fn restore_occurrence(
+    db: &DatabaseConnection,
+    event: realm_events::Model,
+    occurrence: DateTime<Utc>
+) -> Result<realm_events::Model, EventEditError> {
+    if !event.exdates.0.contains(&occurrence) {
+        return Err(EventEditError::NotAnOccurrence);
+    }
+    let exdates = event.exdates.0.iter().copied().filter(|exdate| *exdate != occurrence).collect();
+    let mut active = event.into_active_model();
+    active.exdates = Set(Exdates(exdates));
+    Ok(active.update(db).await?)
+}
+
+/// Edits an occurrence and everything after it: the series ends right before
+/// it, and a new series picks up from there with the changes. Returns the new
+/// series and the shortened one.
+pub async fn split_series(
+    db: &DatabaseConnection,
+    event: realm_events::Model,
+    occurrence: DateTime<Utc>,
+    changes: EventChanges
+) -> Result<(realm_events::Model, realm_events::Model), EventEditError> {
+    let position = position_of(&event, occurrence)?;
+    let rule = event.recurrence.clone().ok_or(EventEditError::NotAnOccurrence)?;
+
+    // A counted series keeps its total across both halves.
+    let remaining_rule = match count_of(&rule) {
+        Some(count) => with_end(&rule, &format!("COUNT={}", count.saturating_sub(position).max(1))),
+        None => rule.clone(),
+    };
+    let until = occurrence - Duration::seconds(1);
+    let ended_rule = with_end(&rule, &format!("UNTIL={}", until.format("%Y%m%dT%H%M%SZ")));
+
+    // Exceptions after the split follow the new series, unless it moves and
+    // they'd no longer line up with its occurrences.
+    let shifted = changes.start_time.is_some_and(|start| start != occurrence);
+    let (before, mut after): (Vec<_>, Vec<_>) = event.exdates.0.iter().partition(|exdate| **exdate < occurrence);
+    if shifted {
+        after.clear();
+    }
+    let overrides = overrides_by_event(db, &[event.id]).await?.remove(&event.id).unwrap_or_default();
+    let (earlier_overrides, mut later_overrides): (Vec<_>, Vec<_>) = overrides
+        .into_iter()
+        .partition(|change| change.original_start < occurrence);
+    if shifted {
+        later_overrides.clear();
+    }
+
+    let mut following = copy_of(&event, occurrence);
+    following.recurrence = Set(Some(remaining_rule));
+    following.exdates = Set(Exdates(after));
+    apply(&mut following, changes);
+    refresh_series_end(&mut following, &later_overrides);
+    let following_id = *following.id.as_ref();
+    let event_id = event.id;
+
+    let mut series = event.into_active_model();
+    series.recurrence = Set(Some(ended_rule));
+    series.exdates = Set(Exdates(before));
+    refresh_series_end(&mut series, &earlier_overrides);
+
+    let txn = db.begin().await?;
+    let following = following.insert(&txn).await?;
+    let series = series.update(&txn).await?;
+    let later_overrides = realm_event_overrides::Column::EventId.eq(event_id)
+        .and(realm_event_overrides::Column::OriginalStart.gte(occurrence));
+    if shifted {
+        realm_event_overrides::Entity::delete_many()
+            .filter(later_overrides)
+            .exec(&txn)
+            .await?;
+    } else {
+        realm_event_overrides::Entity::update_many()
+            .col_expr(realm_event_overrides::Column::EventId, Expr::value(following_id))
+            .filter(later_overrides)
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+    Ok((following, series))
+}
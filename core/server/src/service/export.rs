@@ -0,0 +1,251 @@
+use std::io::{Cursor, Write};
+use crate::data::snowflake::Snowflake;
+use crate::schema::data_exports::ExportStatus;
+use crate::schema::{data_exports, realm_events, realm_members, realm_tasks, realms, users};
+use crate::service;
+use crate::service::ics::IcsCalendar;
+use crate::service::snowflake::next_snowflake;
+use crate::web::routing::dto::{AccessTokenDto, IdentityDto, RealmDto, RealmEventDto, TaskDto};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use zip::write::SimpleFileOptions;
+
+/// How long a queued export can stay pending before it's considered lost.
+const STALE_EXPORT_AFTER: Duration = Duration::minutes(30);
+
+/// Everything Nebula stores about a user, minus credentials and other secrets.
+#[derive(Serialize)]
+pub struct PersonalData {
+    pub generated_at: DateTime<Utc>,
+    pub user: ExportedUser,
+    pub identities: Vec<IdentityDto>,
+    pub access_tokens: Vec<AccessTokenDto>,
+    pub sessions: Vec<ExportedSession>,
+    pub memberships: Vec<ExportedMembership>,
+    pub events: Vec<RealmEventDto>,
+    pub tasks: Vec<TaskDto>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedUser {
+    pub id: Snowflake,
+    pub name: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub totp_enabled: bool,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct ExportedSession {
+    pub id: Snowflake,
+    pub device: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedMembership {
+    pub realm: RealmDto,
+    pub permissions: i16,
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Archive(String),
+    Database(DbErr),
+}
+
+impl From<DbErr> for ExportError {
+    fn from(err: DbErr) -> Self {
+        ExportError::Database(err)
+    }
+}
+
+impl From<zip::result::ZipError> for ExportError {
+    fn from(err: zip::result::ZipError) -> Self {
+        ExportError::Archive(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(err: std::io::Error) -> Self {
+        ExportError::Archive(err.to_string())
+    }
+}
+
+/// How many events and tasks a user has authored, used to decide whether an
+/// export can be built inline.
+pub async fn count_authored_content(db: &DatabaseConnection, user_id: Snowflake) -> Result<u64, DbErr> {
+    let events = realm_events::Entity::find()
+        .filter(realm_events::Column::CreatedBy.eq(user_id))
+        .count(db)
+        .await?;
+    let tasks = realm_tasks::Entity::find()
+        .filter(realm_tasks::Column::AuthorId.eq(user_id))
+        .count(db)
+        .await?;
+    Ok(events + tasks)
+}
+
+async fn collect_personal_data(
+    db: &DatabaseConnection,
+    user: &users::Model,
+    events: &[realm_events::Model],
+    tasks: &[realm_tasks::Model]
+) -> Result<PersonalData, DbErr> {
+    let identities = service::identity::list_identities(db, user.id).await?;
+    let access_tokens = service::access_token::list_tokens(db, user.id).await?;
+    let sessions = service::session::list_active_sessions(db, user.id).await?;
+
+    let memberships = realm_members::Entity::find()
+        .filter(realm_members::Column::UserId.eq(user.id))
+        .find_also_related(realms::Entity)
+        .all(db)
+        .await?;
+
+    Ok(PersonalData {
+        generated_at: Utc::now(),
+        user: ExportedUser {
+            id: user.id,
+            name: user.name.clone(),
+            email: user.email.clone(),
+            email_verified: user.email_verified,
+            totp_enabled: user.totp_enabled,
+            updated_at: user.updated_at,
+        },
+        identities: identities.iter().map(IdentityDto::from_model).collect(),
+        access_tokens: access_tokens.iter().map(AccessTokenDto::from_model).collect(),
+        sessions: sessions.into_iter().map(|session| ExportedSession {
+            id: session.id,
+            device: session.device,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+        }).collect(),
+        memberships: memberships.into_iter()
+            .filter_map(|(member, realm)| realm.map(|realm| ExportedMembership {
+                realm: RealmDto::from_model(&realm),
+                permissions: member.permissions,
+            }))
+            .collect(),
+        events: events.iter().map(RealmEventDto::from_model).collect(),
+        tasks: tasks.iter().cloned().map(TaskDto::from_model).collect(),
+    })
+}
+
+/// Builds a zip archive holding `export.json` and a `calendar.ics` with the
+/// user's events and tasks.
+pub async fn build_archive(db: &DatabaseConnection, user: &users::Model) -> Result<Vec<u8>, ExportError> {
+    let events = realm_events::Entity::find()
+        .filter(realm_events::Column::CreatedBy.eq(user.id))
+        .order_by_asc(realm_events::Column::StartTime)
+        .all(db)
+        .await?;
+    let tasks = realm_tasks::Entity::find()
+        .filter(realm_tasks::Column::AuthorId.eq(user.id))
+        .order_by_asc(realm_tasks::Column::Id)
+        .all(db)
+        .await?;
+
+    let data = collect_personal_data(db, user, &events, &tasks).await?;
+    let event_ids: Vec<Snowflake> = events.iter().map(|event| event.id).collect();
+    let overrides = service::event::overrides_by_event(db, &event_ids).await?;
+
+    let mut calendar = IcsCalendar::new(&format!("{}'s Nebula data", user.name));
+    events.iter().for_each(|event| {
+        calendar.push_event(event, overrides.get(&event.id).map_or(&[], Vec::as_slice))
+    });
+    tasks.iter().for_each(|task| calendar.push_task(task));
+
+    let json = serde_json::to_vec_pretty(&data)
+        .map_err(|err| ExportError::Archive(err.to_string()))?;
+
+    let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    archive.start_file("export.json", options)?;
+    archive.write_all(&json)?;
+    archive.start_file("calendar.ics", options)?;
+    archive.write_all(calendar.finish().as_bytes())?;
+    Ok(archive.finish()?.into_inner())
+}
+
+/// Marks the user's jobs that have been pending for too long as failed. They
+/// only run in the process that queued them, so a restart leaves them behind.
+async fn fail_stale_exports(db: &DatabaseConnection, user_id: Snowflake) -> Result<(), DbErr> {
+    data_exports::Entity::update_many()
+        .col_expr(data_exports::Column::Status, Expr::value(ExportStatus::Failed))
+        .col_expr(data_exports::Column::CompletedAt, Expr::value(Utc::now()))
+        .filter(data_exports::Column::UserId.eq(user_id))
+        .filter(data_exports::Column::Status.eq(ExportStatus::Pending))
+        .filter(data_exports::Column::CreatedAt.lt(Utc::now() - STALE_EXPORT_AFTER))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Returns the user's unfinished export job, or queues a new one.
+pub async fn find_or_create_pending(
+    db: &DatabaseConnection,
+    user_id: Snowflake
+) -> Result<(data_exports::Model, bool), DbErr> {
+    fail_stale_exports(db, user_id).await?;
+    let pending = data_exports::Entity::find()
+        .filter(data_exports::Column::UserId.eq(user_id))
+        .filter(data_exports::Column::Status.eq(ExportStatus::Pending))
+        .one(db)
+        .await?;
+    if let Some(pending) = pending {
+        return Ok((pending, false));
+    }
+
+    let model = data_exports::ActiveModel {
+        id: Set(next_snowflake()),
+        user_id: Set(user_id),
+        status: Set(ExportStatus::Pending),
+        created_at: Set(Utc::now()),
+        completed_at: Set(None),
+        archive: Set(None),
+    };
+    Ok((model.insert(db).await?, true))
+}
+
+/// Builds the archive for a queued export and stores the result on the job.
+pub async fn run_export(db: DatabaseConnection, export_id: Snowflake, user: users::Model) {
+    let (status, archive) = match build_archive(&db, &user).await {
+        Ok(archive) => (ExportStatus::Ready, Some(archive)),
+        Err(err) => {
+            tracing::error!("Failed to build data export {}: {:?}", export_id, err);
+            (ExportStatus::Failed, None)
+        }
+    };
+
+    let model = data_exports::ActiveModel {
+        id: Set(export_id),
+        status: Set(status),
+        completed_at: Set(Some(Utc::now())),
+        archive: Set(archive),
+        ..Default::default()
+    };
+    if let Err(err) = model.update(&db).await {
+        tracing::error!("Failed to store data export {}: {:?}", export_id, err);
+    }
+}
+
+pub async fn find_export(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    export_id: Snowflake
+) -> Result<Option<data_exports::Model>, DbErr> {
+    fail_stale_exports(db, user_id).await?;
+    data_exports::Entity::find_by_id(export_id)
+        .filter(data_exports::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+}
@@ -0,0 +1,126 @@
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realm_calendar_feeds, realm_events, realm_tasks, realms, users};
+use crate::service;
+use crate::service::ics::IcsCalendar;
+use crate::service::snowflake::next_snowflake;
+use crate::util::token::{generate_token, hash_token};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+
+/// Prefix that tells calendar feed tokens apart from other secrets.
+pub const TOKEN_PREFIX: &str = "nbf_";
+const LAST_USED_GRANULARITY_SECS: i64 = 60;
+
+pub struct CreatedFeed {
+    pub model: realm_calendar_feeds::Model,
+    pub token: String,
+}
+
+/// Gives the member a new feed token, replacing the one they had.
+pub async fn create_feed(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    user_id: Snowflake
+) -> Result<CreatedFeed, DbErr> {
+    let token = format!("{TOKEN_PREFIX}{}", generate_token());
+    let model = realm_calendar_feeds::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm_id),
+        user_id: Set(user_id),
+        token_hash: Set(hash_token(&token)),
+        created_at: Set(chrono::Utc::now()),
+        last_used_at: Set(None),
+    };
+
+    let txn = db.begin().await?;
+    realm_calendar_feeds::Entity::delete_many()
+        .filter(realm_calendar_feeds::Column::RealmId.eq(realm_id))
+        .filter(realm_calendar_feeds::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    let model = model.insert(&txn).await?;
+    txn.commit().await?;
+    Ok(CreatedFeed { model, token })
+}
+
+pub async fn find_feed(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    user_id: Snowflake
+) -> Result<Option<realm_calendar_feeds::Model>, DbErr> {
+    realm_calendar_feeds::Entity::find()
+        .filter(realm_calendar_feeds::Column::RealmId.eq(realm_id))
+        .filter(realm_calendar_feeds::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+}
+
+pub async fn revoke_feed(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    user_id: Snowflake
+) -> Result<bool, DbErr> {
+    let deleted = realm_calendar_feeds::Entity::delete_many()
+        .filter(realm_calendar_feeds::Column::RealmId.eq(realm_id))
+        .filter(realm_calendar_feeds::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(deleted.rows_affected > 0)
+}
+
+/// Resolves a feed token to its feed and owner, recording when it was used.
+/// Whether the owner can still see the realm is left to the membership check.
+pub async fn authenticate(
+    db: &DatabaseConnection,
+    token: &str
+) -> Result<Option<(realm_calendar_feeds::Model, users::Model)>, DbErr> {
+    let feed = realm_calendar_feeds::Entity::find()
+        .filter(realm_calendar_feeds::Column::TokenHash.eq(hash_token(token)))
+        .find_also_related(users::Entity)
+        .one(db)
+        .await?;
+    let Some((feed, Some(user))) = feed else {
+        return Ok(None);
+    };
+
+    let now = chrono::Utc::now();
+    let recently_used = feed.last_used_at
+        .is_some_and(|last_used| (now - last_used).num_seconds() < LAST_USED_GRANULARITY_SECS);
+    if !recently_used {
+        realm_calendar_feeds::Entity::update_many()
+            .col_expr(realm_calendar_feeds::Column::LastUsedAt, Expr::value(now))
+            .filter(realm_calendar_feeds::Column::Id.eq(feed.id))
+            .exec(db)
+            .await?;
+    }
+    Ok(Some((feed, user)))
+}
+
+/// The realm's whole calendar as an iCalendar document, tasks included on request.
+pub async fn render_calendar(
+    db: &DatabaseConnection,
+    realm: &realms::Model,
+    include_tasks: bool
+) -> Result<String, DbErr> {
+    let events = realm_events::Entity::find()
+        .filter(realm_events::Column::RealmId.eq(realm.id))
+        .order_by_asc(realm_events::Column::StartTime)
+        .all(db)
+        .await?;
+    let event_ids: Vec<Snowflake> = events.iter().map(|event| event.id).collect();
+    let overrides = service::event::overrides_by_event(db, &event_ids).await?;
+
+    let mut calendar = IcsCalendar::new(&realm.name);
+    events.iter().for_each(|event| {
+        calendar.push_event(event, overrides.get(&event.id).map_or(&[], Vec::as_slice))
+    });
+    if include_tasks {
+        let tasks = realm_tasks::Entity::find()
+            .filter(realm_tasks::Column::RealmId.eq(realm.id))
+            .order_by_asc(realm_tasks::Column::Id)
+            .all(db)
+            .await?;
+        tasks.iter().for_each(|task| calendar.push_task(task));
+    }
+    Ok(calendar.finish())
+}
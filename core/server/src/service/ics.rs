@@ -0,0 +1,273 @@
+use crate::schema::{realm_event_overrides, realm_events, realm_tasks};
+use crate::service::event::is_occurrence;
+use chrono::{DateTime, Duration, FixedOffset, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz, TzOffset};
+use std::collections::BTreeMap;
+
+const PRODUCT_ID: &str = "-//Nebula//Nebula Calendar//EN";
+const MAX_LINE_OCTETS: usize = 75;
+/// How far past today time zone definitions reach for series that never end.
+const TIMEZONE_HORIZON_DAYS: i64 = 5 * 365;
+
+/// Builds an iCalendar (RFC 5545) document out of realm events and tasks.
+pub struct IcsCalendar {
+    lines: Vec<String>,
+    header_len: usize,
+    /// Zones referenced through `TZID`, with the span their definition has to cover.
+    timezones: BTreeMap<&'static str, (Tz, DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl IcsCalendar {
+    pub fn new(name: &str) -> Self {
+        let mut calendar = IcsCalendar { lines: Vec::new(), header_len: 0, timezones: BTreeMap::new() };
+        calendar.push("BEGIN", "VCALENDAR");
+        calendar.push("VERSION", "2.0");
+        calendar.push("PRODID", PRODUCT_ID);
+        calendar.push("CALSCALE", "GREGORIAN");
+        calendar.push("X-WR-CALNAME", &escape_text(name));
+        calendar.header_len = calendar.lines.len();
+        calendar
+    }
+
+    /// Adds the event, and for a recurring one, a `RECURRENCE-ID` component
+    /// for each overridden occurrence. Recurring events are written in their
+    /// own zone so clients keep them at the same wall-clock time.
+    pub fn push_event(&mut self, event: &realm_events::Model, overrides: &[realm_event_overrides::Model]) {
+        let timezone = event.recurrence.as_ref()
+            .and_then(|_| event.timezone.parse::<Tz>().ok())
+            .filter(|timezone| *timezone != Tz::UTC);
+        let duration = event.end_time.map(|end| end - event.start_time);
+
+        self.push("BEGIN", "VEVENT");
+        self.push("UID", &event_uid(event));
+        self.push("DTSTAMP", &format_datetime(Utc::now()));
+        self.push_time("DTSTART", event.start_time, timezone);
+        if let Some(end_time) = event.end_time {
+            self.push_time("DTEND", end_time, timezone);
+        }
+        self.push("SUMMARY", &escape_text(&event.name));
+        if let Some(description) = &event.description {
+            self.push("DESCRIPTION", &escape_text(description));
+        }
+        if let Some(location) = &event.location {
+            self.push("LOCATION", &escape_text(location));
+        }
+        if let Some(recurrence) = &event.recurrence {
+            self.push("RRULE", recurrence.trim_start_matches("RRULE:"));
+            if !event.exdates.0.is_empty() {
+                self.push_times("EXDATE", &event.exdates.0, timezone);
+            }
+        }
+        self.push("END", "VEVENT");
+
+        if event.recurrence.is_none() {
+            return;
+        }
+        let mut last = event.series_end
+            .unwrap_or_else(|| Utc::now() + Duration::days(TIMEZONE_HORIZON_DAYS))
+            .max(event.start_time);
+        // Cancelled occurrences and ones the series no longer produces would
+        // only confuse clients.
+        let changes = overrides.iter().filter(|change| {
+            !event.exdates.0.contains(&change.original_start) && is_occurrence(event, change.original_start)
+        });
+        for change in changes {
+            let start = change.start_time.unwrap_or(change.original_start);
+            let end = change.end_time.or(duration.map(|duration| start + duration));
+            last = last.max(end.unwrap_or(start));
+
+            self.push("BEGIN", "VEVENT");
+            self.push("UID", &event_uid(event));
+            self.push("DTSTAMP", &format_datetime(Utc::now()));
+            self.push_time("RECURRENCE-ID", change.original_start, timezone);
+            self.push_time("DTSTART", start, timezone);
+            if let Some(end) = end {
+                self.push_time("DTEND", end, timezone);
+            }
+            self.push("SUMMARY", &escape_text(change.name.as_ref().unwrap_or(&event.name)));
+            if let Some(description) = change.description.as_ref().or(event.description.as_ref()) {
+                self.push("DESCRIPTION", &escape_text(description));
+            }
+            if let Some(location) = change.location.as_ref().or(event.location.as_ref()) {
+                self.push("LOCATION", &escape_text(location));
+            }
+            self.push("END", "VEVENT");
+        }
+        if let Some(timezone) = timezone {
+            let first = overrides.iter()
+                .filter_map(|change| change.start_time)
+                .fold(event.start_time, DateTime::min);
+            self.cover_timezone(timezone, first, last);
+        }
+    }
+
+    pub fn push_task(&mut self, task: &realm_tasks::Model) {
+        self.push("BEGIN", "VTODO");
+        self.push("UID", &format!("task-{}@nebula", task.id));
+        self.push("DTSTAMP", &format_datetime(Utc::now()));
+        self.push("SUMMARY", &escape_text(&task.title));
+        if let Some(description) = &task.description {
+            self.push("DESCRIPTION", &escape_text(description));
+        }
+        if let Some(start_date) = task.start_date {
+            self.push("DTSTART", &format_datetime(start_date));
+        }
+        if let Some(due_date) = task.due_date {
+            self.push("DUE", &format_datetime(due_date));
+        }
+        if let Some(priority) = &task.priority {
+            self.push("PRIORITY", priority_value(priority));
+        }
+        self.push("STATUS", if task.completed { "COMPLETED" } else { "NEEDS-ACTION" });
+        self.push("END", "VTODO");
+    }
+
+    pub fn finish(mut self) -> String {
+        // Time zone definitions go before the components that use them.
+        let components = self.lines.split_off(self.header_len);
+        for (timezone, first, last) in std::mem::take(&mut self.timezones).into_values() {
+            self.push_timezone(timezone, first, last);
+        }
+        self.lines.extend(components);
+        self.push("END", "VCALENDAR");
+        let mut output = self.lines.join("\r\n");
+        output.push_str("\r\n");
+        output
+    }
+
+    fn push(&mut self, name: &str, value: &str) {
+        self.lines.push(fold_line(&format!("{name}:{value}")));
+    }
+
+    /// Writes a UTC time, or a local one tagged with `TZID` when given a zone.
+    fn push_time(&mut self, name: &str, time: DateTime<Utc>, timezone: Option<Tz>) {
+        self.push_times(name, &[time], timezone);
+    }
+
+    fn push_times(&mut self, name: &str, times: &[DateTime<Utc>], timezone: Option<Tz>) {
+        match timezone {
+            Some(timezone) => {
+                let values: Vec<String> = times.iter()
+                    .map(|time| format_local(time.with_timezone(&timezone).naive_local()))
+                    .collect();
+                self.push(&format!("{name};TZID={}", timezone.name()), &values.join(","));
+            },
+            None => {
+                let values: Vec<String> = times.iter().copied().map(format_datetime).collect();
+                self.push(name, &values.join(","));
+            }
+        }
+    }
+
+    fn cover_timezone(&mut self, timezone: Tz, first: DateTime<Utc>, last: DateTime<Utc>) {
+        let span = self.timezones.entry(timezone.name()).or_insert((timezone, first, last));
+        span.1 = span.1.min(first);
+        span.2 = span.2.max(last);
+    }
+
+    /// Describes the zone through every offset change between `first` and
+    /// `last`, starting from the offset in effect at `first`.
+    fn push_timezone(&mut self, timezone: Tz, first: DateTime<Utc>, last: DateTime<Utc>) {
+        self.push("BEGIN", "VTIMEZONE");
+        self.push("TZID", timezone.name());
+        let mut offset = timezone.offset_from_utc_datetime(&first.naive_utc());
+        self.push_observance(first, offset, offset);
+
+        let mut day = first;
+        while day < last {
+            let next_day = day + Duration::days(1);
+            let next_offset = timezone.offset_from_utc_datetime(&next_day.naive_utc());
+            if !same_offset(&next_offset, &offset) {
+                // Zones change offset at most once a day, so the change is in here.
+                let (mut before, mut after) = (day, next_day);
+                while after - before > Duration::seconds(1) {
+                    let middle = before + (after - before) / 2;
+                    let middle_offset = timezone.offset_from_utc_datetime(&middle.naive_utc());
+                    if same_offset(&middle_offset, &offset) {
+                        before = middle;
+                    } else {
+                        after = middle;
+                    }
+                }
+                self.push_observance(after, offset, next_offset);
+                offset = next_offset;
+            }
+            day = next_day;
+        }
+        self.push("END", "VTIMEZONE");
+    }
+
+    fn push_observance(&mut self, onset: DateTime<Utc>, from: TzOffset, to: TzOffset) {
+        let kind = if to.dst_offset().is_zero() { "STANDARD" } else { "DAYLIGHT" };
+        self.push("BEGIN", kind);
+        self.push("DTSTART", &format_local(onset.with_timezone(&from.fix()).naive_local()));
+        self.push("TZOFFSETFROM", &format_offset(from.fix()));
+        self.push("TZOFFSETTO", &format_offset(to.fix()));
+        if let Some(abbreviation) = to.abbreviation() {
+            self.push("TZNAME", &escape_text(abbreviation));
+        }
+        self.push("END", kind);
+    }
+}
+
+fn same_offset(a: &TzOffset, b: &TzOffset) -> bool {
+    a.fix() == b.fix() && a.dst_offset() == b.dst_offset() && a.abbreviation() == b.abbreviation()
+}
+
+fn event_uid(event: &realm_events::Model) -> String {
+    format!("event-{}@nebula", event.id)
+}
+
+pub fn format_datetime(datetime: DateTime<Utc>) -> String {
+    datetime.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_local(datetime: chrono::NaiveDateTime) -> String {
+    datetime.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// UTC offsets as `+hhmm`, with seconds only when a zone actually has them.
+fn format_offset(offset: FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.unsigned_abs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if seconds == 0 {
+        format!("{sign}{hours:02}{minutes:02}")
+    } else {
+        format!("{sign}{hours:02}{minutes:02}{seconds:02}")
+    }
+}
+
+fn priority_value(priority: &realm_tasks::Priority) -> &'static str {
+    // iCalendar priorities go from 1 (highest) to 9 (lowest).
+    match priority {
+        realm_tasks::Priority::Important => "1",
+        realm_tasks::Priority::Desirable => "5",
+        realm_tasks::Priority::Discardable => "9",
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Splits content lines longer than 75 octets, continuing them on lines that
+/// start with a space. Never splits inside a UTF-8 character.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut current_octets = 0;
+    for c in line.chars() {
+        if current_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            current_octets = 1;
+        }
+        folded.push(c);
+        current_octets += c.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,129 @@
+use crate::data::snowflake::Snowflake;
+use crate::schema::{user_identities, users};
+use crate::service::oidc::OidcClaims;
+use crate::service::snowflake::next_snowflake;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+
+const MAX_NAME_LENGTH: usize = 50;
+
+#[derive(Debug)]
+pub enum IdentityError {
+    MissingEmail,
+    EmailTaken,
+    AlreadyLinked,
+    Database(DbErr),
+}
+
+impl From<DbErr> for IdentityError {
+    fn from(err: DbErr) -> Self {
+        IdentityError::Database(err)
+    }
+}
+
+/// Finds the user an external identity belongs to, linking or creating an
+/// account when it is seen for the first time.
+///
+/// An existing account is only linked by email when both sides have verified
+/// the address, otherwise whoever registered it first could take the identity
+/// over.
+pub async fn resolve_user(
+    db: &DatabaseConnection,
+    provider: &str,
+    claims: &OidcClaims,
+    link_user_id: Option<Snowflake>
+) -> Result<users::Model, IdentityError> {
+    let existing = user_identities::Entity::find()
+        .filter(user_identities::Column::Provider.eq(provider))
+        .filter(user_identities::Column::Subject.eq(claims.sub.clone()))
+        .one(db)
+        .await?;
+    if let Some(identity) = existing {
+        if link_user_id.is_some_and(|user_id| user_id != identity.user_id) {
+            return Err(IdentityError::AlreadyLinked);
+        }
+        let user = users::Entity::find_by_id(identity.user_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Linked user no longer exists".to_string()))?;
+        return Ok(user);
+    }
+
+    if let Some(user_id) = link_user_id {
+        let user = users::Entity::find_by_id(user_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Linking user no longer exists".to_string()))?;
+        insert_identity(db, user.id, provider, claims).await?;
+        return Ok(user);
+    }
+
+    let Some(email) = claims.email.clone() else {
+        return Err(IdentityError::MissingEmail);
+    };
+    let email_verified = claims.email_verified.unwrap_or(false);
+    let user_with_same_email = users::Entity::find()
+        .filter(users::Column::Email.eq(email.clone()))
+        .one(db)
+        .await?;
+    if let Some(user) = user_with_same_email {
+        if !(user.email_verified && email_verified) {
+            return Err(IdentityError::EmailTaken);
+        }
+        insert_identity(db, user.id, provider, claims).await?;
+        return Ok(user);
+    }
+
+    let user = users::ActiveModel {
+        id: Set(next_snowflake()),
+        name: Set(display_name(claims, &email)),
+        email: Set(email),
+        password_hash: Set(None),
+        email_verified: Set(email_verified),
+        totp_secret: Set(None),
+        totp_enabled: Set(false),
+        totp_recovery_codes: Set(None),
+        totp_last_step: Set(None),
+        updated_at: Set(chrono::Utc::now().naive_utc()),
+    };
+    let user = user.insert(db).await?;
+    insert_identity(db, user.id, provider, claims).await?;
+    Ok(user)
+}
+
+pub async fn list_identities(
+    db: &DatabaseConnection,
+    user_id: Snowflake
+) -> Result<Vec<user_identities::Model>, DbErr> {
+    user_identities::Entity::find()
+        .filter(user_identities::Column::UserId.eq(user_id))
+        .order_by_asc(user_identities::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+async fn insert_identity(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    provider: &str,
+    claims: &OidcClaims
+) -> Result<user_identities::Model, DbErr> {
+    let identity = user_identities::ActiveModel {
+        id: Set(next_snowflake()),
+        user_id: Set(user_id),
+        provider: Set(provider.to_string()),
+        subject: Set(claims.sub.clone()),
+        email: Set(claims.email.clone()),
+        created_at: Set(chrono::Utc::now()),
+    };
+    identity.insert(db).await
+}
+
+fn display_name(claims: &OidcClaims, email: &str) -> String {
+    claims.name.clone()
+        .or_else(|| claims.preferred_username.clone())
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email).to_string())
+        .chars()
+        .take(MAX_NAME_LENGTH)
+        .collect()
+}
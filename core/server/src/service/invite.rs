@@ -0,0 +1,211 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realm_invites, realm_members, realms, users};
+use crate::service::snowflake::next_snowflake;
+use sea_orm::sea_query::{Condition, Expr, ExprTrait};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+
+const CODE_BYTES: usize = 9;
+
+#[derive(Debug)]
+pub enum InviteError {
+    /// The code doesn't exist, or it's a direct invite meant for someone else.
+    NotFound,
+    /// Revoked, expired or out of uses.
+    Unavailable,
+    AlreadyMember,
+    Database(DbErr),
+}
+
+impl From<DbErr> for InviteError {
+    fn from(err: DbErr) -> Self {
+        InviteError::Database(err)
+    }
+}
+
+pub struct NewInvite {
+    pub invitee_id: Option<Snowflake>,
+    pub invitee_email: Option<String>,
+    pub permissions: i16,
+    pub max_uses: Option<i32>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Short enough to share by hand, long enough not to be guessed.
+fn generate_code() -> String {
+    let mut bytes = [0u8; CODE_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub async fn create_invite(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    created_by: Snowflake,
+    invite: NewInvite
+) -> Result<realm_invites::Model, DbErr> {
+    // A direct invite is for a single person, so it can only be used once.
+    let is_direct = invite.invitee_id.is_some() || invite.invitee_email.is_some();
+    let max_uses = if is_direct { Some(1) } else { invite.max_uses };
+
+    let model = realm_invites::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm_id),
+        created_by: Set(created_by),
+        code: Set(generate_code()),
+        invitee_id: Set(invite.invitee_id),
+        invitee_email: Set(invite.invitee_email.map(|email| email.to_lowercase())),
+        permissions: Set(invite.permissions),
+        max_uses: Set(max_uses),
+        uses: Set(0),
+        created_at: Set(chrono::Utc::now()),
+        expires_at: Set(invite.expires_at),
+        revoked_at: Set(None),
+    };
+    model.insert(db).await
+}
+
+/// Invites of a realm that can still be used.
+pub async fn list_realm_invites(
+    db: &DatabaseConnection,
+    realm_id: Snowflake
+) -> Result<Vec<realm_invites::Model>, DbErr> {
+    let invites = realm_invites::Entity::find()
+        .filter(realm_invites::Column::RealmId.eq(realm_id))
+        .filter(realm_invites::Column::RevokedAt.is_null())
+        .order_by_desc(realm_invites::Column::CreatedAt)
+        .all(db)
+        .await?;
+    Ok(invites.into_iter().filter(realm_invites::Model::is_usable).collect())
+}
+
+/// Pending direct invites addressed to the user, by id or by verified email.
+pub async fn list_user_invites(
+    db: &DatabaseConnection,
+    user: &users::Model
+) -> Result<Vec<realm_invites::Model>, DbErr> {
+    let mut recipient = Condition::any().add(realm_invites::Column::InviteeId.eq(user.id));
+    if user.email_verified {
+        recipient = recipient.add(realm_invites::Column::InviteeEmail.eq(user.email.to_lowercase()));
+    }
+
+    let invites = realm_invites::Entity::find()
+        .filter(recipient)
+        .filter(realm_invites::Column::RevokedAt.is_null())
+        .order_by_desc(realm_invites::Column::CreatedAt)
+        .all(db)
+        .await?;
+    Ok(invites.into_iter().filter(realm_invites::Model::is_usable).collect())
+}
+
+pub async fn revoke_invite(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    invite_id: Snowflake
+) -> Result<bool, DbErr> {
+    let result = realm_invites::Entity::update_many()
+        .col_expr(realm_invites::Column::RevokedAt, Expr::value(chrono::Utc::now()))
+        .filter(realm_invites::Column::Id.eq(invite_id))
+        .filter(realm_invites::Column::RealmId.eq(realm_id))
+        .filter(realm_invites::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Finds an invite the user is allowed to see: any invite link, or a direct
+/// invite addressed to them.
+pub async fn find_invite_for(
+    db: &DatabaseConnection,
+    code: &str,
+    user: &users::Model
+) -> Result<Option<realm_invites::Model>, DbErr> {
+    let invite = realm_invites::Entity::find()
+        .filter(realm_invites::Column::Code.eq(code))
+        .one(db)
+        .await?;
+    Ok(invite.filter(|invite| is_recipient(invite, user)))
+}
+
+fn is_recipient(invite: &realm_invites::Model, user: &users::Model) -> bool {
+    if !invite.is_direct() {
+        return true;
+    }
+    invite.invitee_id == Some(user.id)
+        || (user.email_verified && invite.invitee_email.as_deref() == Some(user.email.to_lowercase().as_str()))
+}
+
+/// Joins the realm with the permissions preset on the invite. Archived realms
+/// can't be joined until they're restored.
+pub async fn accept_invite(
+    db: &DatabaseConnection,
+    code: &str,
+    user: &users::Model
+) -> Result<(realm_invites::Model, realm_members::Model), InviteError> {
+    let invite = find_invite_for(db, code, user).await?.ok_or(InviteError::NotFound)?;
+    if !invite.is_usable() {
+        return Err(InviteError::Unavailable);
+    }
+    let archived = realms::Entity::find_by_id(invite.realm_id)
+        .filter(realms::Column::ArchivedAt.is_not_null())
+        .one(db)
+        .await?;
+    if archived.is_some() {
+        return Err(InviteError::Unavailable);
+    }
+
+    let txn = db.begin().await?;
+    let existing = realm_members::Entity::find()
+        .filter(realm_members::Column::RealmId.eq(invite.realm_id))
+        .filter(realm_members::Column::UserId.eq(user.id))
+        .one(&txn)
+        .await?;
+    if existing.is_some() {
+        return Err(InviteError::AlreadyMember);
+    }
+
+    // Take a use only if one is left, so concurrent joins can't overshoot `max_uses`.
+    let claimed = realm_invites::Entity::update_many()
+        .col_expr(realm_invites::Column::Uses, Expr::col(realm_invites::Column::Uses).add(1))
+        .filter(realm_invites::Column::Id.eq(invite.id))
+        .filter(realm_invites::Column::RevokedAt.is_null())
+        .filter(
+            Condition::any()
+                .add(realm_invites::Column::MaxUses.is_null())
+                .add(Expr::col(realm_invites::Column::Uses).lt(Expr::col(realm_invites::Column::MaxUses)))
+        )
+        .exec(&txn)
+        .await?;
+    if claimed.rows_affected == 0 {
+        return Err(InviteError::Unavailable);
+    }
+
+    let membership = realm_members::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(invite.realm_id),
+        user_id: Set(user.id),
+        permissions: Set(invite.permissions),
+        denied_permissions: Set(0),
+    };
+    let membership = membership.insert(&txn).await?;
+    txn.commit().await?;
+    Ok((invite, membership))
+}
+
+/// Turns down a direct invite. Invite links can't be declined, only ignored.
+pub async fn decline_invite(
+    db: &DatabaseConnection,
+    code: &str,
+    user: &users::Model
+) -> Result<(), InviteError> {
+    let invite = find_invite_for(db, code, user).await?.ok_or(InviteError::NotFound)?;
+    if !invite.is_direct() {
+        return Err(InviteError::NotFound);
+    }
+
+    realm_invites::Entity::delete_by_id(invite.id).exec(db).await?;
+    Ok(())
+}
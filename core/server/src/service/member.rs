@@ -0,0 +1,80 @@
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realm_calendar_feeds, realm_members, users};
+use crate::service::snowflake::next_snowflake;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+
+/// Members of a realm along with their user, oldest first.
+pub async fn list_members(
+    db: &DatabaseConnection,
+    realm_id: Snowflake
+) -> Result<Vec<(realm_members::Model, users::Model)>, DbErr> {
+    let members = realm_members::Entity::find()
+        .filter(realm_members::Column::RealmId.eq(realm_id))
+        .find_also_related(users::Entity)
+        .order_by_asc(realm_members::Column::Id)
+        .all(db)
+        .await?;
+    Ok(members.into_iter()
+        .filter_map(|(member, user)| user.map(|user| (member, user)))
+        .collect())
+}
+
+pub async fn add_member<C: ConnectionTrait>(
+    db: &C,
+    realm_id: Snowflake,
+    user_id: Snowflake,
+    permissions: i16
+) -> Result<realm_members::Model, DbErr> {
+    let membership = realm_members::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm_id),
+        user_id: Set(user_id),
+        permissions: Set(permissions),
+        denied_permissions: Set(0),
+    };
+    membership.insert(db).await
+}
+
+pub async fn update_permissions<C: ConnectionTrait>(
+    db: &C,
+    membership: realm_members::Model,
+    permissions: i16,
+    denied_permissions: i16
+) -> Result<realm_members::Model, DbErr> {
+    let mut active = membership.into_active_model();
+    active.permissions = Set(permissions);
+    active.denied_permissions = Set(denied_permissions);
+    active.update(db).await
+}
+
+/// Gives someone who only inherits access their own membership, saved with
+/// the permissions it's being set to.
+pub async fn override_inherited(
+    db: &DatabaseConnection,
+    membership: realm_members::Model,
+    permissions: i16,
+    denied_permissions: i16
+) -> Result<realm_members::Model, DbErr> {
+    let txn = db.begin().await?;
+    let membership = add_member(&txn, membership.realm_id, membership.user_id, membership.permissions).await?;
+    let membership = update_permissions(&txn, membership, permissions, denied_permissions).await?;
+    txn.commit().await?;
+    Ok(membership)
+}
+
+/// Removes the member along with their calendar feed, so the feed's token
+/// doesn't come back to life if they rejoin.
+pub async fn remove_member(
+    db: &DatabaseConnection,
+    membership: realm_members::Model
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    realm_calendar_feeds::Entity::delete_many()
+        .filter(realm_calendar_feeds::Column::RealmId.eq(membership.realm_id))
+        .filter(realm_calendar_feeds::Column::UserId.eq(membership.user_id))
+        .exec(&txn)
+        .await?;
+    realm_members::Entity::delete_by_id(membership.id).exec(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
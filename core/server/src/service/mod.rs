@@ -1,4 +1,23 @@
 pub mod auth;
+pub mod password;
 pub mod snowflake;
 pub mod realm;
-pub mod schedule;
\ No newline at end of file
+pub mod schedule;
+pub mod session;
+pub mod user_token;
+pub mod totp;
+pub mod oidc;
+pub mod identity;
+pub mod access_token;
+pub mod account;
+pub mod ics;
+pub mod export;
+pub mod rate_limit;
+pub mod invite;
+pub mod member;
+pub mod role;
+pub mod audit;
+pub mod share;
+pub mod discovery;
+pub mod event;
+pub mod feed;
@@ -0,0 +1,267 @@
+use crate::app::{NebulaApp, OidcProviderConfig};
+use crate::data::snowflake::Snowflake;
+use crate::schema::oidc_auth_requests;
+use crate::service::snowflake::next_snowflake;
+use crate::util::token::{generate_token, hash_token};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+const AUTH_REQUEST_TTL_SECS: i64 = 10 * 60;
+const METADATA_TTL_SECS: i64 = 60 * 60;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Clone, Debug)]
+struct CachedProvider {
+    metadata: ProviderMetadata,
+    jwks: JwkSet,
+    fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Discovery documents and signing keys of the configured providers, kept
+/// around so they aren't fetched on every login.
+#[derive(Debug, Default)]
+pub struct ProviderCache {
+    http: reqwest::Client,
+    providers: HashMap<String, CachedProvider>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct OidcClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+    pub nonce: Option<String>,
+}
+
+/// The outcome of a finished authorization flow.
+pub struct CompletedAuthorization {
+    pub claims: OidcClaims,
+    pub link_user_id: Option<Snowflake>,
+}
+
+#[derive(Debug)]
+pub enum OidcError {
+    InvalidState,
+    Provider(String),
+    InvalidToken(String),
+    Database(DbErr),
+}
+
+impl From<DbErr> for OidcError {
+    fn from(err: DbErr) -> Self {
+        OidcError::Database(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Starts an authorization-code flow with PKCE and returns the URL the user has
+/// to be sent to. When `link_user_id` is set, the resulting identity is linked
+/// to that user instead of being used to log in.
+pub async fn begin_authorization(
+    app: &NebulaApp,
+    provider: &OidcProviderConfig,
+    link_user_id: Option<Snowflake>
+) -> Result<String, OidcError> {
+    let cached = provider_metadata(app, provider, false).await?;
+    let state = generate_token();
+    let nonce = generate_token();
+    let code_verifier = generate_token();
+    let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+    let now = chrono::Utc::now();
+    let request = oidc_auth_requests::ActiveModel {
+        id: Set(next_snowflake()),
+        provider: Set(provider.name.clone()),
+        state_hash: Set(hash_token(&state)),
+        code_verifier: Set(code_verifier),
+        nonce: Set(nonce.clone()),
+        link_user_id: Set(link_user_id),
+        created_at: Set(now),
+        expires_at: Set(now + chrono::Duration::seconds(AUTH_REQUEST_TTL_SECS)),
+    };
+    request.insert(&app.db).await?;
+
+    let url = Url::parse_with_params(&cached.metadata.authorization_endpoint, &[
+        ("response_type", "code"),
+        ("client_id", provider.client_id.as_str()),
+        ("redirect_uri", provider.redirect_url.as_str()),
+        ("scope", provider.scopes.as_str()),
+        ("state", state.as_str()),
+        ("nonce", nonce.as_str()),
+        ("code_challenge", code_challenge.as_str()),
+        ("code_challenge_method", "S256"),
+    ]).map_err(|err| OidcError::Provider(format!("Invalid authorization endpoint: {err}")))?;
+    Ok(url.to_string())
+}
+
+/// Consumes the pending request matching `state`, exchanges the code and
+/// validates the returned ID token.
+pub async fn complete_authorization(
+    app: &NebulaApp,
+    provider: &OidcProviderConfig,
+    code: &str,
+    state: &str
+) -> Result<CompletedAuthorization, OidcError> {
+    let request = oidc_auth_requests::Entity::find()
+        .filter(oidc_auth_requests::Column::StateHash.eq(hash_token(state)))
+        .filter(oidc_auth_requests::Column::Provider.eq(provider.name.clone()))
+        .one(&app.db)
+        .await?;
+    let Some(request) = request else {
+        return Err(OidcError::InvalidState);
+    };
+    let deleted = oidc_auth_requests::Entity::delete_by_id(request.id)
+        .exec(&app.db)
+        .await?;
+    if deleted.rows_affected == 0 || request.expires_at <= chrono::Utc::now() {
+        return Err(OidcError::InvalidState);
+    }
+
+    let cached = provider_metadata(app, provider, false).await?;
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", provider.redirect_url.as_str()),
+        ("client_id", provider.client_id.as_str()),
+        ("code_verifier", request.code_verifier.as_str()),
+    ];
+    if let Some(secret) = &provider.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+
+    let http = app.state.read().await.oidc_providers.http.clone();
+    let response = http.post(&cached.metadata.token_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|err| OidcError::Provider(err.to_string()))?;
+    if !response.status().is_success() {
+        return Err(OidcError::Provider(format!("Token endpoint answered with {}", response.status())));
+    }
+    let tokens: TokenResponse = response.json()
+        .await
+        .map_err(|err| OidcError::Provider(err.to_string()))?;
+
+    let claims = validate_id_token(app, provider, &tokens.id_token).await?;
+    if claims.nonce.as_deref() != Some(request.nonce.as_str()) {
+        return Err(OidcError::InvalidToken("Nonce mismatch".to_string()));
+    }
+
+    Ok(CompletedAuthorization {
+        claims,
+        link_user_id: request.link_user_id,
+    })
+}
+
+async fn validate_id_token(
+    app: &NebulaApp,
+    provider: &OidcProviderConfig,
+    id_token: &str
+) -> Result<OidcClaims, OidcError> {
+    let header = jsonwebtoken::decode_header(id_token)
+        .map_err(|err| OidcError::InvalidToken(err.to_string()))?;
+    if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+        return Err(OidcError::InvalidToken("Symmetric signatures are not accepted".to_string()));
+    }
+
+    let mut cached = provider_metadata(app, provider, false).await?;
+    let mut jwk = find_key(&cached.jwks, header.kid.as_deref());
+    if jwk.is_none() {
+        // The provider may have rotated its keys since they were cached.
+        cached = provider_metadata(app, provider, true).await?;
+        jwk = find_key(&cached.jwks, header.kid.as_deref());
+    }
+    let Some(jwk) = jwk else {
+        return Err(OidcError::InvalidToken("Unknown signing key".to_string()));
+    };
+    let key = DecodingKey::from_jwk(jwk)
+        .map_err(|err| OidcError::InvalidToken(err.to_string()))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[&cached.metadata.issuer]);
+    validation.set_audience(&[&provider.client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    jsonwebtoken::decode::<OidcClaims>(id_token, &key, &validation)
+        .map(|data| data.claims)
+        .map_err(|err| OidcError::InvalidToken(err.to_string()))
+}
+
+fn find_key<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Option<&'a jsonwebtoken::jwk::Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    }
+}
+
+async fn provider_metadata(
+    app: &NebulaApp,
+    provider: &OidcProviderConfig,
+    force_refresh: bool
+) -> Result<CachedProvider, OidcError> {
+    let http = {
+        let state = app.state.read().await;
+        let cached = state.oidc_providers.providers.get(&provider.name);
+        let fresh = cached.filter(|cached|
+            (chrono::Utc::now() - cached.fetched_at).num_seconds() < METADATA_TTL_SECS
+        );
+        if let (Some(cached), false) = (fresh, force_refresh) {
+            return Ok(cached.clone());
+        }
+        state.oidc_providers.http.clone()
+    };
+
+    let discovery_url = format!("{}/.well-known/openid-configuration", provider.issuer);
+    let metadata: ProviderMetadata = fetch_json(&http, &discovery_url).await?;
+    if metadata.issuer.trim_end_matches('/') != provider.issuer {
+        return Err(OidcError::Provider("Discovery document has a different issuer".to_string()));
+    }
+    let jwks: JwkSet = fetch_json(&http, &metadata.jwks_uri).await?;
+
+    let cached = CachedProvider {
+        metadata,
+        jwks,
+        fetched_at: chrono::Utc::now(),
+    };
+    app.state.write().await
+        .oidc_providers
+        .providers
+        .insert(provider.name.clone(), cached.clone());
+    Ok(cached)
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    http: &reqwest::Client,
+    url: &str
+) -> Result<T, OidcError> {
+    let response = http.get(url)
+        .send()
+        .await
+        .map_err(|err| OidcError::Provider(err.to_string()))?;
+    if !response.status().is_success() {
+        return Err(OidcError::Provider(format!("{url} answered with {}", response.status())));
+    }
+    response.json()
+        .await
+        .map_err(|err| OidcError::Provider(err.to_string()))
+}
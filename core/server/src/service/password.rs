@@ -0,0 +1,56 @@
+use crate::app::AppConfig;
+use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use rand::rngs::OsRng;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PasswordCheck {
+    Invalid,
+    Valid,
+    /// The password matched, but the stored hash predates the current salt or
+    /// Argon2 parameters and should be replaced.
+    ValidNeedsRehash,
+}
+
+pub fn hash_password(config: &AppConfig, password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    config.argon2.hash_password(password.as_bytes(), &salt)
+        .expect("Failed to hash password")
+        .to_string()
+}
+
+pub fn verify_password(config: &AppConfig, stored_hash: &str, password: &str) -> PasswordCheck {
+    let Ok(parsed_hash) = PasswordHash::new(stored_hash) else {
+        return PasswordCheck::Invalid;
+    };
+    if config.argon2.verify_password(password.as_bytes(), &parsed_hash).is_err() {
+        return PasswordCheck::Invalid;
+    }
+
+    if needs_rehash(config, &parsed_hash) {
+        PasswordCheck::ValidNeedsRehash
+    } else {
+        PasswordCheck::Valid
+    }
+}
+
+fn needs_rehash(config: &AppConfig, hash: &PasswordHash) -> bool {
+    let uses_legacy_salt = match (&config.legacy_argon_salt, hash.salt) {
+        (Some(legacy), Some(salt)) => legacy.as_str() == salt.as_str(),
+        _ => false
+    };
+    if uses_legacy_salt {
+        return true;
+    }
+
+    let expected = config.argon2.params();
+    let current_params = Params::try_from(hash).is_ok_and(|params|
+        params.m_cost() == expected.m_cost()
+            && params.t_cost() == expected.t_cost()
+            && params.p_cost() == expected.p_cost()
+    );
+    let current_algorithm = hash.algorithm == Algorithm::Argon2id.ident();
+    let current_version = hash.version == Some(Version::V0x13.into());
+
+    !(current_params && current_algorithm && current_version)
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use crate::app::{RateLimit, RateLimitConfig};
+use crate::data::snowflake::Snowflake;
+
+/// Windows are only swept once the table grows past this many entries.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Route groups that get their own budget, see `RateLimitConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    Auth,
+    Api,
+    User,
+}
+
+impl RouteGroup {
+    pub fn limit(&self, config: &RateLimitConfig) -> RateLimit {
+        match self {
+            RouteGroup::Auth => config.auth,
+            RouteGroup::Api => config.api,
+            RouteGroup::User => config.user,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Ip(IpAddr),
+    User(Snowflake),
+}
+
+#[derive(Debug)]
+struct Window {
+    started_at: Instant,
+    length: Duration,
+    count: u32,
+}
+
+/// Fixed-window request counters.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    windows: HashMap<(RouteGroup, RateLimitKey), Window>,
+}
+
+impl RateLimiter {
+    /// Counts a request, returning how long to wait when the budget is used up.
+    pub fn check(&mut self, group: RouteGroup, key: RateLimitKey, limit: RateLimit) -> Result<(), Duration> {
+        let now = Instant::now();
+        if self.windows.len() >= SWEEP_THRESHOLD {
+            self.windows.retain(|_, window| now.duration_since(window.started_at) < window.length);
+        }
+
+        let window = self.windows.entry((group, key)).or_insert(Window {
+            started_at: now,
+            length: limit.window,
+            count: 0,
+        });
+        if now.duration_since(window.started_at) >= window.length {
+            *window = Window { started_at: now, length: limit.window, count: 0 };
+        }
+
+        if window.count >= limit.requests {
+            return Err(window.length - now.duration_since(window.started_at));
+        }
+        window.count += 1;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct FailedLogins {
+    failures: u32,
+    locked_until: Option<Instant>,
+    last_failure: Instant,
+}
+
+/// Failed password attempts per account. After `lockout_threshold` failures
+/// the account is locked, and each further failure doubles the lock.
+#[derive(Debug, Default)]
+pub struct LoginLockouts {
+    accounts: HashMap<String, FailedLogins>,
+}
+
+impl LoginLockouts {
+    /// How much longer the account stays locked, if it is.
+    pub fn locked_for(&self, email: &str) -> Option<Duration> {
+        let locked_until = self.accounts.get(&email.to_lowercase())?.locked_until?;
+        locked_until.checked_duration_since(Instant::now()).filter(|remaining| !remaining.is_zero())
+    }
+
+    pub fn record_failure(&mut self, email: &str, config: &RateLimitConfig) {
+        let now = Instant::now();
+        if self.accounts.len() >= SWEEP_THRESHOLD {
+            self.accounts.retain(|_, account| now.duration_since(account.last_failure) < config.lockout_max);
+        }
+
+        let account = self.accounts.entry(email.to_lowercase()).or_insert(FailedLogins {
+            failures: 0,
+            locked_until: None,
+            last_failure: now,
+        });
+        // Forget old failures once they're further back than the longest lock.
+        if now.duration_since(account.last_failure) >= config.lockout_max {
+            account.failures = 0;
+        }
+        account.failures += 1;
+        account.last_failure = now;
+
+        if account.failures >= config.lockout_threshold {
+            let doublings = (account.failures - config.lockout_threshold).min(16);
+            let lock = config.lockout_base.saturating_mul(1 << doublings).min(config.lockout_max);
+            account.locked_until = Some(now + lock);
+        }
+    }
+
+    pub fn clear(&mut self, email: &str) {
+        self.accounts.remove(&email.to_lowercase());
+    }
+}
@@ -1,7 +1,9 @@
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
-use crate::data::permissions::{BitwisePermissions, RealmPermissions};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+use crate::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
 use crate::data::snowflake::Snowflake;
 use crate::schema::{realm_members, realms};
+use crate::schema::realms::RealmVisibility;
+use crate::service::role::effective_permissions;
 use crate::service::snowflake::next_snowflake;
 
 pub async fn create_realm(
@@ -18,10 +20,313 @@ pub async fn create_realm(
         id: Set(membership_snowflake),
         realm_id: Set(inserted_realm.id),
         user_id: Set(user_id),
-        permissions: Set(permissions.bits())
+        permissions: Set(permissions.bits()),
+        denied_permissions: Set(0)
     };
 
     new_membership.insert(db)
         .await?;
     Ok(inserted_realm)
-}
\ No newline at end of file
+}
+
+#[derive(Debug)]
+pub enum TransferError {
+    /// The proposed owner isn't a member of the realm.
+    NotMember,
+    /// The caller isn't the member the realm was offered to.
+    NotPending,
+    /// The new owner already owns a realm with this name.
+    NameTaken,
+    Database(DbErr),
+}
+
+impl From<DbErr> for TransferError {
+    fn from(err: DbErr) -> Self {
+        TransferError::Database(err)
+    }
+}
+
+/// Realm names are unique per owner.
+pub async fn owner_has_realm_named<C: ConnectionTrait>(
+    db: &C,
+    owner_id: Snowflake,
+    name: &str,
+    except: Option<Snowflake>
+) -> Result<bool, DbErr> {
+    let mut query = realms::Entity::find()
+        .filter(realms::Column::Name.eq(name))
+        .filter(realms::Column::OwnerId.eq(owner_id));
+    if let Some(except) = except {
+        query = query.filter(realms::Column::Id.ne(except));
+    }
+    Ok(query.one(db).await?.is_some())
+}
+
+pub struct RealmChanges {
+    pub name: Option<String>,
+    pub description: Option<Option<String>>,
+    pub parent_id: Option<Option<Snowflake>>,
+    pub visibility: Option<RealmVisibility>,
+    pub timezone: Option<String>,
+}
+
+pub async fn update_realm(
+    db: &DatabaseConnection,
+    realm: realms::Model,
+    changes: RealmChanges
+) -> Result<realms::Model, DbErr> {
+    let mut active = realm.into_active_model();
+    if let Some(name) = changes.name {
+        active.name = Set(name);
+    }
+    if let Some(description) = changes.description {
+        active.description = Set(description);
+    }
+    if let Some(parent_id) = changes.parent_id {
+        active.parent_id = Set(parent_id);
+    }
+    if let Some(visibility) = changes.visibility {
+        active.visibility = Set(visibility);
+    }
+    if let Some(timezone) = changes.timezone {
+        active.timezone = Set(timezone);
+    }
+    active.update(db).await
+}
+
+/// Archives the realm, or restores it when `archived` is false.
+pub async fn set_archived(
+    db: &DatabaseConnection,
+    realm: realms::Model,
+    archived: bool
+) -> Result<realms::Model, DbErr> {
+    let mut active = realm.into_active_model();
+    active.archived_at = Set(archived.then(chrono::Utc::now));
+    active.update(db).await
+}
+
+/// Everything in the realm (members, events, tasks, invites, roles) goes with it.
+pub async fn delete_realm(db: &DatabaseConnection, realm_id: Snowflake) -> Result<(), DbErr> {
+    realms::Entity::delete_by_id(realm_id).exec(db).await?;
+    Ok(())
+}
+
+/// Offers the realm to a member, or withdraws the offer when `user_id` is `None`.
+pub async fn set_pending_owner(
+    db: &DatabaseConnection,
+    realm: realms::Model,
+    user_id: Option<Snowflake>
+) -> Result<realms::Model, TransferError> {
+    if let Some(user_id) = user_id {
+        let membership = realm_members::Entity::find_membership(db, realm.id, user_id).await?;
+        if membership.is_none() {
+            return Err(TransferError::NotMember);
+        }
+    }
+
+    let mut active = realm.into_active_model();
+    active.pending_owner_id = Set(user_id);
+    Ok(active.update(db).await?)
+}
+
+pub async fn accept_transfer(
+    db: &DatabaseConnection,
+    realm: realms::Model,
+    user_id: Snowflake
+) -> Result<realms::Model, TransferError> {
+    if realm.pending_owner_id != Some(user_id) {
+        return Err(TransferError::NotPending);
+    }
+
+    let txn = db.begin().await?;
+    if owner_has_realm_named(&txn, user_id, &realm.name, None).await? {
+        return Err(TransferError::NameTaken);
+    }
+    // The member may have left since the offer was made.
+    if realm_members::Entity::find_membership(&txn, realm.id, user_id).await?.is_none() {
+        return Err(TransferError::NotMember);
+    }
+
+    let mut active = realm.into_active_model();
+    active.owner_id = Set(user_id);
+    active.pending_owner_id = Set(None);
+    let realm = active.update(&txn).await?;
+    txn.commit().await?;
+    Ok(realm)
+}
+
+/// How many levels realms can be nested below a top-level realm.
+pub const MAX_REALM_DEPTH: usize = 8;
+
+/// How a user reaches a realm: through their own membership in it, or through
+/// their membership in the nearest ancestor they belong to.
+#[derive(Clone, Debug)]
+pub struct RealmAccess {
+    pub membership: realm_members::Model,
+    pub permissions: RealmPermissions,
+    /// The ancestor the membership belongs to, `None` for direct members.
+    pub inherited_from: Option<Snowflake>,
+}
+
+impl RealmAccess {
+    pub fn is_inherited(&self) -> bool {
+        self.inherited_from.is_some()
+    }
+}
+
+/// A direct membership always wins, so a child realm can override what a
+/// member inherits by giving them their own membership.
+pub async fn resolve_access<C: ConnectionTrait>(
+    db: &C,
+    realm: &realms::Model,
+    user_id: Snowflake
+) -> Result<Option<RealmAccess>, DbErr> {
+    if let Some(membership) = realm_members::Entity::find_membership(db, realm.id, user_id).await? {
+        let permissions = effective_permissions(db, realm, &membership).await?;
+        return Ok(Some(RealmAccess { membership, permissions, inherited_from: None }));
+    }
+    inherited_access(db, realm, user_id).await
+}
+
+/// Access through the nearest ancestor the user is a member of, regardless of
+/// any membership in the realm itself.
+pub async fn inherited_access<C: ConnectionTrait>(
+    db: &C,
+    realm: &realms::Model,
+    user_id: Snowflake
+) -> Result<Option<RealmAccess>, DbErr> {
+    for ancestor in ancestors(db, realm).await? {
+        if let Some(membership) = realm_members::Entity::find_membership(db, ancestor.id, user_id).await? {
+            let permissions = effective_permissions(db, &ancestor, &membership).await?;
+            return Ok(Some(RealmAccess { membership, permissions, inherited_from: Some(ancestor.id) }));
+        }
+    }
+    Ok(None)
+}
+
+/// The realms this one is nested in, nearest first.
+pub async fn ancestors<C: ConnectionTrait>(
+    db: &C,
+    realm: &realms::Model
+) -> Result<Vec<realms::Model>, DbErr> {
+    let mut ancestors = vec![];
+    let mut parent_id = realm.parent_id;
+    while let Some(id) = parent_id && ancestors.len() < MAX_REALM_DEPTH {
+        let Some(parent) = realms::Entity::find_by_id(id).one(db).await? else {
+            break;
+        };
+        parent_id = parent.parent_id;
+        ancestors.push(parent);
+    }
+    Ok(ancestors)
+}
+
+/// Everything nested below the given realms, one level per entry.
+pub async fn descendant_levels<C: ConnectionTrait>(
+    db: &C,
+    realm_ids: &[Snowflake]
+) -> Result<Vec<Vec<realms::Model>>, DbErr> {
+    let mut levels = vec![];
+    let mut parent_ids = realm_ids.to_vec();
+    while !parent_ids.is_empty() && levels.len() < MAX_REALM_DEPTH {
+        let children = realms::Entity::find()
+            .filter(realms::Column::ParentId.is_in(parent_ids))
+            .all(db)
+            .await?;
+        if children.is_empty() {
+            break;
+        }
+        parent_ids = children.iter().map(|child| child.id).collect();
+        levels.push(children);
+    }
+    Ok(levels)
+}
+
+pub async fn descendants<C: ConnectionTrait>(
+    db: &C,
+    realm_ids: &[Snowflake]
+) -> Result<Vec<realms::Model>, DbErr> {
+    Ok(descendant_levels(db, realm_ids).await?.into_iter().flatten().collect())
+}
+
+pub async fn list_children(
+    db: &DatabaseConnection,
+    realm_id: Snowflake
+) -> Result<Vec<realms::Model>, DbErr> {
+    realms::Entity::find()
+        .filter(realms::Column::ParentId.eq(realm_id))
+        .order_by_asc(realms::Column::Id)
+        .all(db)
+        .await
+}
+
+#[derive(Debug)]
+pub enum ParentError {
+    /// The parent doesn't exist or the user can't see it.
+    NotFound,
+    /// The user can't manage the parent realm.
+    Forbidden,
+    Archived,
+    /// The parent is the realm itself or nested inside it.
+    Cycle,
+    TooDeep,
+    Database(DbErr),
+}
+
+impl From<DbErr> for ParentError {
+    fn from(err: DbErr) -> Self {
+        ParentError::Database(err)
+    }
+}
+
+/// A realm the user can manage, directly or through a parent.
+pub async fn managed_realm(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    realm_id: Snowflake
+) -> Result<realms::Model, ParentError> {
+    let realm = realms::Entity::find_by_id(realm_id)
+        .one(db)
+        .await?
+        .ok_or(ParentError::NotFound)?;
+    let access = resolve_access(db, &realm, user_id)
+        .await?
+        .ok_or(ParentError::NotFound)?;
+    if !access.permissions.contains(RealmPermission::ManageRealm) {
+        return Err(ParentError::Forbidden);
+    }
+    Ok(realm)
+}
+
+/// Checks that `realm` (or a new realm, when `None`) can be nested in
+/// `parent_id` by the user, who needs to manage the parent.
+pub async fn check_parent(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    realm: Option<&realms::Model>,
+    parent_id: Snowflake
+) -> Result<realms::Model, ParentError> {
+    let parent = managed_realm(db, user_id, parent_id).await?;
+    if parent.archived_at.is_some() {
+        return Err(ParentError::Archived);
+    }
+
+    let parent_depth = ancestors(db, &parent).await?.len();
+    let subtree_depth = match realm {
+        Some(realm) => {
+            if realm.id == parent.id {
+                return Err(ParentError::Cycle);
+            }
+            let levels = descendant_levels(db, &[realm.id]).await?;
+            if levels.iter().flatten().any(|descendant| descendant.id == parent.id) {
+                return Err(ParentError::Cycle);
+            }
+            levels.len()
+        },
+        None => 0,
+    };
+    if parent_depth + 1 + subtree_depth > MAX_REALM_DEPTH {
+        return Err(ParentError::TooDeep);
+    }
+    Ok(parent)
+}
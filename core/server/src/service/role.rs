@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use crate::data::permissions::{BitwisePermissions, RealmPermissions};
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realm_member_roles, realm_members, realm_roles, realms};
+use crate::service::snowflake::next_snowflake;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Set};
+
+pub struct RoleChanges {
+    pub name: Option<String>,
+    pub permissions: Option<i16>,
+    pub colour: Option<Option<i32>>,
+    pub position: Option<i32>,
+}
+
+/// What a member can actually do: the union of their roles and their own
+/// grants, minus their denied bits. The realm owner can always do everything.
+pub fn combine_permissions(
+    realm: &realms::Model,
+    membership: &realm_members::Model,
+    roles: &[realm_roles::Model]
+) -> RealmPermissions {
+    if membership.user_id == realm.owner_id {
+        return RealmPermissions::all();
+    }
+
+    let granted = roles.iter().fold(membership.permissions, |bits, role| bits | role.permissions);
+    RealmPermissions::new(granted & !membership.denied_permissions)
+}
+
+pub async fn effective_permissions<C: ConnectionTrait>(
+    db: &C,
+    realm: &realms::Model,
+    membership: &realm_members::Model
+) -> Result<RealmPermissions, DbErr> {
+    let roles = member_roles(db, membership.id).await?;
+    Ok(combine_permissions(realm, membership, &roles))
+}
+
+pub async fn member_roles<C: ConnectionTrait>(
+    db: &C,
+    member_id: Snowflake
+) -> Result<Vec<realm_roles::Model>, DbErr> {
+    realm_roles::Entity::find()
+        .inner_join(realm_member_roles::Entity)
+        .filter(realm_member_roles::Column::MemberId.eq(member_id))
+        .order_by_asc(realm_roles::Column::Position)
+        .all(db)
+        .await
+}
+
+/// Roles of many memberships at once, keyed by membership id.
+pub async fn roles_by_member<C: ConnectionTrait>(
+    db: &C,
+    member_ids: &[Snowflake]
+) -> Result<HashMap<Snowflake, Vec<realm_roles::Model>>, DbErr> {
+    let rows = realm_member_roles::Entity::find()
+        .filter(realm_member_roles::Column::MemberId.is_in(member_ids.iter().copied()))
+        .find_also_related(realm_roles::Entity)
+        .all(db)
+        .await?;
+
+    let mut roles: HashMap<Snowflake, Vec<realm_roles::Model>> = HashMap::new();
+    for (assignment, role) in rows {
+        if let Some(role) = role {
+            roles.entry(assignment.member_id).or_default().push(role);
+        }
+    }
+    roles.values_mut().for_each(|roles| roles.sort_by_key(|role| role.position));
+    Ok(roles)
+}
+
+pub async fn list_roles(
+    db: &DatabaseConnection,
+    realm_id: Snowflake
+) -> Result<Vec<realm_roles::Model>, DbErr> {
+    realm_roles::Entity::find()
+        .filter(realm_roles::Column::RealmId.eq(realm_id))
+        .order_by_asc(realm_roles::Column::Position)
+        .all(db)
+        .await
+}
+
+pub async fn find_role(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    role_id: Snowflake
+) -> Result<Option<realm_roles::Model>, DbErr> {
+    realm_roles::Entity::find_by_id(role_id)
+        .filter(realm_roles::Column::RealmId.eq(realm_id))
+        .one(db)
+        .await
+}
+
+/// New roles go after the existing ones unless a position is given.
+pub async fn create_role(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    name: String,
+    permissions: i16,
+    colour: Option<i32>,
+    position: Option<i32>
+) -> Result<realm_roles::Model, DbErr> {
+    let position = match position {
+        Some(position) => position,
+        None => {
+            let last: Option<i32> = realm_roles::Entity::find()
+                .select_only()
+                .column_as(realm_roles::Column::Position.max(), "position")
+                .filter(realm_roles::Column::RealmId.eq(realm_id))
+                .into_tuple::<Option<i32>>()
+                .one(db)
+                .await?
+                .flatten();
+            last.map_or(0, |last| last + 1)
+        }
+    };
+
+    let model = realm_roles::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm_id),
+        name: Set(name),
+        permissions: Set(permissions),
+        colour: Set(colour),
+        position: Set(position),
+    };
+    model.insert(db).await
+}
+
+pub async fn update_role(
+    db: &DatabaseConnection,
+    role: realm_roles::Model,
+    changes: RoleChanges
+) -> Result<realm_roles::Model, DbErr> {
+    let mut active = role.into_active_model();
+    if let Some(name) = changes.name {
+        active.name = Set(name);
+    }
+    if let Some(permissions) = changes.permissions {
+        active.permissions = Set(permissions);
+    }
+    if let Some(colour) = changes.colour {
+        active.colour = Set(colour);
+    }
+    if let Some(position) = changes.position {
+        active.position = Set(position);
+    }
+    active.update(db).await
+}
+
+pub async fn delete_role(db: &DatabaseConnection, role_id: Snowflake) -> Result<(), DbErr> {
+    realm_roles::Entity::delete_by_id(role_id).exec(db).await?;
+    Ok(())
+}
+
+pub async fn assign_role(
+    db: &DatabaseConnection,
+    member_id: Snowflake,
+    role_id: Snowflake
+) -> Result<(), DbErr> {
+    let assignment = realm_member_roles::ActiveModel {
+        member_id: Set(member_id),
+        role_id: Set(role_id),
+    };
+    realm_member_roles::Entity::insert(assignment)
+        .on_conflict(
+            OnConflict::columns([realm_member_roles::Column::MemberId, realm_member_roles::Column::RoleId])
+                .do_nothing()
+                .to_owned()
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn unassign_role(
+    db: &DatabaseConnection,
+    member_id: Snowflake,
+    role_id: Snowflake
+) -> Result<bool, DbErr> {
+    let result = realm_member_roles::Entity::delete_many()
+        .filter(realm_member_roles::Column::MemberId.eq(member_id))
+        .filter(realm_member_roles::Column::RoleId.eq(role_id))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
@@ -1,23 +1,48 @@
 use std::str::FromStr;
 use sea_orm::QueryFilter;
 use sea_orm::ColumnTrait;
-use rrule::{RRule, Tz};
-use sea_orm::{DbErr, EntityTrait};
+use rrule::{RRule, RRuleError};
+use sea_orm::{DbErr, EntityTrait, QueryOrder};
 use sea_orm::Condition;
+use sea_orm::sea_query::Query;
 use crate::data::snowflake::Snowflake;
-use crate::schema::realm_events;
-use crate::web::routing::dto::{RealmEventDto, RealmEventOccurrenceDto, RealmScheduleDto};
+use crate::schema::{realm_event_overrides, realm_events, realms};
+use crate::web::routing::dto::{OccurrenceStatus, RealmEventDto, RealmEventOccurrenceDto, RealmScheduleDto};
 
+/// Most occurrences one series adds to a schedule. Anything past it sets the
+/// schedule's `truncated` flag.
+const MAX_OCCURRENCES: u16 = 1000;
+
+/// Everything in the realm that overlaps `[start, end)`: events running into
+/// the window count, and so do series that began long before it.
 pub async fn get_realm_schedule(
     db: &sea_orm::DatabaseConnection,
-    realm_id: Snowflake,
+    realm: &realms::Model,
     start: chrono::DateTime<chrono::Utc>,
     end: chrono::DateTime<chrono::Utc>
 ) -> Result<RealmScheduleDto, DbErr> {
+    let realm_id = realm.id;
     let events = realm_events::Entity::find()
         .filter(realm_events::Column::RealmId.eq(realm_id))
-        .filter(realm_events::Column::StartTime.gte(start))
-        .filter(realm_events::Column::StartTime.lte(end))
+        .filter(
+            Condition::any()
+                .add(realm_events::Column::StartTime.lt(end))
+                // An override can move an occurrence to before the series starts.
+                .add(realm_events::Column::Id.in_subquery(
+                    Query::select()
+                        .column(realm_event_overrides::Column::EventId)
+                        .from(realm_event_overrides::Entity)
+                        .and_where(realm_event_overrides::Column::StartTime.lt(end))
+                        .to_owned()
+                ))
+        )
+        .filter(
+            Condition::any()
+                .add(realm_events::Column::SeriesEnd.is_null())
+                .add(realm_events::Column::SeriesEnd.gte(start))
+        )
+        .order_by_asc(realm_events::Column::StartTime)
+        .order_by_asc(realm_events::Column::Id)
         .all(db)
         .await?;
     let mut occurrence_dtos = vec![];
@@ -30,17 +55,23 @@ pub async fn get_realm_schedule(
                 .add(
                     Condition::all()
                         .add(crate::schema::realm_tasks::Column::PlannedFor.gte(start))
-                        .add(crate::schema::realm_tasks::Column::PlannedFor.lte(end))
+                        .add(crate::schema::realm_tasks::Column::PlannedFor.lt(end))
                 )
                 .add(
                     Condition::all()
                         .add(crate::schema::realm_tasks::Column::DueDate.gte(start))
-                        .add(crate::schema::realm_tasks::Column::DueDate.lte(end))
+                        .add(crate::schema::realm_tasks::Column::DueDate.lt(end))
                 )
                 .add(
                     Condition::all()
                         .add(crate::schema::realm_tasks::Column::StartDate.gte(start))
-                        .add(crate::schema::realm_tasks::Column::StartDate.lte(end))
+                        .add(crate::schema::realm_tasks::Column::StartDate.lt(end))
+                )
+                // Tasks that started before the window and are due after it.
+                .add(
+                    Condition::all()
+                        .add(crate::schema::realm_tasks::Column::StartDate.lt(start))
+                        .add(crate::schema::realm_tasks::Column::DueDate.gte(end))
                 )
         )
         .all(db)
@@ -50,56 +81,160 @@ pub async fn get_realm_schedule(
         .map(|t| crate::web::routing::dto::TaskDto::from_model(t))
         .collect();
 
-    let mut i = 0;
-    for event in events {
-        let event_dto = RealmEventDto::from_model(&event);
-        event_dtos.push(event_dto.clone());
+    let event_ids: Vec<Snowflake> = events.iter().map(|event| event.id).collect();
+    let mut overrides = crate::service::event::overrides_by_event(db, &event_ids).await?;
 
-        let recurrence_rule = match event.recurrence {
-            Some(encoded) => RRule::from_str(&encoded).ok(),
-            None => None,
-        };
-        let occurrences = match recurrence_rule {
-            Some(rule) => {
-                let start_utc = event.start_time.with_timezone(&Tz::UTC);
-                let end_utc = end.with_timezone(&Tz::UTC);
-                rule
-                    .build(start_utc)
-                    .expect("Error building recurrence rule")
-                    .before(end_utc)
-                    .after(start_utc)
-                    .all(1000)
-                    .dates
-                    .into_iter()
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .collect()
-            },
-            None => {
-                if event.start_time >= start && event.start_time <= end {
-                    vec![event.start_time]
-                } else {
-                    vec![]
-                }
+    let mut truncated = false;
+    for event in events {
+        let event_duration = event.end_time.map(|end| end - event.start_time);
+        // Occurrences that start before the window can still run into it.
+        let lookback = event_duration.unwrap_or_default().max(chrono::Duration::zero());
+        let (mut starts, limited) = match occurrence_starts(&event, start - lookback, end) {
+            Ok(expansion) => expansion,
+            Err(err) => {
+                tracing::warn!("Skipping event {} with an invalid recurrence rule: {}", event.id.0, err);
+                continue;
             }
         };
-        let event_duration = match event.end_time {
-            Some(end) => Some(end - event.start_time),
-            None => None
-        };
+        truncated |= limited;
 
+        // Overrides can also move an occurrence in from outside the window.
+        // Those are checked against one more expansion spanning all of them,
+        // instead of walking the series again for each.
+        let index = event_dtos.len();
+        let event_overrides = overrides.remove(&event.id).unwrap_or_default();
+        let moved_in: Vec<_> = event_overrides
+            .iter()
+            .filter(|change| !starts.contains(&change.original_start))
+            .filter(|change| {
+                let moved = occurrence_dto(index, change.original_start, event_duration, &[], std::slice::from_ref(change));
+                overlaps(&moved, start, end)
+            })
+            .map(|change| change.original_start)
+            .collect();
+        if let (Some(first), Some(last)) = (moved_in.iter().min(), moved_in.iter().max())
+            && let Ok((series, _)) = occurrence_starts(&event, *first, *last) {
+            starts.extend(moved_in.iter().filter(|original| series.contains(original)));
+        }
 
-        for occurrence in occurrences {
-            occurrence_dtos.push(RealmEventOccurrenceDto {
-                event_index: i,
-                occurrence_start: occurrence,
-                occurrence_end: event_duration.map(|d| occurrence + d)
-            });
+        let occurrences: Vec<_> = starts
+            .into_iter()
+            .map(|occurrence| occurrence_dto(index, occurrence, event_duration, &event.exdates.0, &event_overrides))
+            .filter(|occurrence| overlaps(occurrence, start, end))
+            .collect();
+        if occurrences.is_empty() {
+            continue;
         }
-        i += 1;
+        event_dtos.push(RealmEventDto::from_model(&event));
+        occurrence_dtos.extend(occurrences);
     }
     Ok(RealmScheduleDto {
+        timezone: realm.timezone.clone(),
         events: event_dtos,
         tasks: task_dtos,
         occurrences: occurrence_dtos,
+        truncated,
     })
-}
\ No newline at end of file
+}
+
+/// Start times of the event between `after` and `before`, both inclusive, and
+/// whether the series had more than `MAX_OCCURRENCES` of them.
+fn occurrence_starts(
+    event: &realm_events::Model,
+    after: chrono::DateTime<chrono::Utc>,
+    before: chrono::DateTime<chrono::Utc>
+) -> Result<(Vec<chrono::DateTime<chrono::Utc>>, bool), RRuleError> {
+    let Some(encoded) = &event.recurrence else {
+        return Ok((vec![event.start_time], false));
+    };
+    // Expanding in the event's own zone keeps it at the same wall-clock time
+    // across daylight saving changes.
+    let timezone = crate::service::event::timezone_of(event);
+    let result = RRule::from_str(encoded)?
+        .build(event.start_time.with_timezone(&timezone))?
+        .after(after.with_timezone(&timezone))
+        .before(before.with_timezone(&timezone))
+        .all(MAX_OCCURRENCES + 1);
+    let limited = result.dates.len() > MAX_OCCURRENCES as usize;
+    let starts = result.dates
+        .into_iter()
+        .take(MAX_OCCURRENCES as usize)
+        .map(|date| date.with_timezone(&chrono::Utc))
+        .collect();
+    Ok((starts, limited))
+}
+
+/// Whether an occurrence overlaps `[start, end)`. Ones without a length only
+/// need to start inside it.
+fn overlaps(
+    occurrence: &RealmEventOccurrenceDto,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>
+) -> bool {
+    match occurrence.occurrence_end {
+        Some(occurrence_end) if occurrence_end > occurrence.occurrence_start => {
+            occurrence.occurrence_start < end && occurrence_end > start
+        },
+        _ => occurrence.occurrence_start >= start && occurrence.occurrence_start < end,
+    }
+}
+
+/// Cancelled occurrences keep their place in the series, overridden ones take
+/// the override's times.
+fn occurrence_dto(
+    event_index: usize,
+    occurrence: chrono::DateTime<chrono::Utc>,
+    duration: Option<chrono::Duration>,
+    exdates: &[chrono::DateTime<chrono::Utc>],
+    overrides: &[realm_event_overrides::Model]
+) -> RealmEventOccurrenceDto {
+    let mut dto = RealmEventOccurrenceDto {
+        event_index,
+        occurrence_start: occurrence,
+        occurrence_end: duration.map(|d| occurrence + d),
+        status: OccurrenceStatus::Scheduled,
+        original_start: None,
+        override_id: None,
+        name: None,
+        description: None,
+        location: None,
+    };
+    if exdates.contains(&occurrence) {
+        dto.status = OccurrenceStatus::Cancelled;
+    } else if let Some(change) = overrides.iter().find(|change| change.original_start == occurrence) {
+        let start = change.start_time.unwrap_or(occurrence);
+        dto.status = OccurrenceStatus::Overridden;
+        dto.occurrence_start = start;
+        dto.occurrence_end = change.end_time.or(duration.map(|d| start + d));
+        dto.original_start = Some(occurrence);
+        dto.override_id = Some(change.id);
+        dto.name = change.name.clone();
+        dto.description = change.description.clone();
+        dto.location = change.location.clone();
+    }
+    dto
+}
+
+/// Puts several realms' schedules into one, keeping each occurrence pointed at
+/// its own event. The first schedule's realm sets the time zone.
+pub fn merge_schedules(schedules: Vec<RealmScheduleDto>) -> RealmScheduleDto {
+    let mut merged = RealmScheduleDto {
+        timezone: schedules.first().map_or_else(|| "UTC".to_string(), |schedule| schedule.timezone.clone()),
+        events: vec![],
+        tasks: vec![],
+        occurrences: vec![],
+        truncated: false,
+    };
+    for schedule in schedules {
+        merged.truncated |= schedule.truncated;
+        let offset = merged.events.len();
+        merged.occurrences.extend(schedule.occurrences.into_iter().map(|occurrence| RealmEventOccurrenceDto {
+            event_index: occurrence.event_index + offset,
+            ..occurrence
+        }));
+        merged.events.extend(schedule.events);
+        merged.tasks.extend(schedule.tasks);
+    }
+    merged.occurrences.sort_by_key(|occurrence| occurrence.occurrence_start);
+    merged
+}
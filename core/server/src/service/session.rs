@@ -0,0 +1,115 @@
+use crate::data::snowflake::Snowflake;
+use crate::schema::sessions;
+use crate::service;
+use crate::service::snowflake::next_snowflake;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+
+const LAST_SEEN_GRANULARITY_SECS: i64 = 60;
+
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub device: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+pub async fn create_session(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    client: ClientInfo
+) -> Result<sessions::Model, DbErr> {
+    let now = chrono::Utc::now();
+    let session = sessions::ActiveModel {
+        id: Set(next_snowflake()),
+        user_id: Set(user_id),
+        device: Set(client.device),
+        user_agent: Set(client.user_agent),
+        ip_address: Set(client.ip_address),
+        created_at: Set(now),
+        last_seen_at: Set(now),
+        revoked_at: Set(None),
+    };
+    session.insert(db).await
+}
+
+pub async fn find_active_session(
+    db: &DatabaseConnection,
+    session_id: Snowflake,
+    user_id: Snowflake
+) -> Result<Option<sessions::Model>, DbErr> {
+    sessions::Entity::find_by_id(session_id)
+        .filter(sessions::Column::UserId.eq(user_id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .one(db)
+        .await
+}
+
+pub async fn list_active_sessions(
+    db: &DatabaseConnection,
+    user_id: Snowflake
+) -> Result<Vec<sessions::Model>, DbErr> {
+    sessions::Entity::find()
+        .filter(sessions::Column::UserId.eq(user_id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .order_by_desc(sessions::Column::LastSeenAt)
+        .all(db)
+        .await
+}
+
+/// Bumps `last_seen_at`, skipping the write when the session was seen recently.
+pub async fn touch_session(
+    db: &DatabaseConnection,
+    session: &sessions::Model
+) -> Result<(), DbErr> {
+    let now = chrono::Utc::now();
+    if (now - session.last_seen_at).num_seconds() < LAST_SEEN_GRANULARITY_SECS {
+        return Ok(());
+    }
+    sessions::Entity::update_many()
+        .col_expr(sessions::Column::LastSeenAt, Expr::value(now))
+        .filter(sessions::Column::Id.eq(session.id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Revokes a session and every refresh token issued for it.
+pub async fn revoke_session(
+    db: &DatabaseConnection,
+    session_id: Snowflake
+) -> Result<(), DbErr> {
+    sessions::Entity::update_many()
+        .col_expr(sessions::Column::RevokedAt, Expr::value(chrono::Utc::now()))
+        .filter(sessions::Column::Id.eq(session_id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    service::auth::revoke_refresh_family(db, session_id).await
+}
+
+pub async fn revoke_other_sessions(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    current_session_id: Snowflake
+) -> Result<u64, DbErr> {
+    let others = list_active_sessions(db, user_id)
+        .await?
+        .into_iter()
+        .filter(|s| s.id != current_session_id)
+        .collect::<Vec<_>>();
+    for session in &others {
+        revoke_session(db, session.id).await?;
+    }
+    Ok(others.len() as u64)
+}
+
+pub async fn revoke_all_sessions(
+    db: &DatabaseConnection,
+    user_id: Snowflake
+) -> Result<(), DbErr> {
+    for session in list_active_sessions(db, user_id).await? {
+        revoke_session(db, session.id).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realm_share_links, realms};
+use crate::service;
+use crate::service::snowflake::next_snowflake;
+use crate::web::routing::dto::RealmScheduleDto;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+
+/// Share links are meant to be posted publicly, so they have to be unguessable.
+const TOKEN_BYTES: usize = 24;
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub async fn create_link(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    created_by: Snowflake,
+    include_tasks: bool,
+    include_descriptions: bool
+) -> Result<realm_share_links::Model, DbErr> {
+    let model = realm_share_links::ActiveModel {
+        id: Set(next_snowflake()),
+        realm_id: Set(realm_id),
+        created_by: Set(created_by),
+        token: Set(generate_token()),
+        include_tasks: Set(include_tasks),
+        include_descriptions: Set(include_descriptions),
+        created_at: Set(chrono::Utc::now()),
+        revoked_at: Set(None),
+    };
+    model.insert(db).await
+}
+
+pub async fn list_links(
+    db: &DatabaseConnection,
+    realm_id: Snowflake
+) -> Result<Vec<realm_share_links::Model>, DbErr> {
+    realm_share_links::Entity::find()
+        .filter(realm_share_links::Column::RealmId.eq(realm_id))
+        .filter(realm_share_links::Column::RevokedAt.is_null())
+        .order_by_desc(realm_share_links::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+pub async fn revoke_link(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    link_id: Snowflake
+) -> Result<bool, DbErr> {
+    let result = realm_share_links::Entity::update_many()
+        .col_expr(realm_share_links::Column::RevokedAt, Expr::value(chrono::Utc::now()))
+        .filter(realm_share_links::Column::Id.eq(link_id))
+        .filter(realm_share_links::Column::RealmId.eq(realm_id))
+        .filter(realm_share_links::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// The link behind a token and the realm it shares, unless it was revoked.
+pub async fn find_active_link(
+    db: &DatabaseConnection,
+    token: &str
+) -> Result<Option<(realm_share_links::Model, realms::Model)>, DbErr> {
+    let link = realm_share_links::Entity::find()
+        .filter(realm_share_links::Column::Token.eq(token))
+        .filter(realm_share_links::Column::RevokedAt.is_null())
+        .find_also_related(realms::Entity)
+        .one(db)
+        .await?;
+    Ok(link.and_then(|(link, realm)| realm.map(|realm| (link, realm))))
+}
+
+/// The realm's schedule, trimmed down to what the link was created to show.
+pub async fn shared_schedule(
+    db: &DatabaseConnection,
+    link: &realm_share_links::Model,
+    realm: &realms::Model,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>
+) -> Result<RealmScheduleDto, DbErr> {
+    let mut schedule = service::schedule::get_realm_schedule(db, realm, start, end).await?;
+    // Viewers aren't members, so they don't get to see who's behind what.
+    schedule.events.iter_mut().for_each(|event| event.created_by = Snowflake(0));
+    schedule.tasks.iter_mut().for_each(|task| task.created_by = Snowflake(0));
+    if !link.include_tasks {
+        schedule.tasks.clear();
+    }
+    if !link.include_descriptions {
+        schedule.events.iter_mut().for_each(|event| event.description = None);
+        schedule.tasks.iter_mut().for_each(|task| task.description = None);
+        schedule.occurrences.iter_mut().for_each(|occurrence| occurrence.description = None);
+    }
+    Ok(schedule)
+}
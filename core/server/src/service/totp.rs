@@ -0,0 +1,107 @@
+use crate::schema::users;
+use crate::schema::users::RecoveryCodes;
+use crate::util::token::hash_token;
+use rand::distributions::Alphanumeric;
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, IntoActiveModel, Set};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const ISSUER: &str = "Nebula";
+const DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+/// How many steps before and after the current one are still accepted, to
+/// tolerate clock drift between the server and the authenticator.
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const SECRET_BYTES: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+}
+
+pub fn otpauth_uri(secret: &str, account: &str) -> String {
+    build_totp(secret, account).get_url()
+}
+
+/// Checks a code against the secret and returns the time step it belongs to.
+/// Steps at or before `last_step` are rejected so a code can't be replayed.
+pub fn verify_code(secret: &str, code: &str, last_step: Option<i64>) -> Option<i64> {
+    let totp = build_totp(secret, "");
+    let current_step = (chrono::Utc::now().timestamp() as u64 / STEP_SECS) as i64;
+
+    (-ALLOWED_DRIFT_STEPS..=ALLOWED_DRIFT_STEPS)
+        .map(|offset| current_step + offset)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| {
+            let expected = totp.generate(*step as u64 * STEP_SECS);
+            constant_time_eq(expected.as_bytes(), code.as_bytes())
+        })
+}
+
+/// Generates a fresh set of recovery codes, returning them in plain text for
+/// the user along with the hashes that get stored.
+pub fn generate_recovery_codes() -> (Vec<String>, RecoveryCodes) {
+    let codes = (0..RECOVERY_CODE_COUNT)
+        .map(|_| OsRng
+            .sample_iter(&Alphanumeric)
+            .take(RECOVERY_CODE_LENGTH)
+            .map(|c| (c as char).to_ascii_lowercase())
+            .collect::<String>()
+        )
+        .collect::<Vec<_>>();
+    let hashes = codes.iter().map(|code| hash_token(code)).collect();
+    (codes, RecoveryCodes(hashes))
+}
+
+/// Verifies a second factor for a user with TOTP enabled. Both authenticator
+/// codes and recovery codes are accepted, and either is consumed on success.
+pub async fn verify_second_factor(
+    db: &DatabaseConnection,
+    user: users::Model,
+    code: &str
+) -> Result<Option<users::Model>, DbErr> {
+    let Some(secret) = user.totp_secret.clone().filter(|_| user.totp_enabled) else {
+        return Ok(None);
+    };
+
+    let code = code.trim().replace(' ', "");
+    if let Some(step) = verify_code(&secret, &code, user.totp_last_step) {
+        let mut active_user = user.into_active_model();
+        active_user.totp_last_step = Set(Some(step));
+        return active_user.update(db).await.map(Some);
+    }
+
+    let mut recovery_codes = user.totp_recovery_codes.clone().unwrap_or_default();
+    let hash = hash_token(&code.to_ascii_lowercase());
+    let Some(index) = recovery_codes.0.iter().position(|stored| *stored == hash) else {
+        return Ok(None);
+    };
+    recovery_codes.0.remove(index);
+
+    let mut active_user = user.into_active_model();
+    active_user.totp_recovery_codes = Set(Some(recovery_codes));
+    active_user.update(db).await.map(Some)
+}
+
+fn build_totp(secret: &str, account: &str) -> TOTP {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .expect("Stored TOTP secret is not valid base32");
+    TOTP::new_unchecked(
+        Algorithm::SHA1,
+        DIGITS,
+        0,
+        STEP_SECS,
+        secret,
+        Some(ISSUER.to_string()),
+        account.to_string()
+    )
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
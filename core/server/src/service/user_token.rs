@@ -0,0 +1,80 @@
+use crate::data::snowflake::Snowflake;
+use crate::schema::user_tokens;
+use crate::schema::user_tokens::Purpose;
+use crate::service::snowflake::next_snowflake;
+use crate::util::token::{generate_token, hash_token};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set};
+
+impl Purpose {
+    pub fn ttl(&self) -> chrono::Duration {
+        match self {
+            Purpose::PasswordReset => chrono::Duration::hours(1),
+            Purpose::EmailVerification | Purpose::EmailChange => chrono::Duration::hours(24),
+        }
+    }
+}
+
+/// Issues a one-time token for the given purpose, invalidating any earlier token
+/// of the same purpose that was not used yet. Only the hash is stored.
+pub async fn issue_token(
+    db: &DatabaseConnection,
+    user_id: Snowflake,
+    purpose: Purpose,
+    payload: Option<String>
+) -> Result<String, DbErr> {
+    let now = chrono::Utc::now();
+    user_tokens::Entity::update_many()
+        .col_expr(user_tokens::Column::ConsumedAt, Expr::value(now))
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .filter(user_tokens::Column::ConsumedAt.is_null())
+        .exec(db)
+        .await?;
+
+    let token = generate_token();
+    let model = user_tokens::ActiveModel {
+        id: Set(next_snowflake()),
+        user_id: Set(user_id),
+        purpose: Set(purpose),
+        token_hash: Set(hash_token(&token)),
+        created_at: Set(now),
+        expires_at: Set(now + purpose.ttl()),
+        consumed_at: Set(None),
+        payload: Set(payload),
+    };
+    model.insert(db).await?;
+    Ok(token)
+}
+
+/// Marks a token as used and returns it, or `None` when the token is unknown,
+/// expired, already used or meant for something else.
+pub async fn consume_token(
+    db: &DatabaseConnection,
+    token: &str,
+    purpose: Purpose
+) -> Result<Option<user_tokens::Model>, DbErr> {
+    let now = chrono::Utc::now();
+    let existing = user_tokens::Entity::find()
+        .filter(user_tokens::Column::TokenHash.eq(hash_token(token)))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .one(db)
+        .await?;
+    let Some(existing) = existing else {
+        return Ok(None);
+    };
+    if existing.consumed_at.is_some() || existing.expires_at <= now {
+        return Ok(None);
+    }
+
+    let claimed = user_tokens::Entity::update_many()
+        .col_expr(user_tokens::Column::ConsumedAt, Expr::value(now))
+        .filter(user_tokens::Column::Id.eq(existing.id))
+        .filter(user_tokens::Column::ConsumedAt.is_null())
+        .exec(db)
+        .await?;
+    if claimed.rows_affected == 0 {
+        return Ok(None);
+    }
+    Ok(Some(existing))
+}
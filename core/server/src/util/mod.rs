@@ -1 +1,3 @@
-pub mod validation;
\ No newline at end of file
+pub mod validation;
+pub mod token;
+pub mod patch;
\ No newline at end of file
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Deserializer};
+
+/// For optional fields of PATCH bodies that can also be cleared. Combined with
+/// `#[serde(default)]`, a missing field becomes `None` and an explicit `null`
+/// becomes `Some(None)`.
+pub fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -4,4 +4,12 @@ pub fn is_sane(name: &str, _: &()) -> garde::Result {
     } else {
         Err(garde::Error::new("contains invalid control characters"))
     }
-}
\ No newline at end of file
+}
+
+/// An IANA time zone name, such as `Europe/Berlin`.
+pub fn is_timezone(name: &str, _: &()) -> garde::Result {
+    match name.parse::<chrono_tz::Tz>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(garde::Error::new("is not a known time zone")),
+    }
+}
@@ -1,14 +1,18 @@
 use super::{MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
 use crate::app::NebulaApp;
 use crate::schema::users;
-use crate::web::routing::auth::{generate_jwt_token, AuthResponse};
+use crate::service;
+use crate::service::password::PasswordCheck;
+use crate::service::session::ClientInfo;
+use crate::web::routing::auth::{create_auth_response, AuthResponse};
 use crate::web::routing::error::NebulaResponse;
-use crate::web::routing::error::{error, ok};
+use crate::web::routing::error::{error, ok, RateLimited};
+use crate::web::routing::middlewares::client::RequestClient;
 use crate::web::routing::middlewares::validation::ValidJson;
-use argon2::{PasswordHash, PasswordVerifier};
 use axum::extract::State;
+use chrono::{DateTime, Utc};
 use axum::http::StatusCode;
-use sea_orm::ColumnTrait;
+use sea_orm::{ActiveModelTrait, ColumnTrait, IntoActiveModel, Set};
 use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
 
@@ -20,34 +24,137 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, garde::Validate)]
+pub struct TwoFactorLoginRequest {
+    #[garde(length(min = 1, max = 1024))]
+    pub challenge_token: String,
+    #[garde(length(min = 6, max = 32))]
+    pub code: String,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
 pub async fn login_handler(
     State(app): State<NebulaApp>,
+    RequestClient(client): RequestClient,
     ValidJson(payload): ValidJson<LoginRequest>
-) -> NebulaResponse<AuthResponse> {
+) -> Result<NebulaResponse<LoginResponse>, RateLimited> {
+    let locked_for = app.state.read().await.login_lockouts.locked_for(&payload.email);
+    if let Some(retry_after) = locked_for {
+        return Err(RateLimited { retry_after });
+    }
+
     let user = users::Entity::find()
         .filter(users::Column::Email.eq(payload.email.clone()))
         .one(&app.db)
         .await
         .expect("Failed to query the database");
 
-    if user.is_none() {
-        return error(StatusCode::UNAUTHORIZED, "Invalid email or password");
+    let Some(mut user) = user else {
+        return Ok(failed_login(&app, &payload.email).await);
+    };
+    // Accounts created through an external provider don't have a password.
+    let Some(password_hash) = user.password_hash.as_deref() else {
+        return Ok(failed_login(&app, &payload.email).await);
+    };
+
+    match service::password::verify_password(&app.config, password_hash, &payload.password) {
+        PasswordCheck::Invalid => {
+            return Ok(failed_login(&app, &payload.email).await);
+        },
+        PasswordCheck::ValidNeedsRehash => {
+            let mut active_user = user.into_active_model();
+            active_user.password_hash = Set(Some(service::password::hash_password(&app.config, &payload.password)));
+            user = active_user.update(&app.db)
+                .await
+                .expect("Failed to upgrade password hash");
+        },
+        PasswordCheck::Valid => {}
+    }
+
+    // With a second factor the failures only clear once that passes too, or
+    // the password could be used to reset the count between code guesses.
+    if !user.totp_enabled {
+        app.state.write().await.login_lockouts.clear(&payload.email);
+    }
+    Ok(ok(login_response(&app, &user, client).await))
+}
+
+/// Counts the failure towards the account's lockout, for unknown emails too so
+/// responses don't reveal which accounts exist.
+async fn failed_login(app: &NebulaApp, email: &str) -> NebulaResponse<LoginResponse> {
+    record_failure(app, email).await;
+    error(StatusCode::UNAUTHORIZED, "Invalid email or password")
+}
+
+async fn record_failure(app: &NebulaApp, email: &str) {
+    if app.config.rate_limits.enabled {
+        app.state.write().await.login_lockouts.record_failure(email, &app.config.rate_limits);
     }
-    let user = user.unwrap();
-    let password_hash = PasswordHash::new(&user.password_hash).expect("Failed to hash password");
-
-    let is_password_valid = app.config.argon2.verify_password(
-        payload.password.as_bytes(),
-        &password_hash
-    ).is_ok();
-    if !is_password_valid {
-        return error(StatusCode::UNAUTHORIZED, "Invalid email or password");
+}
+
+/// Logs the user in, or hands out a challenge when a second factor is needed.
+pub async fn login_response(
+    app: &NebulaApp,
+    user: &users::Model,
+    client: ClientInfo
+) -> LoginResponse {
+    if user.totp_enabled {
+        let (challenge_token, expires_at) = service::auth::sign_two_factor_challenge(&app.config, user.id);
+        return LoginResponse::TwoFactorRequired(TwoFactorChallenge {
+            two_factor_required: true,
+            challenge_token,
+            expires_at,
+        });
     }
-    let dto =  crate::web::routing::dto::UserDto::from_model(&user);
 
-    let token = generate_jwt_token(&app.config.jwt_key, user.id.0);
-    ok(AuthResponse {
-        user: dto,
-        token,
-    })
+    LoginResponse::Authenticated(create_auth_response(app, user, client).await)
+}
+
+pub async fn two_factor_login_handler(
+    State(app): State<NebulaApp>,
+    RequestClient(client): RequestClient,
+    ValidJson(payload): ValidJson<TwoFactorLoginRequest>
+) -> Result<NebulaResponse<AuthResponse>, RateLimited> {
+    let user_id = service::auth::verify_two_factor_challenge(&app.config, &payload.challenge_token);
+    let Some(user_id) = user_id else {
+        return Ok(error(StatusCode::UNAUTHORIZED, "Invalid or expired challenge"));
+    };
+
+    let user = users::Entity::find_by_id(user_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query the database");
+    let Some(user) = user else {
+        return Ok(error(StatusCode::UNAUTHORIZED, "Invalid or expired challenge"));
+    };
+    // Wrong codes count towards the same lockout as wrong passwords.
+    let locked_for = app.state.read().await.login_lockouts.locked_for(&user.email);
+    if let Some(retry_after) = locked_for {
+        return Err(RateLimited { retry_after });
+    }
+
+    let email = user.email.clone();
+    let user = service::totp::verify_second_factor(&app.db, user, &payload.code)
+        .await
+        .expect("Failed to verify second factor");
+    let Some(user) = user else {
+        record_failure(&app, &email).await;
+        return Ok(error(StatusCode::UNAUTHORIZED, "Invalid authentication code"));
+    };
+
+    app.state.write().await.login_lockouts.clear(&email);
+    Ok(ok(create_auth_response(&app, &user, client).await))
 }
\ No newline at end of file
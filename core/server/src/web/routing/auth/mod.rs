@@ -1,11 +1,17 @@
-use std::collections::BTreeMap;
-use hmac::Hmac;
-use jwt::SignWithKey;
-use sha2::Sha256;
+use crate::app::{AppConfig, NebulaApp};
+use crate::data::snowflake::Snowflake;
+use crate::schema::users;
+use crate::service;
+use crate::service::session::ClientInfo;
 use crate::web::routing::dto::UserDto;
+use chrono::{DateTime, Utc};
 
 pub mod login;
 pub mod signup;
+pub mod refresh;
+pub mod password;
+pub mod verification;
+pub mod oidc;
 
 pub const MIN_PASSWORD_LENGTH: usize = 8;
 pub const MAX_PASSWORD_LENGTH: usize = 128;
@@ -14,11 +20,44 @@ pub const MAX_PASSWORD_LENGTH: usize = 128;
 pub struct AuthResponse {
     pub user: UserDto,
     pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub refresh_token: String,
+    pub refresh_expires_at: DateTime<Utc>,
 }
 
-pub fn generate_jwt_token(key: &Hmac<Sha256>, user_id: u64) -> String {
-    let mut token_claims = BTreeMap::new();
-    token_claims.insert("user_id", user_id.to_string());
-    token_claims.sign_with_key(key)
-        .expect("Failed to sign JWT")
-}
\ No newline at end of file
+pub fn generate_jwt_token(
+    config: &AppConfig,
+    user_id: Snowflake,
+    session_id: Snowflake
+) -> (String, DateTime<Utc>) {
+    service::auth::sign_access_token(config, user_id, session_id)
+}
+
+/// Opens a new session and issues its access token along with the first refresh
+/// token of the session's family.
+pub async fn create_auth_response(
+    app: &NebulaApp,
+    user: &users::Model,
+    client: ClientInfo
+) -> AuthResponse {
+    let session = service::session::create_session(&app.db, user.id, client)
+        .await
+        .expect("Failed to create session");
+    let (token, expires_at) = generate_jwt_token(&app.config, user.id, session.id);
+    let refresh = service::auth::issue_refresh_token(
+        &app.config,
+        &app.db,
+        user.id,
+        session.id
+    )
+        .await
+        .expect("Failed to issue refresh token");
+
+    AuthResponse {
+        user: UserDto::from_model(user),
+        token,
+        expires_at,
+        refresh_token: refresh.token,
+        refresh_expires_at: refresh.expires_at,
+    }
+}
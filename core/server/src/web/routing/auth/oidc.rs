@@ -0,0 +1,114 @@
+use crate::app::NebulaApp;
+use crate::service;
+use crate::service::identity::IdentityError;
+use crate::service::oidc::OidcError;
+use crate::web::routing::auth::login::{login_response, LoginResponse};
+use crate::web::routing::error::{error, ok, NebulaResponse};
+use crate::web::routing::middlewares::client::RequestClient;
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProvidersObject {
+    pub providers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuthorizationObject {
+    pub authorization_url: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct OidcCallbackRequest {
+    #[garde(length(min = 1, max = 2048))]
+    pub code: String,
+    #[garde(length(min = 1, max = 256))]
+    pub state: String,
+}
+
+pub async fn list_providers(
+    State(app): State<NebulaApp>
+) -> NebulaResponse<ProvidersObject> {
+    let providers = app.config.oidc_providers
+        .iter()
+        .map(|provider| provider.name.clone())
+        .collect();
+    ok(ProvidersObject { providers })
+}
+
+pub async fn authorize(
+    Path(provider): Path<String>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<AuthorizationObject> {
+    start_authorization(&app, &provider, None).await
+}
+
+pub async fn callback(
+    Path(provider): Path<String>,
+    State(app): State<NebulaApp>,
+    RequestClient(client): RequestClient,
+    ValidJson(payload): ValidJson<OidcCallbackRequest>
+) -> NebulaResponse<LoginResponse> {
+    let Some(provider) = app.config.oidc_provider(&provider) else {
+        return error(StatusCode::NOT_FOUND, "Unknown login provider");
+    };
+
+    let completed = service::oidc::complete_authorization(&app, provider, &payload.code, &payload.state).await;
+    let completed = match completed {
+        Ok(completed) => completed,
+        Err(err) => return oidc_error(err),
+    };
+
+    let user = service::identity::resolve_user(
+        &app.db,
+        &provider.name,
+        &completed.claims,
+        completed.link_user_id
+    ).await;
+    let user = match user {
+        Ok(user) => user,
+        Err(IdentityError::MissingEmail) => {
+            return error(StatusCode::BAD_REQUEST, "The provider did not share an email address");
+        },
+        Err(IdentityError::EmailTaken) => {
+            return error(StatusCode::CONFLICT, "An account with this email already exists, log in to link this provider");
+        },
+        Err(IdentityError::AlreadyLinked) => {
+            return error(StatusCode::CONFLICT, "This identity is already linked to another account");
+        },
+        Err(IdentityError::Database(err)) => panic!("Failed to resolve external identity: {err}"),
+    };
+
+    ok(login_response(&app, &user, client).await)
+}
+
+pub async fn start_authorization(
+    app: &NebulaApp,
+    provider: &str,
+    link_user_id: Option<crate::data::snowflake::Snowflake>
+) -> NebulaResponse<AuthorizationObject> {
+    let Some(provider) = app.config.oidc_provider(provider) else {
+        return error(StatusCode::NOT_FOUND, "Unknown login provider");
+    };
+    match service::oidc::begin_authorization(app, provider, link_user_id).await {
+        Ok(authorization_url) => ok(AuthorizationObject { authorization_url }),
+        Err(err) => oidc_error(err),
+    }
+}
+
+fn oidc_error<T: Serialize>(err: OidcError) -> NebulaResponse<T> {
+    match err {
+        OidcError::InvalidState => error(StatusCode::BAD_REQUEST, "Invalid or expired login attempt"),
+        OidcError::InvalidToken(reason) => {
+            tracing::warn!("Rejected ID token: {}", reason);
+            error(StatusCode::UNAUTHORIZED, "The provider returned an invalid ID token")
+        },
+        OidcError::Provider(reason) => {
+            tracing::error!("Login provider request failed: {}", reason);
+            error(StatusCode::BAD_GATEWAY, "The login provider could not be reached")
+        },
+        OidcError::Database(err) => panic!("Failed to process external login: {err}"),
+    }
+}
@@ -0,0 +1,85 @@
+use super::{MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
+use crate::app::NebulaApp;
+use crate::mail::messages;
+use crate::schema::user_tokens::Purpose;
+use crate::schema::users;
+use crate::service;
+use crate::util::validation::is_sane;
+use crate::web::routing::error::{error, no_content, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::State;
+use axum::http::StatusCode;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, Set};
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, garde::Validate)]
+pub struct ForgotPasswordRequest {
+    #[garde(email)]
+    pub email: String,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, garde::Validate)]
+pub struct ResetPasswordRequest {
+    #[garde(length(min = 1, max = 256))]
+    pub token: String,
+    #[garde(length(min = MIN_PASSWORD_LENGTH, max = MAX_PASSWORD_LENGTH), custom(is_sane))]
+    pub password: String,
+}
+
+/// Always answers with 204 so the endpoint can't be used to find out which
+/// emails have an account.
+pub async fn forgot_password_handler(
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<ForgotPasswordRequest>
+) -> NebulaResponse<()> {
+    let user = users::Entity::find()
+        .filter(users::Column::Email.eq(payload.email))
+        .one(&app.db)
+        .await
+        .expect("Failed to query user");
+    let Some(user) = user else {
+        return no_content();
+    };
+
+    let token = service::user_token::issue_token(&app.db, user.id, Purpose::PasswordReset, None)
+        .await
+        .expect("Failed to issue password reset token");
+    let message = messages::password_reset(&app.config, &user.email, &token);
+    if let Err(err) = app.mailer.send(message).await {
+        tracing::error!("Failed to send password reset email: {}", err.0);
+    }
+    no_content()
+}
+
+pub async fn reset_password_handler(
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<ResetPasswordRequest>
+) -> NebulaResponse<()> {
+    let token = service::user_token::consume_token(&app.db, &payload.token, Purpose::PasswordReset)
+        .await
+        .expect("Failed to consume password reset token");
+    let Some(token) = token else {
+        return error(StatusCode::BAD_REQUEST, "Invalid or expired token");
+    };
+    let user_id = token.user_id;
+
+    let user = users::Entity::find_by_id(user_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query user");
+    let Some(user) = user else {
+        return error(StatusCode::BAD_REQUEST, "Invalid or expired token");
+    };
+
+    let password_hash = service::password::hash_password(&app.config, &payload.password);
+    let mut active_user = user.into_active_model();
+    active_user.password_hash = Set(Some(password_hash));
+    active_user.updated_at = Set(chrono::Utc::now().naive_utc());
+    active_user.update(&app.db)
+        .await
+        .expect("Failed to update password");
+
+    service::session::revoke_all_sessions(&app.db, user_id)
+        .await
+        .expect("Failed to revoke sessions");
+    no_content()
+}
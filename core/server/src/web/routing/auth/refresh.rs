@@ -0,0 +1,57 @@
+use crate::app::NebulaApp;
+use crate::schema::users;
+use crate::service;
+use crate::service::auth::RefreshError;
+use crate::web::routing::auth::{generate_jwt_token, AuthResponse};
+use crate::web::routing::dto::UserDto;
+use crate::web::routing::error::{error, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::State;
+use axum::http::StatusCode;
+use sea_orm::EntityTrait;
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, garde::Validate)]
+pub struct RefreshTokenRequest {
+    #[garde(length(min = 1, max = 256))]
+    pub refresh_token: String,
+}
+
+pub async fn refresh_handler(
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<RefreshTokenRequest>
+) -> NebulaResponse<AuthResponse> {
+    let rotation = service::auth::rotate_refresh_token(
+        &app.config,
+        &app.db,
+        &payload.refresh_token
+    ).await;
+
+    let refresh = match rotation {
+        Ok(rotated) => rotated,
+        Err(RefreshError::Invalid) => {
+            return error(StatusCode::UNAUTHORIZED, "Invalid refresh token");
+        },
+        Err(RefreshError::Reused) => {
+            return error(StatusCode::UNAUTHORIZED, "Refresh token was already used, all sessions from it were revoked");
+        },
+        Err(RefreshError::Database(err)) => panic!("Failed to rotate refresh token: {err}"),
+    };
+
+    let user = users::Entity::find_by_id(refresh.user_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query user");
+    if user.is_none() {
+        return error(StatusCode::UNAUTHORIZED, "Invalid refresh token");
+    }
+    let user = user.unwrap();
+
+    let (token, expires_at) = generate_jwt_token(&app.config, user.id, refresh.family_id);
+    ok(AuthResponse {
+        user: UserDto::from_model(&user),
+        token,
+        expires_at,
+        refresh_token: refresh.token,
+        refresh_expires_at: refresh.expires_at,
+    })
+}
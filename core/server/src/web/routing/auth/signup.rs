@@ -1,12 +1,12 @@
 use crate::util::validation::is_sane;
-use argon2::PasswordHasher;
 use axum::{extract::State, http::StatusCode};
 use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
 
 use super::{MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
-use crate::web::routing::auth::{generate_jwt_token, AuthResponse};
-use crate::web::routing::dto::UserDto;
-use crate::{app::NebulaApp, schema::users, service::snowflake::next_snowflake, web::routing::error::{error, ok, NebulaResponse}};
+use crate::web::routing::auth::{create_auth_response, AuthResponse};
+use crate::web::routing::auth::verification::send_verification_email;
+use crate::{app::NebulaApp, schema::users, service, service::snowflake::next_snowflake, web::routing::error::{error, ok, NebulaResponse}};
+use crate::web::routing::middlewares::client::RequestClient;
 use crate::web::routing::middlewares::validation::ValidJson;
 
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, garde::Validate)]
@@ -21,6 +21,7 @@ pub struct SignupRequest {
 
 pub async fn signup_handler(
     State(app): State<NebulaApp>,
+    RequestClient(client): RequestClient,
     ValidJson(payload): ValidJson<SignupRequest>
 ) -> NebulaResponse<AuthResponse> {
     let db = &app.db;
@@ -37,29 +38,25 @@ pub async fn signup_handler(
         );
     }
 
-    let password_hash = &app.config.argon2.hash_password(
-        payload.password.as_bytes(),
-        &app.config.argon_salt
-    ).expect("Failed to hash password").to_string();
+    let password_hash = service::password::hash_password(&app.config, &payload.password);
 
     let user_id = next_snowflake();
     let user = users::ActiveModel {
         id: Set(user_id),
-        name: Set(payload.name.clone()),
+        name: Set(payload.name),
         email: Set(payload.email),
-        password_hash: Set(password_hash.to_owned()),
+        password_hash: Set(Some(password_hash)),
+        email_verified: Set(false),
+        totp_secret: Set(None),
+        totp_enabled: Set(false),
+        totp_recovery_codes: Set(None),
+        totp_last_step: Set(None),
         updated_at: Set(chrono::Utc::now().naive_utc()),
     };
-    user.insert(db)
+    let user = user.insert(db)
         .await
         .expect("Failed to insert new user");
-    let token = generate_jwt_token(&app.config.jwt_key, user_id.0);
 
-    ok(AuthResponse {
-        user: UserDto {
-            id: user_id,
-            name: payload.name
-        },
-        token,
-    })
+    send_verification_email(&app, &user).await;
+    ok(create_auth_response(&app, &user, client).await)
 }
\ No newline at end of file
@@ -0,0 +1,105 @@
+use crate::app::NebulaApp;
+use crate::mail::messages;
+use crate::schema::user_tokens::Purpose;
+use crate::schema::users;
+use crate::service;
+use crate::web::routing::error::{error, no_content, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Extension;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, garde::Validate)]
+pub struct VerifyEmailRequest {
+    #[garde(length(min = 1, max = 256))]
+    pub token: String,
+}
+
+pub async fn send_verification_email(app: &NebulaApp, user: &users::Model) {
+    let token = service::user_token::issue_token(&app.db, user.id, Purpose::EmailVerification, None)
+        .await
+        .expect("Failed to issue email verification token");
+    let message = messages::email_verification(&app.config, &user.email, &token);
+    if let Err(err) = app.mailer.send(message).await {
+        tracing::error!("Failed to send verification email: {}", err.0);
+    }
+}
+
+pub async fn request_verification_handler(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    if user.email_verified {
+        return error(StatusCode::CONFLICT, "Your email is already verified");
+    }
+    send_verification_email(&app, &user).await;
+    no_content()
+}
+
+pub async fn verify_email_handler(
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<VerifyEmailRequest>
+) -> NebulaResponse<()> {
+    let token = service::user_token::consume_token(&app.db, &payload.token, Purpose::EmailVerification)
+        .await
+        .expect("Failed to consume email verification token");
+    let Some(token) = token else {
+        return error(StatusCode::BAD_REQUEST, "Invalid or expired token");
+    };
+
+    users::Entity::update_many()
+        .col_expr(users::Column::EmailVerified, Expr::value(true))
+        .filter(users::Column::Id.eq(token.user_id))
+        .exec(&app.db)
+        .await
+        .expect("Failed to verify email");
+    no_content()
+}
+
+pub async fn confirm_email_change_handler(
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<VerifyEmailRequest>
+) -> NebulaResponse<()> {
+    let token = service::user_token::consume_token(&app.db, &payload.token, Purpose::EmailChange)
+        .await
+        .expect("Failed to consume email change token");
+    let Some(token) = token else {
+        return error(StatusCode::BAD_REQUEST, "Invalid or expired token");
+    };
+    let Some(new_email) = token.payload else {
+        return error(StatusCode::BAD_REQUEST, "Invalid or expired token");
+    };
+
+    let user_with_same_email = users::Entity::find()
+        .filter(users::Column::Email.eq(new_email.clone()))
+        .one(&app.db)
+        .await
+        .expect("Failed to query the database");
+    if user_with_same_email.is_some() {
+        return error(StatusCode::CONFLICT, "A user with the same email already exists");
+    }
+    let user = users::Entity::find_by_id(token.user_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query user");
+    let Some(user) = user else {
+        return error(StatusCode::BAD_REQUEST, "Invalid or expired token");
+    };
+
+    users::Entity::update_many()
+        .col_expr(users::Column::Email, Expr::value(new_email.clone()))
+        .col_expr(users::Column::EmailVerified, Expr::value(true))
+        .col_expr(users::Column::UpdatedAt, Expr::value(chrono::Utc::now().naive_utc()))
+        .filter(users::Column::Id.eq(user.id))
+        .exec(&app.db)
+        .await
+        .expect("Failed to change email");
+
+    let notice = messages::email_changed_notice(&user.email, &new_email);
+    if let Err(err) = app.mailer.send(notice).await {
+        tracing::error!("Failed to send email change notice: {}", err.0);
+    }
+    no_content()
+}
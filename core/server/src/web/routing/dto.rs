@@ -1,5 +1,7 @@
+use crate::data::permissions::BitwisePermissions;
 use crate::data::snowflake::Snowflake;
 use crate::schema::{realm_tasks, users};
+use crate::schema::realms::RealmVisibility;
 use rrule::{RRule, Unvalidated};
 use serde::{Deserialize, Serialize};
 
@@ -23,7 +25,12 @@ pub struct RealmDto {
     pub id: Snowflake,
     pub name: String,
     pub description: Option<String>,
-    pub owner_id: Snowflake
+    pub owner_id: Snowflake,
+    pub pending_owner_id: Option<Snowflake>,
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub parent_id: Option<Snowflake>,
+    pub visibility: RealmVisibility,
+    pub timezone: String
 }
 
 impl RealmDto {
@@ -32,7 +39,94 @@ impl RealmDto {
             id: model.id,
             name: model.name.clone(),
             description: model.description.clone(),
-            owner_id: model.owner_id
+            owner_id: model.owner_id,
+            pending_owner_id: model.pending_owner_id,
+            archived_at: model.archived_at,
+            parent_id: model.parent_id,
+            visibility: model.visibility,
+            timezone: model.timezone.clone()
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RealmMemberDto {
+    pub user: UserDto,
+    pub roles: Vec<Snowflake>,
+    pub permissions: i16,
+    pub denied_permissions: i16,
+    pub effective_permissions: i16
+}
+
+impl RealmMemberDto {
+    pub fn from_model(
+        membership: &crate::schema::realm_members::Model,
+        user: &users::Model,
+        roles: &[crate::schema::realm_roles::Model],
+        effective_permissions: crate::data::permissions::RealmPermissions
+    ) -> Self {
+        RealmMemberDto {
+            user: UserDto::from_model(user),
+            roles: roles.iter().map(|role| role.id).collect(),
+            permissions: membership.permissions,
+            denied_permissions: membership.denied_permissions,
+            effective_permissions: effective_permissions.0
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RealmRoleDto {
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub name: String,
+    pub permissions: i16,
+    pub colour: Option<i32>,
+    pub position: i32
+}
+
+impl RealmRoleDto {
+    pub fn from_model(model: &crate::schema::realm_roles::Model) -> Self {
+        RealmRoleDto {
+            id: model.id,
+            realm_id: model.realm_id,
+            name: model.name.clone(),
+            permissions: model.permissions,
+            colour: model.colour,
+            position: model.position
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RealmInviteDto {
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub code: String,
+    pub created_by: Snowflake,
+    pub invitee_id: Option<Snowflake>,
+    pub invitee_email: Option<String>,
+    pub permissions: i16,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>
+}
+
+impl RealmInviteDto {
+    pub fn from_model(model: &crate::schema::realm_invites::Model) -> Self {
+        RealmInviteDto {
+            id: model.id,
+            realm_id: model.realm_id,
+            code: model.code.clone(),
+            created_by: model.created_by,
+            invitee_id: model.invitee_id,
+            invitee_email: model.invitee_email.clone(),
+            permissions: model.permissions,
+            max_uses: model.max_uses,
+            uses: model.uses,
+            created_at: model.created_at,
+            expires_at: model.expires_at
         }
     }
 }
@@ -47,7 +141,12 @@ pub struct RealmEventDto {
     pub realm_id: Snowflake,
     pub start_time: chrono::DateTime<chrono::Utc>,
     pub end_time: Option<chrono::DateTime<chrono::Utc>>,
-    pub recurrence: Option<RRule<Unvalidated>>
+    pub recurrence: Option<RRule<Unvalidated>>,
+    /// Start times left out of the series.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exdates: Vec<chrono::DateTime<chrono::Utc>>,
+    /// IANA time zone the series recurs in.
+    pub timezone: String
 }
 
 impl RealmEventDto {
@@ -61,23 +160,82 @@ impl RealmEventDto {
             realm_id: model.realm_id,
             start_time: model.start_time,
             end_time: model.end_time,
-            recurrence: model.recurrence.as_ref().map(|r| r.parse().unwrap())
+            recurrence: model.recurrence.as_ref().map(|r| r.parse().unwrap()),
+            exdates: model.exdates.0.clone(),
+            timezone: model.timezone.clone()
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RealmEventOccurrenceDto {
-    pub event_index: i8,
+    pub event_index: usize,
     pub occurrence_start: chrono::DateTime<chrono::Utc>,
-    pub occurrence_end: Option<chrono::DateTime<chrono::Utc>>
+    pub occurrence_end: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub status: OccurrenceStatus,
+    /// Where the occurrence falls in the series, set when an override moved it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_start: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub override_id: Option<Snowflake>,
+    /// Fields an override changed for this occurrence only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OccurrenceStatus {
+    #[default]
+    Scheduled,
+    Overridden,
+    /// Left out of the series with an `EXDATE`, shown so clients can strike it through.
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RealmEventOverrideDto {
+    pub id: Snowflake,
+    pub event_id: Snowflake,
+    pub original_start: chrono::DateTime<chrono::Utc>,
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+}
+
+impl RealmEventOverrideDto {
+    pub fn from_model(model: &crate::schema::realm_event_overrides::Model) -> Self {
+        RealmEventOverrideDto {
+            id: model.id,
+            event_id: model.event_id,
+            original_start: model.original_start,
+            start_time: model.start_time,
+            end_time: model.end_time,
+            name: model.name.clone(),
+            description: model.description.clone(),
+            location: model.location.clone(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RealmScheduleDto {
+    /// The realm's time zone. Each event carries the one it recurs in.
+    pub timezone: String,
     pub events: Vec<RealmEventDto>,
     pub tasks: Vec<TaskDto>,
-    pub occurrences: Vec<RealmEventOccurrenceDto>
+    pub occurrences: Vec<RealmEventOccurrenceDto>,
+    /// Set when a series had more occurrences in the window than one schedule
+    /// lists. Narrow the window to see the rest.
+    #[serde(default)]
+    pub truncated: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -121,5 +279,206 @@ impl TaskDto {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SelfStatusDto {
     pub realms: Vec<RealmDto>,
-    pub me: UserDto
-}
\ No newline at end of file
+    pub me: UserDto,
+    pub email_verified: bool,
+    pub totp_enabled: bool
+}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionDto {
+    pub id: Snowflake,
+    pub device: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    pub current: bool
+}
+
+impl SessionDto {
+    pub fn from_model(model: &crate::schema::sessions::Model, current_session_id: Snowflake) -> Self {
+        SessionDto {
+            id: model.id,
+            device: model.device.clone(),
+            user_agent: model.user_agent.clone(),
+            ip_address: model.ip_address.clone(),
+            created_at: model.created_at,
+            last_seen_at: model.last_seen_at,
+            current: model.id == current_session_id
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IdentityDto {
+    pub id: Snowflake,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>
+}
+
+impl IdentityDto {
+    pub fn from_model(model: &crate::schema::user_identities::Model) -> Self {
+        IdentityDto {
+            id: model.id,
+            provider: model.provider.clone(),
+            email: model.email.clone(),
+            created_at: model.created_at
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccessTokenDto {
+    pub id: Snowflake,
+    pub name: String,
+    pub scopes: Vec<crate::data::scopes::TokenScope>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>
+}
+
+impl AccessTokenDto {
+    pub fn from_model(model: &crate::schema::personal_access_tokens::Model) -> Self {
+        AccessTokenDto {
+            id: model.id,
+            name: model.name.clone(),
+            scopes: crate::data::scopes::TokenScopes::new(model.scopes).to_vec(),
+            created_at: model.created_at,
+            expires_at: model.expires_at,
+            last_used_at: model.last_used_at
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DataExportDto {
+    pub id: Snowflake,
+    pub status: crate::schema::data_exports::ExportStatus,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl DataExportDto {
+    pub fn from_model(model: &crate::schema::data_exports::Model) -> Self {
+        DataExportDto {
+            id: model.id,
+            status: model.status,
+            created_at: model.created_at,
+            completed_at: model.completed_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditLogEntryDto {
+    pub id: Snowflake,
+    pub actor_id: Option<Snowflake>,
+    pub action: crate::schema::realm_audit_log::AuditAction,
+    pub target_type: crate::schema::realm_audit_log::AuditTarget,
+    pub target_id: Option<Snowflake>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl AuditLogEntryDto {
+    pub fn from_model(model: &crate::schema::realm_audit_log::Model) -> Self {
+        AuditLogEntryDto {
+            id: model.id,
+            actor_id: model.actor_id,
+            action: model.action,
+            target_type: model.target_type,
+            target_id: model.target_id,
+            before: model.before.clone(),
+            after: model.after.clone(),
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RealmShareLinkDto {
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub token: String,
+    pub created_by: Snowflake,
+    pub include_tasks: bool,
+    pub include_descriptions: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl RealmShareLinkDto {
+    pub fn from_model(model: &crate::schema::realm_share_links::Model) -> Self {
+        RealmShareLinkDto {
+            id: model.id,
+            realm_id: model.realm_id,
+            token: model.token.clone(),
+            created_by: model.created_by,
+            include_tasks: model.include_tasks,
+            include_descriptions: model.include_descriptions,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RealmCalendarFeedDto {
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>
+}
+
+impl RealmCalendarFeedDto {
+    pub fn from_model(model: &crate::schema::realm_calendar_feeds::Model) -> Self {
+        RealmCalendarFeedDto {
+            id: model.id,
+            realm_id: model.realm_id,
+            created_at: model.created_at,
+            last_used_at: model.last_used_at
+        }
+    }
+}
+
+/// A realm as it appears in the directory, to people who aren't in it yet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiscoverableRealmDto {
+    pub id: Snowflake,
+    pub name: String,
+    pub description: Option<String>,
+    pub visibility: RealmVisibility,
+    pub member_count: u64,
+}
+
+impl DiscoverableRealmDto {
+    pub fn from_model(model: &crate::schema::realms::Model, member_count: u64) -> Self {
+        DiscoverableRealmDto {
+            id: model.id,
+            name: model.name.clone(),
+            description: model.description.clone(),
+            visibility: model.visibility,
+            member_count,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RealmJoinRequestDto {
+    pub id: Snowflake,
+    pub realm_id: Snowflake,
+    pub user: UserDto,
+    pub message: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl RealmJoinRequestDto {
+    pub fn from_model(model: &crate::schema::realm_join_requests::Model, user: &users::Model) -> Self {
+        RealmJoinRequestDto {
+            id: model.id,
+            realm_id: model.realm_id,
+            user: UserDto::from_model(user),
+            message: model.message.clone(),
+            created_at: model.created_at,
+        }
+    }
+}
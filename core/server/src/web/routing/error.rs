@@ -1,4 +1,5 @@
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use axum_extra::either::Either;
 use serde::Serialize;
@@ -33,4 +34,18 @@ impl From<StatusCode> for NebulaError {
             message: status.canonical_reason().unwrap_or("Unknown error").to_string()
         }
     }
-}
\ No newline at end of file
+}
+
+/// 429 response telling the client how many seconds to wait before retrying.
+pub struct RateLimited {
+    pub retry_after: std::time::Duration,
+}
+
+impl IntoResponse for RateLimited {
+    fn into_response(self) -> Response {
+        // Round up so clients never retry a moment too early.
+        let seconds = self.retry_after.as_secs() + u64::from(self.retry_after.subsec_nanos() > 0);
+        let (status, body) = error::<()>(StatusCode::TOO_MANY_REQUESTS, "Too many requests, slow down");
+        (status, [(header::RETRY_AFTER, seconds.max(1).to_string())], body).into_response()
+    }
+}
@@ -0,0 +1,130 @@
+use crate::app::NebulaApp;
+use crate::cableway::events::members::send_member_joined;
+use crate::schema::{realms, users};
+use crate::service;
+use crate::service::invite::InviteError;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
+use crate::web::routing::dto::{RealmDto, RealmInviteDto, RealmMemberDto};
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::realms::RealmObject;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+
+/// An invite as seen by the person it's for, with the realm it leads to.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InvitationObject {
+    pub invite: RealmInviteDto,
+    pub realm: RealmDto,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InvitationsObject {
+    pub invites: Vec<InvitationObject>,
+}
+
+pub async fn list_my_invites(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<InvitationsObject> {
+    let invites = service::invite::list_user_invites(&app.db, &user)
+        .await
+        .expect("Failed to query invites");
+
+    let mut invitations = Vec::with_capacity(invites.len());
+    for invite in invites {
+        let realm = realms::Entity::find_by_id(invite.realm_id)
+            .one(&app.db)
+            .await
+            .expect("Failed to query realm");
+        if let Some(realm) = realm {
+            invitations.push(InvitationObject {
+                invite: RealmInviteDto::from_model(&invite),
+                realm: RealmDto::from_model(&realm),
+            });
+        }
+    }
+    ok(InvitationsObject { invites: invitations })
+}
+
+pub async fn get_invite(
+    Path(code): Path<String>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<InvitationObject> {
+    let invite = service::invite::find_invite_for(&app.db, &code, &user)
+        .await
+        .expect("Failed to query invite");
+    let Some(invite) = invite.filter(|invite| invite.is_usable()) else {
+        return error(StatusCode::NOT_FOUND, "Invite not found");
+    };
+
+    let realm = realms::Entity::find_by_id(invite.realm_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query realm")
+        .expect("Invite points to a missing realm");
+    ok(InvitationObject {
+        invite: RealmInviteDto::from_model(&invite),
+        realm: RealmDto::from_model(&realm),
+    })
+}
+
+pub async fn accept_invite(
+    Path(code): Path<String>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmObject> {
+    let (invite, membership) = match service::invite::accept_invite(&app.db, &code, &user).await {
+        Ok(accepted) => accepted,
+        Err(err) => return invite_error(err),
+    };
+
+    let realm = realms::Entity::find_by_id(invite.realm_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query realm")
+        .expect("Invite points to a missing realm");
+
+    let permissions = service::role::combine_permissions(&realm, &membership, &[]);
+    let member = RealmMemberDto::from_model(&membership, &user, &[], permissions);
+
+    let entry = AuditEntry::new(AuditAction::Joined, AuditTarget::Member, user.id)
+        .after(&serde_json::json!({ "invite_id": invite.id, "permissions": member.permissions }));
+    service::audit::record(&app.db, realm.id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    send_member_joined(
+        &app.cableway,
+        realm.id,
+        member
+    )
+        .await
+        .expect("Failed to send member joined message");
+
+    ok(RealmObject { realm: RealmDto::from_model(&realm) })
+}
+
+pub async fn decline_invite(
+    Path(code): Path<String>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    match service::invite::decline_invite(&app.db, &code, &user).await {
+        Ok(()) => no_content(),
+        Err(err) => invite_error(err),
+    }
+}
+
+fn invite_error<T: Serialize>(err: InviteError) -> NebulaResponse<T> {
+    match err {
+        InviteError::NotFound => error(StatusCode::NOT_FOUND, "Invite not found"),
+        InviteError::Unavailable => error(StatusCode::GONE, "This invite has expired or was used up"),
+        InviteError::AlreadyMember => error(StatusCode::CONFLICT, "You are already a member of this realm"),
+        InviteError::Database(err) => panic!("Failed to process invite: {:?}", err),
+    }
+}
@@ -1,16 +1,33 @@
 use crate::app::NebulaApp;
+use crate::data::scopes::{TokenScope, TokenScopes};
 use crate::service;
 use crate::web::routing::error::error;
-use axum::extract::{Request, State};
+use axum::extract::{MatchedPath, Query, Request, State};
 use axum::http::StatusCode;
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 
+/// Personal access tokens only reach realm routes, where `realm_membership!`
+/// checks their scopes. Account management always needs a real session.
+const ACCESS_TOKEN_ROUTE_PREFIX: &str = "/api/realms/{realm_id}";
+/// Calendar apps can't send headers, so feed tokens come in the query string
+/// and only open this route.
+const CALENDAR_FEED_ROUTE: &str = "/api/realms/{realm_id}/calendar.ics";
+
+#[derive(serde::Deserialize)]
+struct FeedQuery {
+    token: String,
+}
+
 pub async fn authorize(
     State(app): State<NebulaApp>,
     mut req: Request,
     next: Next
 ) -> Response {
+    if let Some(token) = feed_token(&req) {
+        return authorize_feed(&app, &token, req, next).await;
+    }
+
     let headers = req.headers().clone();
     let auth = headers.get("Authorization");
     if auth.is_none() {
@@ -28,16 +45,75 @@ pub async fn authorize(
         return error::<String>(StatusCode::UNAUTHORIZED, "Invalid token").into_response();
     }
 
+    let token = token.unwrap();
+    if service::access_token::is_personal_access_token(token) {
+        let allowed_route = req.extensions()
+            .get::<MatchedPath>()
+            .is_some_and(|path| path.as_str().starts_with(ACCESS_TOKEN_ROUTE_PREFIX));
+        if !allowed_route {
+            return error::<String>(StatusCode::FORBIDDEN, "Personal access tokens can't be used for this endpoint").into_response();
+        }
+
+        let authentication = service::access_token::authenticate(&app.db, token)
+            .await
+            .expect("Failed to query personal access token");
+        return match authentication {
+            Some(authentication) => {
+                req.extensions_mut().insert(authentication.user);
+                req.extensions_mut().insert(authentication.scopes);
+                next.run(req).await
+            },
+            None => error::<String>(StatusCode::UNAUTHORIZED, "Invalid token").into_response()
+        };
+    }
+
     let user_result = service::auth::authenticate(
         &app.config,
         &app.db,
-        token.unwrap().to_string()
+        token.to_string()
     ).await;
     match user_result {
-        Ok(user) => {
-            req.extensions_mut().insert(user);
+        Ok(authentication) => {
+            req.extensions_mut().insert(authentication.user);
+            req.extensions_mut().insert(authentication.session);
             next.run(req).await
         },
         Err(_) => error::<String>(StatusCode::UNAUTHORIZED, "Invalid token").into_response()
     }
-}
\ No newline at end of file
+}
+
+fn feed_token(req: &Request) -> Option<String> {
+    let is_feed_route = req.extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| path.as_str() == CALENDAR_FEED_ROUTE);
+    if !is_feed_route {
+        return None;
+    }
+    Query::<FeedQuery>::try_from_uri(req.uri()).ok().map(|Query(query)| query.token)
+}
+
+/// Feed tokens act for their owner with read-only scopes, so the membership
+/// check still decides whether they can see the realm.
+async fn authorize_feed(app: &NebulaApp, token: &str, mut req: Request, next: Next) -> Response {
+    let authentication = service::feed::authenticate(&app.db, token)
+        .await
+        .expect("Failed to query calendar feed");
+    match authentication {
+        Some((feed, user)) => {
+            req.extensions_mut().insert(user);
+            req.extensions_mut().insert(TokenScopes::from_slice(&[TokenScope::EventsRead, TokenScope::TasksRead]));
+            req.extensions_mut().insert(feed);
+            next.run(req).await
+        },
+        None => error::<String>(StatusCode::UNAUTHORIZED, "Invalid token").into_response()
+    }
+}
+
+/// For realm routes that act on the account rather than inside a realm, like
+/// joining one, which personal access tokens can't do.
+pub async fn require_session(req: Request, next: Next) -> Response {
+    if req.extensions().get::<TokenScopes>().is_some() {
+        return error::<String>(StatusCode::FORBIDDEN, "Personal access tokens can't be used for this endpoint").into_response();
+    }
+    next.run(req).await
+}
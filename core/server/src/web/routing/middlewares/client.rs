@@ -0,0 +1,34 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use crate::service::session::ClientInfo;
+
+const DEVICE_HEADER: &str = "X-Device-Name";
+const MAX_HEADER_LENGTH: usize = 256;
+
+/// Collects the device name, user agent and peer address used to label sessions.
+pub struct RequestClient(pub ClientInfo);
+
+impl <S: Send + Sync> FromRequestParts<S> for RequestClient {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ip_address = parts.extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+        Ok(RequestClient(ClientInfo {
+            device: header_value(&parts.headers, DEVICE_HEADER),
+            user_agent: header_value(&parts.headers, axum::http::header::USER_AGENT.as_str()),
+            ip_address,
+        }))
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(MAX_HEADER_LENGTH).collect())
+}
@@ -1,21 +1,58 @@
 use axum::body::Body;
-use axum::extract::{Path, Request, State};
+use axum::extract::{MatchedPath, Path, Request, State};
+use axum::http::Method;
 use axum::middleware::Next;
 use axum::response::Response;
 use sea_orm::EntityTrait;
 use crate::app::NebulaApp;
 use crate::data::snowflake::Snowflake;
 use crate::data::permissions::{BitwisePermissions, RealmPermissions};
+use crate::data::scopes::TokenScopes;
 use crate::schema::realms;
 
+/// Routes that still work in an archived realm: archiving it again, which
+/// reports the conflict, restoring it, deleting it, leaving it and managing
+/// your own calendar feed.
+const ARCHIVED_REALM_ROUTES: [(Method, &str); 6] = [
+    (Method::POST, "/api/realms/{realm_id}/archive"),
+    (Method::POST, "/api/realms/{realm_id}/restore"),
+    (Method::DELETE, "/api/realms/{realm_id}"),
+    (Method::DELETE, "/api/realms/{realm_id}/members/@me"),
+    (Method::POST, "/api/realms/{realm_id}/calendar/feed"),
+    (Method::DELETE, "/api/realms/{realm_id}/calendar/feed"),
+];
+
+fn is_read_only(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Only the realm id is needed here, even on routes with more path parameters.
+#[derive(serde::Deserialize)]
+pub struct RealmPath {
+    pub realm_id: Snowflake,
+}
+
 pub async fn authorize_membership_with_permissions(
-    Path(realm_id): Path<Snowflake>,
+    Path(RealmPath { realm_id }): Path<RealmPath>,
     State(app): State<NebulaApp>,
     mut req: Request,
     next: Next,
     required_perms: Option<RealmPermissions>,
+    required_scopes: Option<TokenScopes>,
 ) -> Response {
     let db = &app.db;
+    // Requests made with a personal access token carry its scopes, and routes
+    // that don't declare any scopes can't be reached with one.
+    if let Some(token_scopes) = req.extensions().get::<TokenScopes>() {
+        let allowed = required_scopes.is_some_and(|required| token_scopes.contains_all(&required));
+        if !allowed {
+            return axum::response::Response::builder()
+                .status(axum::http::StatusCode::FORBIDDEN)
+                .body(Body::from("Token is missing the required scopes"))
+                .expect("Insufficient scopes")
+        }
+    }
+
     let user = req.extensions().get::<crate::schema::users::Model>();
     if user.is_none() {
         panic!("User not found in request extensions");
@@ -34,21 +71,20 @@ pub async fn authorize_membership_with_permissions(
     }
 
     let realm = realm.unwrap();
-    let membership = crate::schema::realm_members::Entity::find_membership(
-        db,
-        realm.id,
-        user.id
-    ).await.expect("Failed to query realm membership");
+    // Members of a parent realm reach its children unless they have their own membership there.
+    let access = crate::service::realm::resolve_access(db, &realm, user.id)
+        .await
+        .expect("Failed to query realm membership");
 
-    if membership.is_none() {
+    if access.is_none() {
         return axum::response::Response::builder()
             .status(axum::http::StatusCode::FORBIDDEN)
             .body(Body::empty())
             .expect("Membership was not found")
     }
 
-    let membership = membership.unwrap();
-    let user_permissions = RealmPermissions::new(membership.permissions);
+    let access = access.unwrap();
+    let user_permissions = access.permissions;
 
     if let Some(required) = required_perms && !user_permissions.contains_all(&required) {
         return axum::response::Response::builder()
@@ -57,8 +93,22 @@ pub async fn authorize_membership_with_permissions(
             .expect("Insufficient permissions")
     }
 
-    req.extensions_mut().insert(membership);
+    if realm.archived_at.is_some() && !is_read_only(req.method()) {
+        let path = req.extensions().get::<MatchedPath>().map(MatchedPath::as_str);
+        let allowed = ARCHIVED_REALM_ROUTES
+            .iter()
+            .any(|(method, route)| method == req.method() && Some(*route) == path);
+        if !allowed {
+            return axum::response::Response::builder()
+                .status(axum::http::StatusCode::LOCKED)
+                .body(Body::from("Realm is archived"))
+                .expect("Realm is archived")
+        }
+    }
+
+    req.extensions_mut().insert(access);
     req.extensions_mut().insert(realm);
+    req.extensions_mut().insert(user_permissions);
 
     next.run(req).await
 }
@@ -66,26 +116,26 @@ pub async fn authorize_membership_with_permissions(
 #[macro_export]
 macro_rules! realm_membership {
     ($state:expr) => {
-        axum::middleware::from_fn_with_state($state.clone(), |path, state, req, next| async move {
-            crate::web::routing::middlewares::membership::authorize_membership_with_permissions(
-                path,
-                state,
-                req,
-                next,
-                None
-            ).await
-        })
+        $crate::realm_membership!($state, [], [])
     };
     ($state:expr, [$($perm:ident),*]) => {
+        $crate::realm_membership!($state, [$($perm),*], [])
+    };
+    ($state:expr, [$($perm:ident),*], [$($scope:ident),*]) => {
         axum::middleware::from_fn_with_state($state.clone(), |path, state, req, next| async move {
-            crate::web::routing::middlewares::membership::authorize_membership_with_permissions(
+            let required_perms = $crate::data::permissions::RealmPermissions::from_slice(&[
+                $($crate::data::permissions::RealmPermission::$perm,)*
+            ]);
+            let required_scopes = $crate::data::scopes::TokenScopes::from_slice(&[
+                $($crate::data::scopes::TokenScope::$scope,)*
+            ]);
+            $crate::web::routing::middlewares::membership::authorize_membership_with_permissions(
                 path,
                 state,
                 req,
                 next,
-                Some(crate::data::permissions::RealmPermissions::from_slice(&[
-                    $(crate::data::permissions::RealmPermission::$perm,)*
-                ]))
+                Some(required_perms).filter(|perms| perms.0 != 0),
+                Some(required_scopes).filter(|scopes| scopes.0 != 0)
             ).await
         })
     };
@@ -1,3 +1,5 @@
 pub mod auth;
+pub mod client;
 pub mod membership;
+pub mod rate_limit;
 pub mod validation;
\ No newline at end of file
@@ -0,0 +1,76 @@
+use std::net::SocketAddr;
+use crate::app::NebulaApp;
+use crate::schema::users;
+use crate::service::rate_limit::{RateLimitKey, RouteGroup};
+use crate::web::routing::error::RateLimited;
+use axum::extract::{ConnectInfo, MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+/// Routes that take credentials or send mail get the stricter `auth` budget.
+const AUTH_ROUTE_PREFIXES: [&str; 6] = [
+    "/api/login",
+    "/api/signup",
+    "/api/oauth/",
+    "/api/token/",
+    "/api/password/",
+    "/api/email/",
+];
+
+/// Limits requests per client IP, using the budget of the matched route's group.
+pub async fn limit_by_ip(
+    State(app): State<NebulaApp>,
+    req: Request,
+    next: Next
+) -> Response {
+    let ip = req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let Some(ip) = ip else {
+        return next.run(req).await;
+    };
+    if ip.is_loopback() && app.config.rate_limits.exempt_loopback {
+        return next.run(req).await;
+    }
+
+    let is_auth_route = req.extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| AUTH_ROUTE_PREFIXES.iter().any(|prefix| path.as_str().starts_with(prefix)));
+    let group = if is_auth_route { RouteGroup::Auth } else { RouteGroup::Api };
+
+    check(&app, group, RateLimitKey::Ip(ip), req, next).await
+}
+
+/// Limits authenticated requests per user, however many addresses they come from.
+pub async fn limit_by_user(
+    State(app): State<NebulaApp>,
+    req: Request,
+    next: Next
+) -> Response {
+    let Some(user_id) = req.extensions().get::<users::Model>().map(|user| user.id) else {
+        return next.run(req).await;
+    };
+
+    check(&app, RouteGroup::User, RateLimitKey::User(user_id), req, next).await
+}
+
+async fn check(
+    app: &NebulaApp,
+    group: RouteGroup,
+    key: RateLimitKey,
+    req: Request,
+    next: Next
+) -> Response {
+    let config = &app.config.rate_limits;
+    if !config.enabled {
+        return next.run(req).await;
+    }
+
+    let result = app.state.write().await
+        .rate_limiter
+        .check(group, key, group.limit(config));
+    match result {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => RateLimited { retry_after }.into_response()
+    }
+}
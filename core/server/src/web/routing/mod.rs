@@ -1,5 +1,5 @@
 use crate::app::NebulaApp;
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::{middleware, Router};
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
@@ -13,35 +13,221 @@ pub mod error;
 pub mod users;
 pub mod dto;
 pub mod realms;
+pub mod invites;
+pub mod shared;
 
 pub fn router(app: NebulaApp) -> Router {
     Router::new()
         .route("/api/users/{user}", get(users::get_user))
+        .route("/api/users/@me",
+               patch(users::account::update_profile)
+                   .delete(users::account::delete_account)
+        )
+        .route("/api/users/@me/password", put(users::account::change_password))
+        .route("/api/users/@me/email", post(users::account::request_email_change))
         .route("/api/users/@me/status", get(get_self_status))
+        .route("/api/users/@me/verification", post(auth::verification::request_verification_handler))
+        .route("/api/users/@me/totp",
+               post(users::totp::enroll_totp)
+                   .delete(users::totp::disable_totp)
+        )
+        .route("/api/users/@me/totp/confirm", post(users::totp::confirm_totp))
+        .route("/api/users/@me/identities", get(users::identities::list_identities))
+        .route("/api/users/@me/identities/{identity_id}", delete(users::identities::unlink_identity))
+        .route("/api/users/@me/identities/link/{provider}", post(users::identities::link_identity))
+        .route("/api/users/@me/tokens",
+               get(users::tokens::list_tokens)
+                   .post(users::tokens::create_token)
+        )
+        .route("/api/users/@me/tokens/{token_id}", delete(users::tokens::delete_token))
+        .route("/api/users/@me/export", get(users::export::export_data))
+        .route("/api/users/@me/exports/{export_id}", get(users::export::get_export))
+        .route("/api/users/@me/exports/{export_id}/download", get(users::export::download_export))
+        .route("/api/users/@me/invites", get(invites::list_my_invites))
+        .route("/api/users/@me/sessions",
+               get(users::sessions::list_sessions)
+                   .delete(users::sessions::revoke_other_sessions)
+        )
+        .route("/api/users/@me/sessions/@current", delete(users::sessions::logout_current_session))
+        .route("/api/users/@me/sessions/{session_id}", delete(users::sessions::revoke_session))
         .route("/api/realms/{realm_id}",
                get(realms::get_realm)
+                   .layer(realm_membership!(app, [], [RealmsRead]))
+        )
+        .route("/api/realms/{realm_id}",
+               patch(realms::settings::update_realm)
+                   .layer(realm_membership!(app, [ManageRealm]))
+        )
+        .route("/api/realms/{realm_id}",
+               delete(realms::settings::delete_realm)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/archive",
+               post(realms::archive::archive_realm)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/restore",
+               post(realms::archive::restore_realm)
                    .layer(realm_membership!(app))
         )
+        .route("/api/realms/{realm_id}/transfer",
+               post(realms::transfer::propose_transfer)
+                   .delete(realms::transfer::cancel_transfer)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/transfer/accept",
+               post(realms::transfer::accept_transfer)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/transfer/decline",
+               post(realms::transfer::decline_transfer)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/permissions/@me",
+               get(realms::permissions::get_my_permissions)
+                   .layer(realm_membership!(app, [], [RealmsRead]))
+        )
         .route("/api/realms/{realm_id}/calendar/events",
                post(realms::calendar::events::create_event)
-                   .layer(realm_membership!(app, [ManageEvents]))
+                   .layer(realm_membership!(app, [], [EventsWrite]))
         )
         .route("/api/realms/{realm_id}/calendar/events/{event_id}",
-               delete(realms::calendar::events::delete_event)
-                   .layer(realm_membership!(app, [ManageEvents]))
+               patch(realms::calendar::events::update_event)
+                   .delete(realms::calendar::events::delete_event)
+                   .layer(realm_membership!(app, [], [EventsWrite]))
+        )
+        .route("/api/realms/{realm_id}/calendar/events/{event_id}/cancellations",
+               post(realms::calendar::events::cancel_occurrence)
+                   .delete(realms::calendar::events::restore_occurrence)
+                   .layer(realm_membership!(app, [], [EventsWrite]))
+        )
+        .route("/api/realms/{realm_id}/calendar/events/{event_id}/overrides/{override_id}",
+               delete(realms::calendar::events::delete_override)
+                   .layer(realm_membership!(app, [], [EventsWrite]))
         )
         .route("/api/realms/{realm_id}/calendar/schedule",
                get(realms::calendar::occurrences::get_occurrences)
-                   .layer(realm_membership!(app))
+                   .layer(realm_membership!(app, [ViewCalendar], [EventsRead, TasksRead]))
+        )
+        .route("/api/realms/{realm_id}/calendar/schedule/combined",
+               get(realms::calendar::occurrences::get_combined_occurrences)
+                   .layer(realm_membership!(app, [ViewCalendar], [EventsRead, TasksRead]))
+        )
+        .route("/api/realms/{realm_id}/calendar.ics",
+               get(realms::calendar::feed::get_calendar_ics)
+                   .layer(realm_membership!(app, [ViewCalendar], [EventsRead, TasksRead]))
+        )
+        .route("/api/realms/{realm_id}/calendar/feed",
+               get(realms::calendar::feed::get_feed)
+                   .post(realms::calendar::feed::create_feed)
+                   .delete(realms::calendar::feed::revoke_feed)
+                   .layer(realm_membership!(app, [ViewCalendar]))
         )
         .route("/api/realms/{realm_id}/tasks",
                post(realms::task::create_task)
-                   .layer(realm_membership!(app, [ManageTasks]))
+                   .layer(realm_membership!(app, [ManageTasks], [TasksWrite]))
+        )
+        .route("/api/realms/{realm_id}/tasks/{task_id}/completion",
+               put(realms::task::set_completion)
+                   .layer(realm_membership!(app, [], [TasksWrite]))
+        )
+        .route("/api/realms/{realm_id}/invites",
+               get(realms::invites::list_invites)
+                   .post(realms::invites::create_invite)
+                   .layer(realm_membership!(app, [InviteMembers]))
+        )
+        .route("/api/realms/{realm_id}/invites/{invite_id}",
+               delete(realms::invites::revoke_invite)
+                   .layer(realm_membership!(app, [InviteMembers]))
+        )
+        .route("/api/realms/{realm_id}/members",
+               get(realms::members::list_members)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/members/@me",
+               delete(realms::members::leave_realm)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/members/{user_id}",
+               patch(realms::members::update_member)
+                   .delete(realms::members::kick_member)
+                   .layer(realm_membership!(app, [ManageMembers]))
+        )
+        .route("/api/realms/{realm_id}/members/{user_id}/roles/{role_id}",
+               put(realms::members::assign_role)
+                   .delete(realms::members::unassign_role)
+                   .layer(realm_membership!(app, [ManageMembers]))
+        )
+        .route("/api/realms/{realm_id}/roles",
+               get(realms::roles::list_roles)
+                   .layer(realm_membership!(app))
+        )
+        .route("/api/realms/{realm_id}/roles",
+               post(realms::roles::create_role)
+                   .layer(realm_membership!(app, [ManageMembers]))
+        )
+        .route("/api/realms/{realm_id}/roles/{role_id}",
+               patch(realms::roles::update_role)
+                   .delete(realms::roles::delete_role)
+                   .layer(realm_membership!(app, [ManageMembers]))
+        )
+        .route("/api/realms/{realm_id}/audit-log",
+               get(realms::audit::get_audit_log)
+                   .layer(realm_membership!(app, [ViewAuditLog]))
+        )
+        .route("/api/realms/{realm_id}/share-links",
+               get(realms::share::list_share_links)
+                   .post(realms::share::create_share_link)
+                   .layer(realm_membership!(app, [ManageRealm]))
+        )
+        .route("/api/realms/{realm_id}/share-links/{link_id}",
+               delete(realms::share::revoke_share_link)
+                   .layer(realm_membership!(app, [ManageRealm]))
+        )
+        .route("/api/realms/{realm_id}/join-requests",
+               get(realms::discovery::list_join_requests)
+                   .layer(realm_membership!(app, [ManageMembers]))
+        )
+        .route("/api/realms/{realm_id}/join-requests/{request_id}/approve",
+               post(realms::discovery::approve_join_request)
+                   .layer(realm_membership!(app, [ManageMembers]))
+        )
+        .route("/api/realms/{realm_id}/join-requests/{request_id}/reject",
+               post(realms::discovery::reject_join_request)
+                   .layer(realm_membership!(app, [ManageMembers]))
+        )
+        .route("/api/realms/{realm_id}/join",
+               post(realms::discovery::join_realm)
+                   .layer(middleware::from_fn(middlewares::auth::require_session))
+        )
+        .route("/api/realms/{realm_id}/join-requests",
+               post(realms::discovery::request_to_join)
+                   .layer(middleware::from_fn(middlewares::auth::require_session))
+        )
+        .route("/api/realms/{realm_id}/join-requests/@me",
+               delete(realms::discovery::withdraw_join_request)
+                   .layer(middleware::from_fn(middlewares::auth::require_session))
         )
+        .route("/api/realms/discover", get(realms::discovery::discover_realms))
         .route("/api/realms", post(realms::create::create_realm))
+        .route("/api/invites/{code}", get(invites::get_invite))
+        .route("/api/invites/{code}/accept", post(invites::accept_invite))
+        .route("/api/invites/{code}/decline", post(invites::decline_invite))
+        .route_layer(middleware::from_fn_with_state(app.clone(), middlewares::rate_limit::limit_by_user))
         .route_layer(middleware::from_fn_with_state(app.clone(), middlewares::auth::authorize))
         .route("/api/login", post(auth::login::login_handler))
+        .route("/api/login/totp", post(auth::login::two_factor_login_handler))
         .route("/api/signup", post(auth::signup::signup_handler))
+        .route("/api/oauth/providers", get(auth::oidc::list_providers))
+        .route("/api/oauth/{provider}/authorize", post(auth::oidc::authorize))
+        .route("/api/oauth/{provider}/callback", post(auth::oidc::callback))
+        .route("/api/token/refresh", post(auth::refresh::refresh_handler))
+        .route("/api/password/forgot", post(auth::password::forgot_password_handler))
+        .route("/api/password/reset", post(auth::password::reset_password_handler))
+        .route("/api/email/verify", post(auth::verification::verify_email_handler))
+        .route("/api/email/change/confirm", post(auth::verification::confirm_email_change_handler))
+        .route("/api/shared/{token}/schedule", get(shared::get_shared_schedule))
+        .route_layer(middleware::from_fn_with_state(app.clone(), middlewares::rate_limit::limit_by_ip))
         .layer(CorsLayer::permissive())
         .layer(
             ServiceBuilder::new()
@@ -0,0 +1,66 @@
+use crate::app::NebulaApp;
+use crate::cableway::events::realm::send_realm_updated;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::schema::{realms, users};
+use crate::service;
+use crate::service::audit::AuditEntry;
+use crate::web::routing::dto::RealmDto;
+use crate::web::routing::error::{error, ok, NebulaResponse};
+use crate::web::routing::realms::RealmObject;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Extension;
+
+/// Makes the realm read-only and hides it from the default status listing.
+pub async fn archive_realm(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmObject> {
+    if user.id != realm.owner_id {
+        return error(StatusCode::FORBIDDEN, "Only the owner can archive a realm");
+    }
+    if realm.archived_at.is_some() {
+        return error(StatusCode::CONFLICT, "Realm is already archived");
+    }
+    set_archived(&app, &user, realm, true).await
+}
+
+pub async fn restore_realm(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmObject> {
+    if user.id != realm.owner_id {
+        return error(StatusCode::FORBIDDEN, "Only the owner can restore a realm");
+    }
+    if realm.archived_at.is_none() {
+        return error(StatusCode::CONFLICT, "Realm isn't archived");
+    }
+    set_archived(&app, &user, realm, false).await
+}
+
+async fn set_archived(
+    app: &NebulaApp,
+    user: &users::Model,
+    realm: realms::Model,
+    archived: bool
+) -> NebulaResponse<RealmObject> {
+    let before = RealmDto::from_model(&realm);
+    let realm = service::realm::set_archived(&app.db, realm, archived)
+        .await
+        .expect("Failed to update realm");
+    let dto = RealmDto::from_model(&realm);
+
+    let action = if archived { AuditAction::Archived } else { AuditAction::Restored };
+    let entry = AuditEntry::new(action, AuditTarget::Realm, realm.id).before(&before).after(&dto);
+    service::audit::record(&app.db, realm.id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    send_realm_updated(&app.cableway, dto.clone())
+        .await
+        .expect("Failed to send realm updated message");
+
+    ok(RealmObject { realm: dto })
+}
@@ -0,0 +1,51 @@
+use crate::app::NebulaApp;
+use crate::data::snowflake::Snowflake;
+use crate::service;
+use crate::web::routing::dto::AuditLogEntryDto;
+use crate::web::routing::error::{ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidQuery;
+use axum::extract::{Path, State};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PAGE_SIZE: u64 = 50;
+
+#[derive(Serialize, Deserialize, Debug, garde::Validate)]
+pub struct AuditLogQuery {
+    /// Cursor from the previous page's `next`.
+    #[garde(skip)]
+    pub before: Option<Snowflake>,
+    #[garde(inner(range(min = 1, max = 100)))]
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuditLogObject {
+    pub entries: Vec<AuditLogEntryDto>,
+    /// Pass as `before` to fetch the next page, `None` on the last one.
+    pub next: Option<Snowflake>,
+}
+
+pub async fn get_audit_log(
+    Path(realm_id): Path<Snowflake>,
+    State(app): State<NebulaApp>,
+    ValidQuery(query): ValidQuery<AuditLogQuery>
+) -> NebulaResponse<AuditLogObject> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let entries = service::audit::list_entries(
+        &app.db,
+        realm_id,
+        query.before,
+        limit,
+        app.config.audit_log_retention
+    )
+        .await
+        .expect("Failed to query audit log");
+
+    let next = (entries.len() as u64 == limit)
+        .then(|| entries.last().map(|entry| entry.id))
+        .flatten();
+    ok(AuditLogObject {
+        entries: entries.iter().map(AuditLogEntryDto::from_model).collect(),
+        next,
+    })
+}
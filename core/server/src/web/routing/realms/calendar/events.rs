@@ -1,19 +1,27 @@
 use crate::app::NebulaApp;
-use crate::cableway::events::calendar::{send_event_created, send_event_deleted};
+use crate::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use crate::cableway::events::calendar::{send_event_created, send_event_deleted, send_event_updated};
 use crate::data::snowflake::Snowflake;
 use crate::schema::realm_events;
-use crate::schema::users;
+use crate::schema::realm_events::Exdates;
+use crate::schema::{realms, users};
+use crate::service;
 use crate::service::snowflake::next_snowflake;
-use crate::util::validation::is_sane;
-use crate::web::routing::dto::RealmEventDto;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
+use crate::service::event::{EventChanges, EventEditError};
+use crate::util::patch::nullable;
+use crate::util::validation::{is_sane, is_timezone};
+use crate::web::routing::dto::{RealmEventDto, RealmEventOverrideDto};
 use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
 use crate::web::routing::middlewares::validation::ValidJson;
-use crate::web::routing::realms::calendar::RealmEventObject;
+use crate::web::routing::realms::calendar::{RealmEventObject, RealmEventsObject};
 use axum::extract::{Path, State};
 use axum::Extension;
 use chrono::{DateTime, Utc};
 use rrule::{RRule, Unvalidated};
-use sea_orm::{EntityTrait, Set};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde::Serialize;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, garde::Validate)]
 pub struct CreateEventRequest {
@@ -28,20 +36,74 @@ pub struct CreateEventRequest {
     #[garde(skip)]
     pub end_time: Option<DateTime<Utc>>,
     #[garde(skip)]
-    pub recurrence: Option<RRule<Unvalidated>>
+    pub recurrence: Option<RRule<Unvalidated>>,
+    /// IANA time zone the event recurs in, the realm's when left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(custom(is_timezone)))]
+    pub timezone: Option<String>
+}
+
+/// Which occurrences of a recurring event an update applies to. Events that
+/// don't recur always change as a whole.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EditScope {
+    This,
+    ThisAndFollowing,
+    #[default]
+    All,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, Clone, garde::Validate)]
+pub struct UpdateEventRequest {
+    #[serde(default)]
+    #[garde(skip)]
+    pub scope: EditScope,
+    /// Original start of the occurrence being edited, needed for the `this`
+    /// and `this_and_following` scopes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub occurrence: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(length(min = 2, max = 48), custom(is_sane)))]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[garde(inner(inner(length(max = 4096), custom(is_sane))))]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[garde(inner(inner(length(max = 4096), custom(is_sane))))]
+    pub location: Option<Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub end_time: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub recurrence: Option<Option<RRule<Unvalidated>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(custom(is_timezone)))]
+    pub timezone: Option<String>,
 }
 
 pub async fn create_event(
     Path(realm_id): Path<Snowflake>,
     Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
     State(app): State<NebulaApp>,
     ValidJson(payload): ValidJson<CreateEventRequest>
 ) -> NebulaResponse<RealmEventObject> {
+    if !permissions.contains_any(&[RealmPermission::CreateEvents, RealmPermission::ManageEvents]) {
+        return error(axum::http::StatusCode::FORBIDDEN, "You can't create events in this realm");
+    }
     let db = &app.db;
     let encoded_recurrence = payload.recurrence.as_ref().map(|r| r.to_string());
+    let timezone = payload.timezone.clone().unwrap_or(realm.timezone);
 
     let snowflake = next_snowflake();
-    let event = realm_events::ActiveModel {
+    let mut event = realm_events::ActiveModel {
         id: Set(snowflake),
         name: Set(payload.name.clone()),
         description: Set(payload.description.clone()),
@@ -51,7 +113,11 @@ pub async fn create_event(
         start_time: Set(payload.start_time),
         end_time: Set(payload.end_time),
         recurrence: Set(encoded_recurrence),
+        exdates: Set(Exdates::default()),
+        timezone: Set(timezone.clone()),
+        series_end: Set(None),
     };
+    service::event::refresh_series_end(&mut event, &[]);
     realm_events::Entity::insert(event)
         .exec(db)
         .await
@@ -65,9 +131,16 @@ pub async fn create_event(
         realm_id,
         start_time: payload.start_time,
         end_time: payload.end_time,
-        recurrence: payload.recurrence
+        recurrence: payload.recurrence,
+        exdates: vec![],
+        timezone
     };
 
+    let entry = AuditEntry::new(AuditAction::Created, AuditTarget::Event, snowflake).after(&dto);
+    service::audit::record(db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
     send_event_created(
         &app.cableway,
         dto.clone()
@@ -82,7 +155,8 @@ pub async fn create_event(
 
 pub async fn delete_event(
     Path((realm_id, event_id)): Path<(Snowflake, Snowflake)>,
-    Extension(_user): Extension<users::Model>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
     State(app): State<NebulaApp>
 ) -> NebulaResponse<()> {
     let db = &app.db;
@@ -99,6 +173,11 @@ pub async fn delete_event(
     if event.realm_id != realm_id {
         return error(axum::http::StatusCode::BAD_REQUEST, "Event does not belong to the specified realm");
     }
+    // Members who can only create events still manage the ones they created.
+    let own_event = event.created_by == user.id && permissions.contains(RealmPermission::CreateEvents);
+    if !own_event && !permissions.contains(RealmPermission::ManageEvents) {
+        return error(axum::http::StatusCode::FORBIDDEN, "You can't delete this event");
+    }
 
     send_event_deleted(
         &app.cableway,
@@ -112,5 +191,224 @@ pub async fn delete_event(
         .await
         .expect("Failed to delete event");
 
+    let entry = AuditEntry::new(AuditAction::Deleted, AuditTarget::Event, event_id)
+        .before(&RealmEventDto::from_model(&event));
+    service::audit::record(db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
     no_content()
-}
\ No newline at end of file
+}
+
+/// Updates an event, or part of a recurring one. The response lists every event
+/// that changed, starting with the one that now holds the edited occurrence.
+pub async fn update_event(
+    Path((realm_id, event_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<UpdateEventRequest>
+) -> NebulaResponse<RealmEventsObject> {
+    let db = &app.db;
+    let event = match editable_event(db, realm_id, event_id, &user, permissions).await {
+        Ok(event) => event,
+        Err(response) => return response,
+    };
+
+    let scope = match (payload.scope, payload.occurrence) {
+        _ if event.recurrence.is_none() => EditScope::All,
+        (EditScope::All, _) => EditScope::All,
+        (_, None) => return error(axum::http::StatusCode::BAD_REQUEST, "Pick the occurrence to edit"),
+        // Everything from the first occurrence on is the whole series.
+        (EditScope::ThisAndFollowing, Some(occurrence)) if occurrence == event.start_time => EditScope::All,
+        (scope, Some(_)) => scope,
+    };
+    if scope == EditScope::This && payload.recurrence.as_ref().is_some_and(Option::is_some) {
+        return error(axum::http::StatusCode::BAD_REQUEST, "A single occurrence can't recur");
+    }
+    if scope == EditScope::This && payload.timezone.is_some() {
+        return error(axum::http::StatusCode::BAD_REQUEST, "A single occurrence can't change time zone");
+    }
+
+    let changes = EventChanges {
+        name: payload.name,
+        description: payload.description,
+        location: payload.location,
+        start_time: payload.start_time,
+        end_time: payload.end_time,
+        recurrence: payload.recurrence.map(|recurrence| recurrence.map(|rule| rule.to_string())),
+        timezone: payload.timezone,
+    };
+    let before = RealmEventDto::from_model(&event);
+    match (scope, payload.occurrence) {
+        (EditScope::This, Some(occurrence)) => {
+            let change = match service::event::override_occurrence(db, &event, occurrence, changes).await {
+                Ok(change) => change,
+                Err(err) => return event_edit_error(err),
+            };
+            let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::EventOverride, change.id)
+                .after(&RealmEventOverrideDto::from_model(&change));
+            finish_update(&app, realm_id, user.id, vec![before], vec![entry]).await
+        },
+        (EditScope::ThisAndFollowing, Some(occurrence)) => {
+            let (created, series) = match service::event::split_series(db, event, occurrence, changes).await {
+                Ok(edited) => edited,
+                Err(err) => return event_edit_error(err),
+            };
+            let created = RealmEventDto::from_model(&created);
+            let series = RealmEventDto::from_model(&series);
+            let entries = vec![
+                AuditEntry::new(AuditAction::Created, AuditTarget::Event, created.id).after(&created),
+                AuditEntry::new(AuditAction::Updated, AuditTarget::Event, event_id).before(&before).after(&series),
+            ];
+            finish_update(&app, realm_id, user.id, vec![created, series], entries).await
+        },
+        _ => {
+            let event = service::event::update_event(db, event, changes)
+                .await
+                .expect("Failed to update event");
+            let dto = RealmEventDto::from_model(&event);
+            let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::Event, event_id).before(&before).after(&dto);
+            finish_update(&app, realm_id, user.id, vec![dto], vec![entry]).await
+        },
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, garde::Validate)]
+pub struct OccurrenceRequest {
+    /// Original start of the occurrence.
+    #[garde(skip)]
+    pub occurrence: DateTime<Utc>,
+}
+
+/// Cancels a single occurrence of a recurring event.
+pub async fn cancel_occurrence(
+    Path((realm_id, event_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<OccurrenceRequest>
+) -> NebulaResponse<RealmEventsObject> {
+    let db = &app.db;
+    let event = match editable_event(db, realm_id, event_id, &user, permissions).await {
+        Ok(event) => event,
+        Err(response) => return response,
+    };
+    let before = RealmEventDto::from_model(&event);
+    let event = match service::event::cancel_occurrence(db, event, payload.occurrence).await {
+        Ok(event) => event,
+        Err(err) => return event_edit_error(err),
+    };
+    let dto = RealmEventDto::from_model(&event);
+    let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::Event, event_id).before(&before).after(&dto);
+    finish_update(&app, realm_id, user.id, vec![dto], vec![entry]).await
+}
+
+/// Brings back an occurrence that was cancelled.
+pub async fn restore_occurrence(
+    Path((realm_id, event_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<OccurrenceRequest>
+) -> NebulaResponse<RealmEventsObject> {
+    let db = &app.db;
+    let event = match editable_event(db, realm_id, event_id, &user, permissions).await {
+        Ok(event) => event,
+        Err(response) => return response,
+    };
+    let before = RealmEventDto::from_model(&event);
+    let event = match service::event::restore_occurrence(db, event, payload.occurrence).await {
+        Ok(event) => event,
+        Err(err) => return event_edit_error(err),
+    };
+    let dto = RealmEventDto::from_model(&event);
+    let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::Event, event_id).before(&before).after(&dto);
+    finish_update(&app, realm_id, user.id, vec![dto], vec![entry]).await
+}
+
+/// Drops an occurrence's override, so it follows the series again.
+pub async fn delete_override(
+    Path((realm_id, event_id, override_id)): Path<(Snowflake, Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmEventsObject> {
+    let db = &app.db;
+    let event = match editable_event(db, realm_id, event_id, &user, permissions).await {
+        Ok(event) => event,
+        Err(response) => return response,
+    };
+    let change = service::event::find_override(db, event_id, override_id)
+        .await
+        .expect("Failed to query event override");
+    let Some(change) = change else {
+        return error(axum::http::StatusCode::NOT_FOUND, "Override not found");
+    };
+    let entry = AuditEntry::new(AuditAction::Deleted, AuditTarget::EventOverride, override_id)
+        .before(&RealmEventOverrideDto::from_model(&change));
+    service::event::delete_override(db, change)
+        .await
+        .expect("Failed to delete event override");
+    finish_update(&app, realm_id, user.id, vec![RealmEventDto::from_model(&event)], vec![entry]).await
+}
+
+/// Finds an event in the realm that the user is allowed to change.
+async fn editable_event<T: Serialize>(
+    db: &DatabaseConnection,
+    realm_id: Snowflake,
+    event_id: Snowflake,
+    user: &users::Model,
+    permissions: RealmPermissions
+) -> Result<realm_events::Model, NebulaResponse<T>> {
+    let event = realm_events::Entity::find_by_id(event_id)
+        .one(db)
+        .await
+        .expect("Failed to query event");
+    let Some(event) = event.filter(|event| event.realm_id == realm_id) else {
+        return Err(error(axum::http::StatusCode::NOT_FOUND, "Event not found"));
+    };
+    let own_event = event.created_by == user.id && permissions.contains(RealmPermission::CreateEvents);
+    if !own_event && !permissions.contains(RealmPermission::ManageEvents) {
+        return Err(error(axum::http::StatusCode::FORBIDDEN, "You can't edit this event"));
+    }
+    Ok(event)
+}
+
+async fn finish_update(
+    app: &NebulaApp,
+    realm_id: Snowflake,
+    actor_id: Snowflake,
+    events: Vec<RealmEventDto>,
+    entries: Vec<AuditEntry>
+) -> NebulaResponse<RealmEventsObject> {
+    for entry in entries {
+        service::audit::record(&app.db, realm_id, actor_id, entry)
+            .await
+            .expect("Failed to record audit log entry");
+    }
+
+    let event_ids: Vec<Snowflake> = events.iter().map(|event| event.id).collect();
+    let mut by_event = service::event::overrides_by_event(&app.db, &event_ids)
+        .await
+        .expect("Failed to query event overrides");
+    let overrides: Vec<RealmEventOverrideDto> = event_ids.iter()
+        .filter_map(|event_id| by_event.remove(event_id))
+        .flatten()
+        .map(|change| RealmEventOverrideDto::from_model(&change))
+        .collect();
+
+    send_event_updated(&app.cableway, realm_id, events.clone(), overrides.clone())
+        .await
+        .expect("Failed to send event updated message");
+
+    ok(RealmEventsObject { events, overrides })
+}
+
+fn event_edit_error<T: Serialize>(err: EventEditError) -> NebulaResponse<T> {
+    match err {
+        EventEditError::NotAnOccurrence => error(axum::http::StatusCode::BAD_REQUEST, "That isn't an occurrence of this event"),
+        EventEditError::InvalidRule => error(axum::http::StatusCode::BAD_REQUEST, "The recurrence rule can't be expanded"),
+        EventEditError::Database(err) => panic!("Failed to update event: {:?}", err),
+    }
+}
@@ -0,0 +1,107 @@
+use crate::app::NebulaApp;
+use crate::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use crate::schema::{realm_calendar_feeds, realms, users};
+use crate::service;
+use crate::web::routing::dto::RealmCalendarFeedDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidQuery;
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, garde::Validate)]
+pub struct CalendarExportQuery {
+    /// Adds the realm's tasks as to-dos.
+    #[serde(default)]
+    #[garde(skip)]
+    pub tasks: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmCalendarFeedObject {
+    pub feed: RealmCalendarFeedDto,
+}
+
+/// The secret is only ever returned here, right after creation.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreatedCalendarFeedObject {
+    pub feed: RealmCalendarFeedDto,
+    pub secret: String,
+    /// Where calendar apps subscribe, relative to the API.
+    pub path: String,
+}
+
+pub async fn get_calendar_ics(
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    feed: Option<Extension<realm_calendar_feeds::Model>>,
+    State(app): State<NebulaApp>,
+    ValidQuery(query): ValidQuery<CalendarExportQuery>
+) -> Response {
+    // A feed token only opens the realm it was made for.
+    if feed.is_some_and(|Extension(feed)| feed.realm_id != realm.id) {
+        return error::<()>(StatusCode::NOT_FOUND, "Calendar feed not found").into_response();
+    }
+
+    let include_tasks = query.tasks && permissions.contains(RealmPermission::ViewTasks);
+    let calendar = service::feed::render_calendar(&app.db, &realm, include_tasks)
+        .await
+        .expect("Failed to render realm calendar");
+
+    let disposition = format!("inline; filename=\"nebula-realm-{}.ics\"", realm.id);
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        calendar
+    ).into_response()
+}
+
+pub async fn get_feed(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmCalendarFeedObject> {
+    let feed = service::feed::find_feed(&app.db, realm.id, user.id)
+        .await
+        .expect("Failed to query calendar feed");
+    match feed {
+        Some(feed) => ok(RealmCalendarFeedObject { feed: RealmCalendarFeedDto::from_model(&feed) }),
+        None => error(StatusCode::NOT_FOUND, "Calendar feed not found"),
+    }
+}
+
+/// Creates the member's feed, or replaces it so the old URL stops working.
+pub async fn create_feed(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<CreatedCalendarFeedObject> {
+    let created = service::feed::create_feed(&app.db, realm.id, user.id)
+        .await
+        .expect("Failed to create calendar feed");
+
+    ok(CreatedCalendarFeedObject {
+        feed: RealmCalendarFeedDto::from_model(&created.model),
+        path: format!("/api/realms/{}/calendar.ics?token={}", realm.id, created.token),
+        secret: created.token,
+    })
+}
+
+pub async fn revoke_feed(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let revoked = service::feed::revoke_feed(&app.db, realm.id, user.id)
+        .await
+        .expect("Failed to revoke calendar feed");
+    if !revoked {
+        return error(StatusCode::NOT_FOUND, "Calendar feed not found");
+    }
+    no_content()
+}
@@ -1,9 +1,17 @@
-use crate::web::routing::dto::RealmEventDto;
+use crate::web::routing::dto::{RealmEventDto, RealmEventOverrideDto};
 
 pub mod events;
+pub mod feed;
 pub mod occurrences;
 
 #[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
 pub struct RealmEventObject {
     pub event: RealmEventDto
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
+pub struct RealmEventsObject {
+    pub events: Vec<RealmEventDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<RealmEventOverrideDto>
 }
\ No newline at end of file
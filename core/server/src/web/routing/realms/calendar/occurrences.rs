@@ -1,10 +1,12 @@
 use crate::app::NebulaApp;
-use crate::data::snowflake::Snowflake;
+use crate::data::permissions::{BitwisePermissions, RealmPermission, RealmPermissions};
+use crate::schema::{realms, users};
 use crate::service;
 use crate::web::routing::dto::RealmScheduleDto;
 use crate::web::routing::error::{ok, NebulaResponse};
 use crate::web::routing::middlewares::validation::ValidQuery;
-use axum::extract::{Path, State};
+use axum::extract::State;
+use axum::Extension;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, garde::Validate)]
 pub struct OccurrenceQuery {
@@ -15,18 +17,58 @@ pub struct OccurrenceQuery {
 }
 
 pub async fn get_occurrences(
-    Path(realm_id): Path<Snowflake>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
     State(app): State<NebulaApp>,
     ValidQuery(query): ValidQuery<OccurrenceQuery>
 ) -> NebulaResponse<RealmScheduleDto> {
-    let schedule = service::schedule::get_realm_schedule(
+    let mut schedule = service::schedule::get_realm_schedule(
         &app.db,
-        realm_id,
+        &realm,
         query.start,
         query.end
     )
         .await
         .expect("Failed to get realm schedule");
+    if !permissions.contains(RealmPermission::ViewTasks) {
+        schedule.tasks.clear();
+    }
 
     ok(schedule)
-}
\ No newline at end of file
+}
+/// The realm's schedule together with every realm nested below it that the
+/// caller can see, after any per-realm overrides.
+pub async fn get_combined_occurrences(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidQuery(query): ValidQuery<OccurrenceQuery>
+) -> NebulaResponse<RealmScheduleDto> {
+    let descendants = service::realm::descendants(&app.db, &[realm.id])
+        .await
+        .expect("Failed to query nested realms");
+
+    let mut visible = vec![(realm, permissions)];
+    for descendant in descendants {
+        let access = service::realm::resolve_access(&app.db, &descendant, user.id)
+            .await
+            .expect("Failed to query realm membership");
+        if let Some(access) = access && access.permissions.contains(RealmPermission::ViewCalendar) {
+            visible.push((descendant, access.permissions));
+        }
+    }
+
+    let mut schedules = vec![];
+    for (realm, permissions) in visible {
+        let mut schedule = service::schedule::get_realm_schedule(&app.db, &realm, query.start, query.end)
+            .await
+            .expect("Failed to get realm schedule");
+        if !permissions.contains(RealmPermission::ViewTasks) {
+            schedule.tasks.clear();
+        }
+        schedules.push(schedule);
+    }
+
+    ok(service::schedule::merge_schedules(schedules))
+}
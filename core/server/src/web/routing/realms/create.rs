@@ -1,15 +1,20 @@
 use crate::app::NebulaApp;
 use crate::schema::{realms, users};
+use crate::schema::realms::RealmVisibility;
 use crate::service::snowflake::next_snowflake;
-use crate::util::validation::is_sane;
+use crate::util::validation::{is_sane, is_timezone};
 use crate::web::routing::dto::RealmDto;
 use crate::web::routing::error::{error, ok, NebulaResponse};
 use crate::web::routing::middlewares::validation::ValidJson;
 use crate::web::routing::realms::RealmObject;
+use crate::web::routing::realms::settings::parent_error;
+use crate::data::snowflake::Snowflake;
 use axum::extract::State;
 use axum::Extension;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::Set;
 use crate::service;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, garde::Validate)]
 pub struct CreateRealmPayload {
@@ -18,6 +23,17 @@ pub struct CreateRealmPayload {
     #[serde(default)]
     #[garde(length(max = 1024), inner(custom(is_sane)))]
     pub description: Option<String>,
+    /// Nests the new realm inside one the user can manage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub parent_id: Option<Snowflake>,
+    #[serde(default)]
+    #[garde(skip)]
+    pub visibility: RealmVisibility,
+    /// IANA time zone for the realm's events, UTC when left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(custom(is_timezone)))]
+    pub timezone: Option<String>,
 }
 
 pub async fn create_realm(
@@ -25,27 +41,41 @@ pub async fn create_realm(
     Extension(user): Extension<users::Model>,
     ValidJson(payload): ValidJson<CreateRealmPayload>
 ) -> NebulaResponse<RealmObject> {
+    if app.config.require_verified_email && !user.email_verified {
+        return error(
+            axum::http::StatusCode::FORBIDDEN,
+            "You must verify your email before creating realms"
+        );
+    }
+
     let db = &app.db;
-    let existing_realm = realms::Entity::find()
-        .filter(realms::Column::Name.eq(payload.name.clone()))
-        .filter(realms::Column::OwnerId.eq(user.id))
-        .one(db)
+    let name_taken = service::realm::owner_has_realm_named(db, user.id, &payload.name, None)
         .await
         .expect("Failed to query realms");
 
-    if existing_realm.is_some() {
+    if name_taken {
         return error(
             axum::http::StatusCode::CONFLICT,
             "You already have a realm with the same name"
         );
     }
 
+    if let Some(parent_id) = payload.parent_id
+        && let Err(err) = service::realm::check_parent(db, user.id, None, parent_id).await {
+        return parent_error(err);
+    }
+
     let new_realm_snowflake = next_snowflake();
     let new_realm = realms::ActiveModel {
         id: Set(new_realm_snowflake),
         name: Set(payload.name.clone()),
         owner_id: Set(user.id),
-        description: Set(payload.description.clone())
+        description: Set(payload.description.clone()),
+        pending_owner_id: Set(None),
+        archived_at: Set(None),
+        parent_id: Set(payload.parent_id),
+        visibility: Set(payload.visibility),
+        timezone: Set(payload.timezone.clone().unwrap_or_else(|| "UTC".to_string()))
     };
 
     let inserted_realm = service::realm::create_realm(db, user.id, new_realm)
@@ -53,5 +83,10 @@ pub async fn create_realm(
         .expect("Failed to create a new realm");
 
     let dto = RealmDto::from_model(&inserted_realm);
+    let entry = AuditEntry::new(AuditAction::Created, AuditTarget::Realm, dto.id).after(&dto);
+    service::audit::record(db, dto.id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
     ok(RealmObject { realm: dto })
 }
\ No newline at end of file
@@ -0,0 +1,246 @@
+use crate::app::NebulaApp;
+use crate::cableway::events::members::send_member_joined;
+use crate::data::snowflake::Snowflake;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::schema::{realm_members, realms, users};
+use crate::service;
+use crate::service::audit::AuditEntry;
+use crate::service::discovery::JoinError;
+use crate::util::validation::is_sane;
+use crate::web::routing::dto::{DiscoverableRealmDto, RealmDto, RealmJoinRequestDto, RealmMemberDto};
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::{ValidJson, ValidQuery};
+use crate::web::routing::realms::members::RealmMemberObject;
+use crate::web::routing::realms::RealmObject;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PAGE_SIZE: u64 = 25;
+
+#[derive(Serialize, Deserialize, Debug, Default, garde::Validate)]
+pub struct DiscoverQuery {
+    /// Matched against realm names and descriptions, ignoring case.
+    #[garde(inner(length(max = 48)))]
+    pub q: Option<String>,
+    /// Cursor from the previous page's `next`.
+    #[garde(skip)]
+    pub after: Option<Snowflake>,
+    #[garde(inner(range(min = 1, max = 50)))]
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DiscoverRealmsObject {
+    pub realms: Vec<DiscoverableRealmDto>,
+    /// Pass as `after` to fetch the next page, `None` on the last one.
+    pub next: Option<Snowflake>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, garde::Validate)]
+pub struct JoinRequestPayload {
+    /// A note for whoever reviews the request.
+    #[serde(default)]
+    #[garde(inner(length(max = 500), custom(is_sane)))]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmJoinRequestObject {
+    pub request: RealmJoinRequestDto,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmJoinRequestsObject {
+    pub requests: Vec<RealmJoinRequestDto>,
+}
+
+pub async fn discover_realms(
+    State(app): State<NebulaApp>,
+    ValidQuery(query): ValidQuery<DiscoverQuery>
+) -> NebulaResponse<DiscoverRealmsObject> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let realms = service::discovery::discover(&app.db, query.q.as_deref(), query.after, limit)
+        .await
+        .expect("Failed to query realm directory");
+    let realm_ids: Vec<Snowflake> = realms.iter().map(|realm| realm.id).collect();
+    let counts = service::discovery::member_counts(&app.db, &realm_ids)
+        .await
+        .expect("Failed to count realm members");
+
+    let next = (realms.len() as u64 == limit)
+        .then(|| realms.last().map(|realm| realm.id))
+        .flatten();
+    ok(DiscoverRealmsObject {
+        realms: realms.iter()
+            .map(|realm| DiscoverableRealmDto::from_model(realm, counts.get(&realm.id).copied().unwrap_or_default()))
+            .collect(),
+        next,
+    })
+}
+
+/// Joins an open realm straight away.
+pub async fn join_realm(
+    Path(realm_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmObject> {
+    let joined = match service::discovery::find_discoverable(&app.db, realm_id).await {
+        Ok(realm) => service::discovery::join_open_realm(&app.db, &realm, user.id)
+            .await
+            .map(|membership| (realm, membership)),
+        Err(err) => Err(err),
+    };
+    let (realm, membership) = match joined {
+        Ok(joined) => joined,
+        Err(err) => return join_error(err),
+    };
+
+    announce_member(&app, &realm, &membership, &user, user.id, None).await;
+    ok(RealmObject { realm: RealmDto::from_model(&realm) })
+}
+
+/// Asks to join a listed realm.
+pub async fn request_to_join(
+    Path(realm_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<JoinRequestPayload>
+) -> NebulaResponse<RealmJoinRequestObject> {
+    let requested = match service::discovery::find_discoverable(&app.db, realm_id).await {
+        Ok(realm) => service::discovery::request_to_join(&app.db, &realm, user.id, payload.message).await,
+        Err(err) => Err(err),
+    };
+    let request = match requested {
+        Ok(request) => request,
+        Err(err) => return join_error(err),
+    };
+
+    let dto = RealmJoinRequestDto::from_model(&request, &user);
+    let entry = AuditEntry::new(AuditAction::Created, AuditTarget::JoinRequest, request.id)
+        .after(&serde_json::json!({ "user_id": user.id }));
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    ok(RealmJoinRequestObject { request: dto })
+}
+
+pub async fn withdraw_join_request(
+    Path(realm_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let request = service::discovery::find_request_by_user(&app.db, realm_id, user.id)
+        .await
+        .expect("Failed to query join request");
+    let Some(request) = request else {
+        return error(StatusCode::NOT_FOUND, "Join request not found");
+    };
+
+    service::discovery::remove_request(&app.db, &request)
+        .await
+        .expect("Failed to withdraw join request");
+    no_content()
+}
+
+pub async fn list_join_requests(
+    Path(realm_id): Path<Snowflake>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmJoinRequestsObject> {
+    let requests = service::discovery::list_requests(&app.db, realm_id)
+        .await
+        .expect("Failed to query join requests");
+    ok(RealmJoinRequestsObject {
+        requests: requests.iter()
+            .map(|(request, user)| RealmJoinRequestDto::from_model(request, user))
+            .collect(),
+    })
+}
+
+pub async fn approve_join_request(
+    Path((realm_id, request_id)): Path<(Snowflake, Snowflake)>,
+    Extension(actor): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmMemberObject> {
+    let request = service::discovery::find_request(&app.db, realm_id, request_id)
+        .await
+        .expect("Failed to query join request");
+    let Some((request, user)) = request else {
+        return error(StatusCode::NOT_FOUND, "Join request not found");
+    };
+
+    let membership = match service::discovery::approve_request(&app.db, &request).await {
+        Ok(membership) => membership,
+        Err(err) => return join_error(err),
+    };
+
+    let member = announce_member(&app, &realm, &membership, &user, actor.id, Some(request.id)).await;
+    ok(RealmMemberObject { member })
+}
+
+pub async fn reject_join_request(
+    Path((realm_id, request_id)): Path<(Snowflake, Snowflake)>,
+    Extension(actor): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let request = service::discovery::find_request(&app.db, realm_id, request_id)
+        .await
+        .expect("Failed to query join request");
+    let Some((request, user)) = request else {
+        return error(StatusCode::NOT_FOUND, "Join request not found");
+    };
+
+    service::discovery::remove_request(&app.db, &request)
+        .await
+        .expect("Failed to reject join request");
+
+    let entry = AuditEntry::new(AuditAction::Rejected, AuditTarget::JoinRequest, request.id)
+        .before(&serde_json::json!({ "user_id": user.id }));
+    service::audit::record(&app.db, realm_id, actor.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    no_content()
+}
+
+/// Records the new member in the audit log and tells the realm about them.
+async fn announce_member(
+    app: &NebulaApp,
+    realm: &realms::Model,
+    membership: &realm_members::Model,
+    user: &users::Model,
+    actor_id: Snowflake,
+    request_id: Option<Snowflake>
+) -> RealmMemberDto {
+    let permissions = service::role::combine_permissions(realm, membership, &[]);
+    let member = RealmMemberDto::from_model(membership, user, &[], permissions);
+
+    let entry = match request_id {
+        Some(request_id) => AuditEntry::new(AuditAction::Approved, AuditTarget::JoinRequest, request_id)
+            .after(&serde_json::json!({ "user_id": user.id, "permissions": member.permissions })),
+        None => AuditEntry::new(AuditAction::Joined, AuditTarget::Member, user.id)
+            .after(&serde_json::json!({ "permissions": member.permissions })),
+    };
+    service::audit::record(&app.db, realm.id, actor_id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    send_member_joined(&app.cableway, realm.id, member.clone())
+        .await
+        .expect("Failed to send member joined message");
+    member
+}
+
+fn join_error<T: Serialize>(err: JoinError) -> NebulaResponse<T> {
+    match err {
+        JoinError::NotFound => error(StatusCode::NOT_FOUND, "Realm not found"),
+        JoinError::NotOpen => error(StatusCode::FORBIDDEN, "This realm takes join requests instead"),
+        JoinError::NotListed => error(StatusCode::CONFLICT, "This realm is open, join it directly"),
+        JoinError::AlreadyMember => error(StatusCode::CONFLICT, "You are already a member of this realm"),
+        JoinError::AlreadyRequested => error(StatusCode::CONFLICT, "You already asked to join this realm"),
+        JoinError::Database(err) => panic!("Failed to process join: {:?}", err),
+    }
+}
@@ -0,0 +1,152 @@
+use crate::app::NebulaApp;
+use crate::data::permissions::{named, BitwisePermissions, RealmPermissions};
+use crate::data::snowflake::Snowflake;
+use crate::mail::messages;
+use crate::schema::{realm_members, realms, users};
+use crate::service;
+use crate::service::invite::NewInvite;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
+use crate::web::routing::dto::RealmInviteDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct CreateInviteRequest {
+    /// Permission names, or raw bits. Defaults to viewing the realm.
+    #[serde(default = "member_default", with = "named")]
+    #[garde(skip)]
+    pub permissions: RealmPermissions,
+    #[serde(default)]
+    #[garde(inner(range(min = 1, max = 10000)))]
+    pub max_uses: Option<i32>,
+    #[serde(default)]
+    #[garde(skip)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Invites a specific user instead of creating a shareable link.
+    #[serde(default)]
+    #[garde(skip)]
+    pub user_id: Option<Snowflake>,
+    /// Invites whoever verifies this address, mailing them the invite.
+    #[serde(default)]
+    #[garde(inner(email))]
+    pub email: Option<String>,
+}
+
+fn member_default() -> RealmPermissions {
+    RealmPermissions::MEMBER_DEFAULT
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmInviteObject {
+    pub invite: RealmInviteDto,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmInvitesObject {
+    pub invites: Vec<RealmInviteDto>,
+}
+
+pub async fn create_invite(
+    Path(realm_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<CreateInviteRequest>
+) -> NebulaResponse<RealmInviteObject> {
+    if payload.user_id.is_some() && payload.email.is_some() {
+        return error(StatusCode::BAD_REQUEST, "Invite either a user or an email, not both");
+    }
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+        return error(StatusCode::BAD_REQUEST, "Expiry must be in the future");
+    }
+    // Nobody can hand out permissions they don't have themselves.
+    let granted = payload.permissions;
+    if !permissions.contains_all(&granted) {
+        return error(StatusCode::FORBIDDEN, "You can't grant permissions you don't have");
+    }
+
+    if let Some(invitee_id) = payload.user_id {
+        let invitee = users::Entity::find_by_id(invitee_id)
+            .one(&app.db)
+            .await
+            .expect("Failed to query user");
+        if invitee.is_none() {
+            return error(StatusCode::NOT_FOUND, "User not found");
+        }
+        let existing = realm_members::Entity::find_membership(&app.db, realm_id, invitee_id)
+            .await
+            .expect("Failed to query realm membership");
+        if existing.is_some() {
+            return error(StatusCode::CONFLICT, "User is already a member of this realm");
+        }
+    }
+
+    let invite = service::invite::create_invite(&app.db, realm_id, user.id, NewInvite {
+        invitee_id: payload.user_id,
+        invitee_email: payload.email.clone(),
+        permissions: granted.bits(),
+        max_uses: payload.max_uses,
+        expires_at: payload.expires_at,
+    })
+        .await
+        .expect("Failed to create invite");
+
+    // The code itself stays out of the log, anyone reading it could join with it.
+    let entry = AuditEntry::new(AuditAction::Created, AuditTarget::Invite, invite.id).after(&serde_json::json!({
+        "invitee_id": invite.invitee_id,
+        "invitee_email": invite.invitee_email,
+        "permissions": invite.permissions,
+        "max_uses": invite.max_uses,
+        "expires_at": invite.expires_at,
+    }));
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    if let Some(email) = &invite.invitee_email {
+        let message = messages::realm_invite(&app.config, email, &user.name, &realm.name, &invite.code);
+        if let Err(err) = app.mailer.send(message).await {
+            tracing::error!("Failed to send realm invite: {}", err.0);
+        }
+    }
+
+    ok(RealmInviteObject { invite: RealmInviteDto::from_model(&invite) })
+}
+
+pub async fn list_invites(
+    Path(realm_id): Path<Snowflake>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmInvitesObject> {
+    let invites = service::invite::list_realm_invites(&app.db, realm_id)
+        .await
+        .expect("Failed to query invites");
+    let dtos = invites.iter().map(RealmInviteDto::from_model).collect();
+    ok(RealmInvitesObject { invites: dtos })
+}
+
+pub async fn revoke_invite(
+    Path((realm_id, invite_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let revoked = service::invite::revoke_invite(&app.db, realm_id, invite_id)
+        .await
+        .expect("Failed to revoke invite");
+    if !revoked {
+        return error(StatusCode::NOT_FOUND, "Invite not found");
+    }
+
+    let entry = AuditEntry::new(AuditAction::Revoked, AuditTarget::Invite, invite_id);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    no_content()
+}
@@ -0,0 +1,301 @@
+use crate::app::NebulaApp;
+use crate::cableway::events::members::{send_member_left, send_member_updated};
+use crate::data::permissions::{named, BitwisePermissions, RealmPermissions};
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realm_members, realms, users};
+use crate::service;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
+use crate::service::realm::RealmAccess;
+use crate::service::snowflake::next_snowflake;
+use crate::web::routing::dto::RealmMemberDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+
+/// Sets the member's own grants and denials, on top of their roles.
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct UpdateMemberRequest {
+    #[serde(default, with = "named::option", skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub permissions: Option<RealmPermissions>,
+    #[serde(default, with = "named::option", skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub denied_permissions: Option<RealmPermissions>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmMemberObject {
+    pub member: RealmMemberDto,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmMembersObject {
+    pub members: Vec<RealmMemberDto>,
+}
+
+pub async fn list_members(
+    Path(realm_id): Path<Snowflake>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmMembersObject> {
+    let members = service::member::list_members(&app.db, realm_id)
+        .await
+        .expect("Failed to query realm members");
+    let member_ids: Vec<Snowflake> = members.iter().map(|(membership, _)| membership.id).collect();
+    let roles = service::role::roles_by_member(&app.db, &member_ids)
+        .await
+        .expect("Failed to query member roles");
+
+    let dtos = members.iter()
+        .map(|(membership, user)| {
+            let roles = roles.get(&membership.id).map(Vec::as_slice).unwrap_or_default();
+            let permissions = service::role::combine_permissions(&realm, membership, roles);
+            RealmMemberDto::from_model(membership, user, roles, permissions)
+        })
+        .collect();
+    ok(RealmMembersObject { members: dtos })
+}
+
+pub async fn update_member(
+    Path((realm_id, user_id)): Path<(Snowflake, Snowflake)>,
+    Extension(actor): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<UpdateMemberRequest>
+) -> NebulaResponse<RealmMemberObject> {
+    if user_id == realm.owner_id {
+        return error(StatusCode::FORBIDDEN, "The realm owner's permissions can't be changed");
+    }
+    let target = match find_member(&app, realm_id, user_id).await {
+        Some((target, user)) => Some((target, user, false)),
+        None => inherited_member(&app, &realm, user_id).await.map(|(target, user)| (target, user, true)),
+    };
+    let Some((target, user, inherited)) = target else {
+        return error(StatusCode::NOT_FOUND, "Member not found");
+    };
+
+    // Every bit that flips, granted or taken away, has to be one the caller holds.
+    let granted = payload.permissions.map_or(target.permissions, |permissions| permissions.bits());
+    let denied = payload.denied_permissions.map_or(target.denied_permissions, |denied| denied.bits());
+    let changed = RealmPermissions::new((target.permissions ^ granted) | (target.denied_permissions ^ denied));
+    if !permissions.contains_all(&changed) {
+        return error(StatusCode::FORBIDDEN, "You can't change permissions you don't have");
+    }
+
+    let before = member_dto(&app, &realm, &target, &user).await;
+    let updated = if inherited {
+        service::member::override_inherited(&app.db, target, granted, denied).await
+    } else {
+        service::member::update_permissions(&app.db, target, granted, denied).await
+    }
+        .expect("Failed to update member permissions");
+    let dto = member_dto(&app, &realm, &updated, &user).await;
+
+    let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::Member, user_id).before(&before).after(&dto);
+    service::audit::record(&app.db, realm_id, actor.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    send_member_updated(&app.cableway, realm_id, dto.clone())
+        .await
+        .expect("Failed to send member updated message");
+
+    ok(RealmMemberObject { member: dto })
+}
+
+/// Gives a member a role. Only roles whose permissions the caller holds can be handed out.
+pub async fn assign_role(
+    Path((_, user_id, role_id)): Path<(Snowflake, Snowflake, Snowflake)>,
+    Extension(actor): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmMemberObject> {
+    change_role(app, actor, realm, permissions, user_id, role_id, true).await
+}
+
+pub async fn unassign_role(
+    Path((_, user_id, role_id)): Path<(Snowflake, Snowflake, Snowflake)>,
+    Extension(actor): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmMemberObject> {
+    change_role(app, actor, realm, permissions, user_id, role_id, false).await
+}
+
+async fn change_role(
+    app: NebulaApp,
+    actor: users::Model,
+    realm: realms::Model,
+    permissions: RealmPermissions,
+    user_id: Snowflake,
+    role_id: Snowflake,
+    assign: bool
+) -> NebulaResponse<RealmMemberObject> {
+    let Some((target, user)) = find_member(&app, realm.id, user_id).await else {
+        return error(StatusCode::NOT_FOUND, "Member not found");
+    };
+    let role = service::role::find_role(&app.db, realm.id, role_id)
+        .await
+        .expect("Failed to query role");
+    let Some(role) = role else {
+        return error(StatusCode::NOT_FOUND, "Role not found");
+    };
+    if !permissions.contains_all(&RealmPermissions::new(role.permissions)) {
+        return error(StatusCode::FORBIDDEN, "You can't manage a role with permissions you don't have");
+    }
+
+    let action = if assign {
+        service::role::assign_role(&app.db, target.id, role.id)
+            .await
+            .expect("Failed to assign role");
+        AuditAction::RoleAssigned
+    } else {
+        service::role::unassign_role(&app.db, target.id, role.id)
+            .await
+            .expect("Failed to unassign role");
+        AuditAction::RoleUnassigned
+    };
+
+    let entry = AuditEntry::new(action, AuditTarget::Member, user_id)
+        .after(&serde_json::json!({ "role_id": role.id, "role": role.name }));
+    service::audit::record(&app.db, realm.id, actor.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    let dto = member_dto(&app, &realm, &target, &user).await;
+    send_member_updated(&app.cableway, realm.id, dto.clone())
+        .await
+        .expect("Failed to send member updated message");
+
+    ok(RealmMemberObject { member: dto })
+}
+
+pub async fn kick_member(
+    Path((realm_id, user_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    if user_id == realm.owner_id {
+        return error(StatusCode::FORBIDDEN, "The realm owner can't be kicked");
+    }
+    if user_id == user.id {
+        return error(StatusCode::BAD_REQUEST, "Leave the realm instead of kicking yourself");
+    }
+    let Some((target, _)) = find_member(&app, realm_id, user_id).await else {
+        return error(StatusCode::NOT_FOUND, "Member not found");
+    };
+    // Same rule as for changing permissions: nobody removes someone who can do
+    // more than they can.
+    let roles = service::role::member_roles(&app.db, target.id)
+        .await
+        .expect("Failed to query member roles");
+    if !permissions.contains_all(&service::role::combine_permissions(&realm, &target, &roles)) {
+        return error(StatusCode::FORBIDDEN, "You can't kick a member with permissions you don't have");
+    }
+
+    service::member::remove_member(&app.db, target)
+        .await
+        .expect("Failed to remove realm member");
+    send_member_left(&app.cableway, realm_id, user_id, true)
+        .await
+        .expect("Failed to send member left message");
+
+    let entry = AuditEntry::new(AuditAction::Kicked, AuditTarget::Member, user_id);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    no_content()
+}
+
+pub async fn leave_realm(
+    Path(realm_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(access): Extension<RealmAccess>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    if user.id == realm.owner_id {
+        return error(StatusCode::CONFLICT, "Transfer ownership before leaving your realm");
+    }
+    if access.is_inherited() {
+        return error(StatusCode::CONFLICT, "Your membership comes from a parent realm, leave that one instead");
+    }
+
+    service::member::remove_member(&app.db, access.membership)
+        .await
+        .expect("Failed to remove realm member");
+    send_member_left(&app.cableway, realm_id, user.id, false)
+        .await
+        .expect("Failed to send member left message");
+
+    let entry = AuditEntry::new(AuditAction::Left, AuditTarget::Member, user.id);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    no_content()
+}
+
+async fn member_dto(
+    app: &NebulaApp,
+    realm: &realms::Model,
+    membership: &realm_members::Model,
+    user: &users::Model
+) -> RealmMemberDto {
+    let roles = service::role::member_roles(&app.db, membership.id)
+        .await
+        .expect("Failed to query member roles");
+    let permissions = service::role::combine_permissions(realm, membership, &roles);
+    RealmMemberDto::from_model(membership, user, &roles, permissions)
+}
+
+/// Someone who only inherits access from a parent realm, as the membership
+/// they'd get here, starting from what they inherit. It isn't saved until the
+/// realm actually overrides it.
+async fn inherited_member(
+    app: &NebulaApp,
+    realm: &realms::Model,
+    user_id: Snowflake
+) -> Option<(realm_members::Model, users::Model)> {
+    let access = service::realm::inherited_access(&app.db, realm, user_id)
+        .await
+        .expect("Failed to query inherited membership")?;
+    let user = users::Entity::find_by_id(user_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query user")?;
+    let membership = realm_members::Model {
+        id: next_snowflake(),
+        realm_id: realm.id,
+        user_id,
+        permissions: access.permissions.bits(),
+        denied_permissions: 0,
+    };
+    Some((membership, user))
+}
+
+async fn find_member(
+    app: &NebulaApp,
+    realm_id: Snowflake,
+    user_id: Snowflake
+) -> Option<(realm_members::Model, users::Model)> {
+    let membership = realm_members::Entity::find_membership(&app.db, realm_id, user_id)
+        .await
+        .expect("Failed to query realm membership")?;
+    let user = users::Entity::find_by_id(user_id)
+        .one(&app.db)
+        .await
+        .expect("Failed to query user")?;
+    Some((membership, user))
+}
@@ -1,6 +1,16 @@
+pub mod archive;
+pub mod audit;
 pub mod create;
+pub mod discovery;
 pub mod calendar;
 pub mod task;
+pub mod invites;
+pub mod members;
+pub mod permissions;
+pub mod roles;
+pub mod settings;
+pub mod share;
+pub mod transfer;
 
 use crate::app::NebulaApp;
 use crate::schema::realms;
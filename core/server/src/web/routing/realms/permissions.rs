@@ -0,0 +1,33 @@
+use crate::data::permissions::{named, BitwisePermissions, RealmPermissions};
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realms, users};
+use crate::service::realm::RealmAccess;
+use crate::web::routing::error::{ok, NebulaResponse};
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+/// The caller's effective permissions in a realm, after roles and denials.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EffectivePermissionsObject {
+    #[serde(with = "named")]
+    pub permissions: RealmPermissions,
+    pub bits: i16,
+    pub owner: bool,
+    /// The parent realm the caller's membership comes from, if they aren't a member here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherited_from: Option<Snowflake>,
+}
+
+pub async fn get_my_permissions(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    Extension(access): Extension<RealmAccess>
+) -> NebulaResponse<EffectivePermissionsObject> {
+    ok(EffectivePermissionsObject {
+        permissions,
+        bits: permissions.bits(),
+        owner: user.id == realm.owner_id,
+        inherited_from: access.inherited_from,
+    })
+}
@@ -0,0 +1,170 @@
+use crate::app::NebulaApp;
+use crate::data::permissions::{named, BitwisePermissions, RealmPermissions};
+use crate::data::snowflake::Snowflake;
+use crate::schema::users;
+use crate::service;
+use crate::service::role::RoleChanges;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
+use crate::util::patch::nullable;
+use crate::util::validation::is_sane;
+use crate::web::routing::dto::RealmRoleDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+const MAX_COLOUR: i32 = 0xFFFFFF;
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct CreateRoleRequest {
+    #[garde(length(min = 1, max = 32), custom(is_sane))]
+    pub name: String,
+    #[serde(default, with = "named")]
+    #[garde(skip)]
+    pub permissions: RealmPermissions,
+    #[serde(default)]
+    #[garde(inner(range(min = 0, max = MAX_COLOUR)))]
+    pub colour: Option<i32>,
+    #[serde(default)]
+    #[garde(skip)]
+    pub position: Option<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct UpdateRoleRequest {
+    #[serde(default)]
+    #[garde(inner(length(min = 1, max = 32), custom(is_sane)))]
+    pub name: Option<String>,
+    #[serde(default, with = "named::option", skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub permissions: Option<RealmPermissions>,
+    /// `null` clears the colour, leaving the field out keeps it.
+    #[serde(default, deserialize_with = "nullable")]
+    #[garde(inner(inner(range(min = 0, max = MAX_COLOUR))))]
+    pub colour: Option<Option<i32>>,
+    #[serde(default)]
+    #[garde(skip)]
+    pub position: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmRoleObject {
+    pub role: RealmRoleDto,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmRolesObject {
+    pub roles: Vec<RealmRoleDto>,
+}
+
+pub async fn list_roles(
+    Path(realm_id): Path<Snowflake>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmRolesObject> {
+    let roles = service::role::list_roles(&app.db, realm_id)
+        .await
+        .expect("Failed to query roles");
+    ok(RealmRolesObject { roles: roles.iter().map(RealmRoleDto::from_model).collect() })
+}
+
+pub async fn create_role(
+    Path(realm_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<CreateRoleRequest>
+) -> NebulaResponse<RealmRoleObject> {
+    if !permissions.contains_all(&payload.permissions) {
+        return error(StatusCode::FORBIDDEN, "You can't grant permissions you don't have");
+    }
+
+    let role = service::role::create_role(
+        &app.db,
+        realm_id,
+        payload.name,
+        payload.permissions.bits(),
+        payload.colour,
+        payload.position
+    )
+        .await
+        .expect("Failed to create role");
+    let dto = RealmRoleDto::from_model(&role);
+
+    let entry = AuditEntry::new(AuditAction::Created, AuditTarget::Role, role.id).after(&dto);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    ok(RealmRoleObject { role: dto })
+}
+
+pub async fn update_role(
+    Path((realm_id, role_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<UpdateRoleRequest>
+) -> NebulaResponse<RealmRoleObject> {
+    let role = service::role::find_role(&app.db, realm_id, role_id)
+        .await
+        .expect("Failed to query role");
+    let Some(role) = role else {
+        return error(StatusCode::NOT_FOUND, "Role not found");
+    };
+    // Editing a role hands its bits to everyone holding it, so the caller needs
+    // both the old and the new ones.
+    let touched = RealmPermissions::new(role.permissions).plus(&payload.permissions.unwrap_or_default());
+    if !permissions.contains_all(&touched) {
+        return error(StatusCode::FORBIDDEN, "You can't manage a role with permissions you don't have");
+    }
+
+    let before = RealmRoleDto::from_model(&role);
+    let role = service::role::update_role(&app.db, role, RoleChanges {
+        name: payload.name,
+        permissions: payload.permissions.map(|permissions| permissions.bits()),
+        colour: payload.colour,
+        position: payload.position,
+    })
+        .await
+        .expect("Failed to update role");
+    let dto = RealmRoleDto::from_model(&role);
+
+    let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::Role, role.id).before(&before).after(&dto);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    ok(RealmRoleObject { role: dto })
+}
+
+pub async fn delete_role(
+    Path((realm_id, role_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let role = service::role::find_role(&app.db, realm_id, role_id)
+        .await
+        .expect("Failed to query role");
+    let Some(role) = role else {
+        return error(StatusCode::NOT_FOUND, "Role not found");
+    };
+    if !permissions.contains_all(&RealmPermissions::new(role.permissions)) {
+        return error(StatusCode::FORBIDDEN, "You can't manage a role with permissions you don't have");
+    }
+
+    service::role::delete_role(&app.db, role.id)
+        .await
+        .expect("Failed to delete role");
+
+    let entry = AuditEntry::new(AuditAction::Deleted, AuditTarget::Role, role.id)
+        .before(&RealmRoleDto::from_model(&role));
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    no_content()
+}
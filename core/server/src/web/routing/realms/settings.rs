@@ -0,0 +1,137 @@
+use crate::app::NebulaApp;
+use crate::cableway::events::realm::{send_realm_deleted, send_realm_updated};
+use crate::schema::{realms, users};
+use crate::schema::realms::RealmVisibility;
+use crate::data::snowflake::Snowflake;
+use crate::service;
+use crate::service::realm::{ParentError, RealmChanges};
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
+use crate::util::patch::nullable;
+use crate::util::validation::{is_sane, is_timezone};
+use crate::web::routing::dto::RealmDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use crate::web::routing::realms::RealmObject;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::Serialize;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, garde::Validate)]
+pub struct UpdateRealmRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(length(min = 3, max = 48), custom(is_sane)))]
+    pub name: Option<String>,
+    /// `null` clears the description, leaving the field out keeps it.
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[garde(inner(inner(length(max = 1024), custom(is_sane))))]
+    pub description: Option<Option<String>>,
+    /// `null` makes the realm top-level again.
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub parent_id: Option<Option<Snowflake>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(skip)]
+    pub visibility: Option<RealmVisibility>,
+    /// Time zone the realm's new events default to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[garde(inner(custom(is_timezone)))]
+    pub timezone: Option<String>,
+}
+
+/// Deleting a realm takes everything in it along, so the owner has to type its
+/// name back.
+#[derive(serde::Serialize, serde::Deserialize, Debug, garde::Validate)]
+pub struct DeleteRealmRequest {
+    #[garde(length(max = 48))]
+    pub confirm_name: String,
+}
+
+pub async fn update_realm(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<UpdateRealmRequest>
+) -> NebulaResponse<RealmObject> {
+    if let Some(name) = &payload.name {
+        let name_taken = service::realm::owner_has_realm_named(&app.db, realm.owner_id, name, Some(realm.id))
+            .await
+            .expect("Failed to query realms");
+        if name_taken {
+            return error(StatusCode::CONFLICT, "The owner already has a realm with the same name");
+        }
+    }
+
+    let parent_id = payload.parent_id.filter(|parent_id| *parent_id != realm.parent_id);
+    if let Some(parent_id) = parent_id {
+        // Moving a realm out of its parent takes the parent's say as well.
+        if let Some(current) = realm.parent_id
+            && let Err(err) = service::realm::managed_realm(&app.db, user.id, current).await {
+            return parent_error(err);
+        }
+        if let Some(parent_id) = parent_id
+            && let Err(err) = service::realm::check_parent(&app.db, user.id, Some(&realm), parent_id).await {
+            return parent_error(err);
+        }
+    }
+
+    let before = RealmDto::from_model(&realm);
+    let realm = service::realm::update_realm(&app.db, realm, RealmChanges {
+        name: payload.name,
+        description: payload.description,
+        parent_id,
+        visibility: payload.visibility,
+        timezone: payload.timezone,
+    })
+        .await
+        .expect("Failed to update realm");
+    let dto = RealmDto::from_model(&realm);
+
+    let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::Realm, realm.id).before(&before).after(&dto);
+    service::audit::record(&app.db, realm.id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    send_realm_updated(&app.cableway, dto.clone())
+        .await
+        .expect("Failed to send realm updated message");
+
+    ok(RealmObject { realm: dto })
+}
+
+pub async fn delete_realm(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<DeleteRealmRequest>
+) -> NebulaResponse<()> {
+    if user.id != realm.owner_id {
+        return error(StatusCode::FORBIDDEN, "Only the owner can delete a realm");
+    }
+    if payload.confirm_name != realm.name {
+        return error(StatusCode::BAD_REQUEST, "The confirmation doesn't match the realm name");
+    }
+
+    // Sent first so subscribers are still authorized for the realm's topics.
+    send_realm_deleted(&app.cableway, realm.id)
+        .await
+        .expect("Failed to send realm deleted message");
+
+    service::realm::delete_realm(&app.db, realm.id)
+        .await
+        .expect("Failed to delete realm");
+
+    no_content()
+}
+
+pub(crate) fn parent_error<T: Serialize>(err: ParentError) -> NebulaResponse<T> {
+    match err {
+        ParentError::NotFound => error(StatusCode::NOT_FOUND, "Parent realm not found"),
+        ParentError::Forbidden => error(StatusCode::FORBIDDEN, "You can't manage the parent realm"),
+        ParentError::Archived => error(StatusCode::LOCKED, "The parent realm is archived"),
+        ParentError::Cycle => error(StatusCode::BAD_REQUEST, "A realm can't be nested inside itself"),
+        ParentError::TooDeep => error(StatusCode::BAD_REQUEST, "Realms can't be nested this deep"),
+        ParentError::Database(err) => panic!("Failed to check parent realm: {:?}", err),
+    }
+}
@@ -0,0 +1,88 @@
+use crate::app::NebulaApp;
+use crate::data::snowflake::Snowflake;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::schema::users;
+use crate::service;
+use crate::service::audit::AuditEntry;
+use crate::web::routing::dto::RealmShareLinkDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct CreateShareLinkRequest {
+    #[serde(default)]
+    #[garde(skip)]
+    pub include_tasks: bool,
+    /// Event and task descriptions are left out unless this is set.
+    #[serde(default)]
+    #[garde(skip)]
+    pub include_descriptions: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmShareLinkObject {
+    pub link: RealmShareLinkDto,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealmShareLinksObject {
+    pub links: Vec<RealmShareLinkDto>,
+}
+
+pub async fn create_share_link(
+    Path(realm_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<CreateShareLinkRequest>
+) -> NebulaResponse<RealmShareLinkObject> {
+    let link = service::share::create_link(
+        &app.db,
+        realm_id,
+        user.id,
+        payload.include_tasks,
+        payload.include_descriptions
+    )
+        .await
+        .expect("Failed to create share link");
+
+    let entry = AuditEntry::new(AuditAction::Created, AuditTarget::ShareLink, link.id).after(&payload);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    ok(RealmShareLinkObject { link: RealmShareLinkDto::from_model(&link) })
+}
+
+pub async fn list_share_links(
+    Path(realm_id): Path<Snowflake>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmShareLinksObject> {
+    let links = service::share::list_links(&app.db, realm_id)
+        .await
+        .expect("Failed to query share links");
+    ok(RealmShareLinksObject { links: links.iter().map(RealmShareLinkDto::from_model).collect() })
+}
+
+pub async fn revoke_share_link(
+    Path((realm_id, link_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let revoked = service::share::revoke_link(&app.db, realm_id, link_id)
+        .await
+        .expect("Failed to revoke share link");
+    if !revoked {
+        return error(StatusCode::NOT_FOUND, "Share link not found");
+    }
+
+    let entry = AuditEntry::new(AuditAction::Revoked, AuditTarget::ShareLink, link_id);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    no_content()
+}
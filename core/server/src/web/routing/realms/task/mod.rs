@@ -1,10 +1,14 @@
 use crate::util::validation::is_sane;
 use crate::app::NebulaApp;
+use crate::data::permissions::{RealmPermission, RealmPermissions};
 use crate::data::snowflake::Snowflake;
 use crate::schema::{realm_tasks, users};
+use crate::service;
 use crate::service::snowflake::next_snowflake;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
 use crate::web::routing::dto::TaskDto;
-use crate::web::routing::error::{ok, NebulaResponse};
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
 use crate::web::routing::middlewares::validation::{ValidJson, ValidQuery};
 use axum::extract::{Path, State};
 use axum::Extension;
@@ -67,9 +71,55 @@ pub async fn create_task(
         .await
         .expect("Failed to insert new task");
     let task_dto = TaskDto::from_model(inserted_task);
+
+    let entry = AuditEntry::new(AuditAction::Created, AuditTarget::Task, task_id).after(&task_dto);
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
     ok(TaskObject { task: task_dto })
 }
 
+#[derive(Serialize, Deserialize, Debug, Validate)]
+pub struct TaskCompletionRequest {
+    #[garde(skip)]
+    pub completed: bool,
+}
+
+/// Marks a task done or not done. Members with `CompleteTasks` can do this
+/// without being able to edit tasks otherwise.
+pub async fn set_completion(
+    Path((realm_id, task_id)): Path<(Snowflake, Snowflake)>,
+    Extension(user): Extension<users::Model>,
+    Extension(permissions): Extension<RealmPermissions>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<TaskCompletionRequest>
+) -> NebulaResponse<()> {
+    if !permissions.contains_any(&[RealmPermission::CompleteTasks, RealmPermission::ManageTasks]) {
+        return error(axum::http::StatusCode::FORBIDDEN, "You can't complete tasks in this realm");
+    }
+
+    let updated = realm_tasks::Entity::update_many()
+        .col_expr(realm_tasks::Column::Completed, sea_query::Expr::value(payload.completed))
+        .col_expr(realm_tasks::Column::UpdatedAt, sea_query::Expr::value(chrono::Utc::now().naive_utc()))
+        .filter(realm_tasks::Column::Id.eq(task_id))
+        .filter(realm_tasks::Column::RealmId.eq(realm_id))
+        .exec(&app.db)
+        .await
+        .expect("Failed to update task");
+    if updated.rows_affected == 0 {
+        return error(axum::http::StatusCode::NOT_FOUND, "Task not found");
+    }
+
+    let entry = AuditEntry::new(AuditAction::Updated, AuditTarget::Task, task_id)
+        .after(&serde_json::json!({ "completed": payload.completed }));
+    service::audit::record(&app.db, realm_id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    no_content()
+}
+
 #[derive(Deserialize, Debug, Validate)]
 pub struct TaskQuery {
     #[garde(skip)]
@@ -0,0 +1,125 @@
+use crate::app::NebulaApp;
+use crate::cableway::events::realm::{send_owner_changed, send_ownership_transfer_changed};
+use crate::data::snowflake::Snowflake;
+use crate::schema::{realms, users};
+use crate::service;
+use crate::service::realm::TransferError;
+use crate::schema::realm_audit_log::{AuditAction, AuditTarget};
+use crate::service::audit::AuditEntry;
+use crate::web::routing::dto::RealmDto;
+use crate::web::routing::error::{error, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use crate::web::routing::realms::RealmObject;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::Serialize;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, garde::Validate)]
+pub struct TransferOwnershipRequest {
+    #[garde(skip)]
+    pub user_id: Snowflake,
+}
+
+/// First step of a transfer: the owner offers the realm to one of its members.
+pub async fn propose_transfer(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<TransferOwnershipRequest>
+) -> NebulaResponse<RealmObject> {
+    if user.id != realm.owner_id {
+        return error(StatusCode::FORBIDDEN, "Only the owner can transfer a realm");
+    }
+    if payload.user_id == user.id {
+        return error(StatusCode::BAD_REQUEST, "You already own this realm");
+    }
+
+    set_pending_owner(&app, &user, realm, Some(payload.user_id), AuditAction::TransferProposed).await
+}
+
+pub async fn cancel_transfer(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmObject> {
+    if user.id != realm.owner_id {
+        return error(StatusCode::FORBIDDEN, "Only the owner can cancel a transfer");
+    }
+
+    set_pending_owner(&app, &user, realm, None, AuditAction::TransferCancelled).await
+}
+
+pub async fn decline_transfer(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmObject> {
+    if realm.pending_owner_id != Some(user.id) {
+        return transfer_error(TransferError::NotPending);
+    }
+
+    set_pending_owner(&app, &user, realm, None, AuditAction::TransferDeclined).await
+}
+
+/// Second step: the member the realm was offered to takes it over.
+pub async fn accept_transfer(
+    Extension(user): Extension<users::Model>,
+    Extension(realm): Extension<realms::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<RealmObject> {
+    let before = RealmDto::from_model(&realm);
+    let previous_owner_id = realm.owner_id;
+    let realm = match service::realm::accept_transfer(&app.db, realm, user.id).await {
+        Ok(realm) => realm,
+        Err(err) => return transfer_error(err),
+    };
+
+    let dto = RealmDto::from_model(&realm);
+
+    let entry = AuditEntry::new(AuditAction::TransferAccepted, AuditTarget::Realm, realm.id).before(&before).after(&dto);
+    service::audit::record(&app.db, realm.id, user.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    send_owner_changed(&app.cableway, realm.id, previous_owner_id, realm.owner_id)
+        .await
+        .expect("Failed to send owner changed message");
+
+    ok(RealmObject { realm: dto })
+}
+
+async fn set_pending_owner(
+    app: &NebulaApp,
+    actor: &users::Model,
+    realm: realms::Model,
+    user_id: Option<Snowflake>,
+    action: AuditAction
+) -> NebulaResponse<RealmObject> {
+    let before = RealmDto::from_model(&realm);
+    let realm = match service::realm::set_pending_owner(&app.db, realm, user_id).await {
+        Ok(realm) => realm,
+        Err(err) => return transfer_error(err),
+    };
+    let dto = RealmDto::from_model(&realm);
+
+    let entry = AuditEntry::new(action, AuditTarget::Realm, realm.id).before(&before).after(&dto);
+    service::audit::record(&app.db, realm.id, actor.id, entry)
+        .await
+        .expect("Failed to record audit log entry");
+
+    send_ownership_transfer_changed(&app.cableway, &dto)
+        .await
+        .expect("Failed to send ownership transfer message");
+
+    ok(RealmObject { realm: dto })
+}
+
+fn transfer_error<T: Serialize>(err: TransferError) -> NebulaResponse<T> {
+    match err {
+        TransferError::NotMember => error(StatusCode::BAD_REQUEST, "The new owner must be a member of the realm"),
+        TransferError::NotPending => error(StatusCode::FORBIDDEN, "This realm wasn't offered to you"),
+        TransferError::NameTaken => error(StatusCode::CONFLICT, "You already have a realm with the same name"),
+        TransferError::Database(err) => panic!("Failed to transfer realm: {:?}", err),
+    }
+}
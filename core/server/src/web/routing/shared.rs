@@ -0,0 +1,54 @@
+use crate::app::NebulaApp;
+use crate::service;
+use crate::web::routing::dto::RealmScheduleDto;
+use crate::web::routing::error::{error, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidQuery;
+use crate::web::routing::realms::calendar::occurrences::OccurrenceQuery;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// Anyone can hit these routes, so a single request can't ask for years of occurrences.
+const MAX_WINDOW_DAYS: i64 = 366;
+
+/// What a share link reveals about the realm itself.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SharedRealmDto {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SharedScheduleObject {
+    pub realm: SharedRealmDto,
+    pub schedule: RealmScheduleDto,
+}
+
+pub async fn get_shared_schedule(
+    Path(token): Path<String>,
+    State(app): State<NebulaApp>,
+    ValidQuery(query): ValidQuery<OccurrenceQuery>
+) -> NebulaResponse<SharedScheduleObject> {
+    if query.end < query.start || query.end - query.start > chrono::Duration::days(MAX_WINDOW_DAYS) {
+        return error(StatusCode::BAD_REQUEST, "The schedule window must be at most a year long");
+    }
+
+    let link = service::share::find_active_link(&app.db, &token)
+        .await
+        .expect("Failed to query share link");
+    let Some((link, realm)) = link else {
+        return error(StatusCode::NOT_FOUND, "Share link not found");
+    };
+
+    let schedule = service::share::shared_schedule(&app.db, &link, &realm, query.start, query.end)
+        .await
+        .expect("Failed to get shared schedule");
+
+    ok(SharedScheduleObject {
+        realm: SharedRealmDto {
+            name: realm.name,
+            description: realm.description.filter(|_| link.include_descriptions),
+        },
+        schedule,
+    })
+}
@@ -0,0 +1,155 @@
+use crate::app::NebulaApp;
+use crate::mail::messages;
+use crate::schema::user_tokens::Purpose;
+use crate::schema::{sessions, users};
+use crate::service;
+use crate::service::account::{DeleteAccountError, OwnedRealmsAction};
+use crate::service::password::PasswordCheck;
+use crate::util::validation::is_sane;
+use crate::web::routing::auth::{MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
+use crate::web::routing::dto::UserDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use crate::web::routing::users::UserObject;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Extension;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct UpdateProfileRequest {
+    #[garde(length(min = 8, max = 50), custom(is_sane))]
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct ChangePasswordRequest {
+    /// Can be left out by accounts that were created through a login provider
+    /// and never had a password.
+    #[serde(default)]
+    #[garde(inner(length(max = MAX_PASSWORD_LENGTH)))]
+    pub current_password: Option<String>,
+    #[garde(length(min = MIN_PASSWORD_LENGTH, max = MAX_PASSWORD_LENGTH), custom(is_sane))]
+    pub new_password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct ChangeEmailRequest {
+    #[garde(email)]
+    pub email: String,
+    #[serde(default)]
+    #[garde(inner(length(max = MAX_PASSWORD_LENGTH)))]
+    pub password: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct DeleteAccountRequest {
+    #[serde(default)]
+    #[garde(inner(length(max = MAX_PASSWORD_LENGTH)))]
+    pub password: Option<String>,
+    #[serde(default)]
+    #[garde(skip)]
+    pub owned_realms: Option<OwnedRealmsAction>,
+}
+
+pub async fn update_profile(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<UpdateProfileRequest>
+) -> NebulaResponse<UserObject> {
+    let mut active_user = user.into_active_model();
+    active_user.name = Set(payload.name);
+    active_user.updated_at = Set(chrono::Utc::now().naive_utc());
+    let user = active_user.update(&app.db)
+        .await
+        .expect("Failed to update user");
+    ok(UserObject { user: UserDto::from_model(&user) })
+}
+
+pub async fn change_password(
+    Extension(user): Extension<users::Model>,
+    Extension(session): Extension<sessions::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<ChangePasswordRequest>
+) -> NebulaResponse<()> {
+    if !confirms_password(&app, &user, payload.current_password.as_deref()) {
+        return error(StatusCode::FORBIDDEN, "Current password is incorrect");
+    }
+
+    let user_id = user.id;
+    let mut active_user = user.into_active_model();
+    active_user.password_hash = Set(Some(service::password::hash_password(&app.config, &payload.new_password)));
+    active_user.updated_at = Set(chrono::Utc::now().naive_utc());
+    active_user.update(&app.db)
+        .await
+        .expect("Failed to update password");
+
+    service::session::revoke_other_sessions(&app.db, user_id, session.id)
+        .await
+        .expect("Failed to revoke sessions");
+    no_content()
+}
+
+/// Sends a confirmation link to the new address. The email only changes once
+/// that link is used.
+pub async fn request_email_change(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<ChangeEmailRequest>
+) -> NebulaResponse<()> {
+    if !confirms_password(&app, &user, payload.password.as_deref()) {
+        return error(StatusCode::FORBIDDEN, "Password is incorrect");
+    }
+    if payload.email == user.email {
+        return error(StatusCode::BAD_REQUEST, "This is already your email");
+    }
+    let user_with_same_email = users::Entity::find()
+        .filter(users::Column::Email.eq(payload.email.clone()))
+        .one(&app.db)
+        .await
+        .expect("Failed to query the database");
+    if user_with_same_email.is_some() {
+        return error(StatusCode::CONFLICT, "A user with the same email already exists");
+    }
+
+    let token = service::user_token::issue_token(&app.db, user.id, Purpose::EmailChange, Some(payload.email.clone()))
+        .await
+        .expect("Failed to issue email change token");
+    let message = messages::email_change(&app.config, &payload.email, &token);
+    if let Err(err) = app.mailer.send(message).await {
+        tracing::error!("Failed to send email change confirmation: {}", err.0);
+    }
+    no_content()
+}
+
+pub async fn delete_account(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<DeleteAccountRequest>
+) -> NebulaResponse<()> {
+    if !confirms_password(&app, &user, payload.password.as_deref()) {
+        return error(StatusCode::FORBIDDEN, "Password is incorrect");
+    }
+
+    match service::account::delete_account(&app.db, user.id, payload.owned_realms).await {
+        Ok(()) => no_content(),
+        Err(DeleteAccountError::SharedRealms) => error(
+            StatusCode::CONFLICT,
+            "You own realms with other members, choose whether to transfer or delete them"
+        ),
+        Err(DeleteAccountError::Database(err)) => panic!("Failed to delete account: {err}"),
+    }
+}
+
+/// Accounts without a password are already authenticated by their session, so
+/// there's nothing else to check for them.
+fn confirms_password(app: &NebulaApp, user: &users::Model, password: Option<&str>) -> bool {
+    let Some(stored_hash) = user.password_hash.as_deref() else {
+        return true;
+    };
+    let Some(password) = password else {
+        return false;
+    };
+    service::password::verify_password(&app.config, stored_hash, password) != PasswordCheck::Invalid
+}
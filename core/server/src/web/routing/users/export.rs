@@ -0,0 +1,95 @@
+use crate::app::NebulaApp;
+use crate::data::snowflake::Snowflake;
+use crate::schema::data_exports::ExportStatus;
+use crate::schema::users;
+use crate::service;
+use crate::web::routing::dto::DataExportDto;
+use crate::web::routing::error::{error, ok, NebulaResponse};
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DataExportObject {
+    pub export: DataExportDto,
+}
+
+/// Small exports are returned right away. Larger ones are queued; the response
+/// is 202 with the job to poll.
+pub async fn export_data(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> Response {
+    let size = service::export::count_authored_content(&app.db, user.id)
+        .await
+        .expect("Failed to count authored content");
+
+    if size <= app.config.export_sync_limit {
+        return match service::export::build_archive(&app.db, &user).await {
+            Ok(archive) => archive_response(user.id, archive),
+            Err(err) => {
+                tracing::error!("Failed to build data export: {:?}", err);
+                error::<()>(StatusCode::INTERNAL_SERVER_ERROR, "Failed to build export").into_response()
+            }
+        };
+    }
+
+    let (export, created) = service::export::find_or_create_pending(&app.db, user.id)
+        .await
+        .expect("Failed to queue data export");
+    if created {
+        tokio::spawn(service::export::run_export(app.db.clone(), export.id, user));
+    }
+
+    let (_, body) = ok(DataExportObject { export: DataExportDto::from_model(&export) });
+    (StatusCode::ACCEPTED, body).into_response()
+}
+
+pub async fn get_export(
+    Path(export_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<DataExportObject> {
+    let export = service::export::find_export(&app.db, user.id, export_id)
+        .await
+        .expect("Failed to query data export");
+
+    match export {
+        Some(export) => ok(DataExportObject { export: DataExportDto::from_model(&export) }),
+        None => error(StatusCode::NOT_FOUND, "Export not found")
+    }
+}
+
+pub async fn download_export(
+    Path(export_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> Response {
+    let export = service::export::find_export(&app.db, user.id, export_id)
+        .await
+        .expect("Failed to query data export");
+
+    let Some(export) = export else {
+        return error::<()>(StatusCode::NOT_FOUND, "Export not found").into_response();
+    };
+
+    match (export.status, export.archive) {
+        (ExportStatus::Ready, Some(archive)) => archive_response(user.id, archive),
+        (ExportStatus::Failed, _) => error::<()>(StatusCode::GONE, "Export failed, request a new one").into_response(),
+        _ => error::<()>(StatusCode::CONFLICT, "Export is not ready yet").into_response(),
+    }
+}
+
+fn archive_response(user_id: Snowflake, archive: Vec<u8>) -> Response {
+    let disposition = format!("attachment; filename=\"nebula-export-{}.zip\"", user_id);
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive
+    ).into_response()
+}
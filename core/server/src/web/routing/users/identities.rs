@@ -0,0 +1,58 @@
+use crate::app::NebulaApp;
+use crate::data::snowflake::Snowflake;
+use crate::schema::{user_identities, users};
+use crate::service;
+use crate::web::routing::auth::oidc::{start_authorization, AuthorizationObject};
+use crate::web::routing::dto::IdentityDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IdentitiesObject {
+    pub identities: Vec<IdentityDto>,
+}
+
+pub async fn list_identities(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<IdentitiesObject> {
+    let identities = service::identity::list_identities(&app.db, user.id)
+        .await
+        .expect("Failed to query identities");
+    let dtos = identities.iter().map(IdentityDto::from_model).collect();
+    ok(IdentitiesObject { identities: dtos })
+}
+
+pub async fn link_identity(
+    Path(provider): Path<String>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<AuthorizationObject> {
+    start_authorization(&app, &provider, Some(user.id)).await
+}
+
+pub async fn unlink_identity(
+    Path(identity_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let identities = service::identity::list_identities(&app.db, user.id)
+        .await
+        .expect("Failed to query identities");
+    if !identities.iter().any(|identity| identity.id == identity_id) {
+        return error(StatusCode::NOT_FOUND, "Identity not found");
+    }
+    if user.password_hash.is_none() && identities.len() == 1 {
+        return error(StatusCode::CONFLICT, "Set a password before unlinking your only login provider");
+    }
+
+    user_identities::Entity::delete_by_id(identity_id)
+        .exec(&app.db)
+        .await
+        .expect("Failed to unlink identity");
+    no_content()
+}
@@ -1,4 +1,10 @@
 pub mod status;
+pub mod sessions;
+pub mod totp;
+pub mod identities;
+pub mod tokens;
+pub mod account;
+pub mod export;
 
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
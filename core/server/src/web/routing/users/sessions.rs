@@ -0,0 +1,70 @@
+use crate::app::NebulaApp;
+use crate::data::snowflake::Snowflake;
+use crate::schema::{sessions, users};
+use crate::service;
+use crate::web::routing::dto::SessionDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionsObject {
+    pub sessions: Vec<SessionDto>
+}
+
+pub async fn list_sessions(
+    Extension(user): Extension<users::Model>,
+    Extension(session): Extension<sessions::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<SessionsObject> {
+    let active_sessions = service::session::list_active_sessions(&app.db, user.id)
+        .await
+        .expect("Failed to query sessions");
+
+    let dtos = active_sessions
+        .iter()
+        .map(|s| SessionDto::from_model(s, session.id))
+        .collect();
+    ok(SessionsObject { sessions: dtos })
+}
+
+pub async fn logout_current_session(
+    Extension(session): Extension<sessions::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    service::session::revoke_session(&app.db, session.id)
+        .await
+        .expect("Failed to revoke session");
+    no_content()
+}
+
+pub async fn revoke_session(
+    Path(session_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let target = service::session::find_active_session(&app.db, session_id, user.id)
+        .await
+        .expect("Failed to query session");
+    if target.is_none() {
+        return error(StatusCode::NOT_FOUND, "Session not found");
+    }
+
+    service::session::revoke_session(&app.db, session_id)
+        .await
+        .expect("Failed to revoke session");
+    no_content()
+}
+
+pub async fn revoke_other_sessions(
+    Extension(user): Extension<users::Model>,
+    Extension(session): Extension<sessions::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    service::session::revoke_other_sessions(&app.db, user.id, session.id)
+        .await
+        .expect("Failed to revoke sessions");
+    no_content()
+}
@@ -10,18 +10,44 @@ use crate::app::NebulaApp;
 use crate::schema::{realm_members, realms, users};
 use crate::web::routing::dto::{SelfStatusDto, RealmDto, UserDto};
 use crate::web::routing::error::{NebulaResponse, ok};
+use crate::web::routing::middlewares::validation::ValidQuery;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, garde::Validate)]
+pub struct StatusQuery {
+    /// Archived realms are left out unless this is set.
+    #[serde(default)]
+    #[garde(skip)]
+    pub include_archived: bool,
+}
 
 pub async fn get_self_status(
     Extension(user): Extension<users::Model>,
-    State(app): State<NebulaApp>
+    State(app): State<NebulaApp>,
+    ValidQuery(query): ValidQuery<StatusQuery>
 ) -> NebulaResponse<SelfStatusDto> {
-    let realms = realms::Entity::find()
+    let mut realms_query = realms::Entity::find()
         .join(JoinType::InnerJoin, realms::Relation::RealmMembers.def())
-        .filter(realm_members::Column::UserId.eq(user.id))
+        .filter(realm_members::Column::UserId.eq(user.id));
+    if !query.include_archived {
+        realms_query = realms_query.filter(realms::Column::ArchivedAt.is_null());
+    }
+    let mut realms = realms_query
         .all(&app.db)
         .await
         .expect("Failed to query realms");
 
+    // Realms nested in the user's realms are reachable through inherited membership.
+    let realm_ids: Vec<_> = realms.iter().map(|realm| realm.id).collect();
+    let descendants = crate::service::realm::descendants(&app.db, &realm_ids)
+        .await
+        .expect("Failed to query nested realms");
+    for descendant in descendants {
+        let visible = query.include_archived || descendant.archived_at.is_none();
+        if visible && !realms.iter().any(|realm| realm.id == descendant.id) {
+            realms.push(descendant);
+        }
+    }
+
     let realms_dto: Vec<RealmDto> = realms
         .iter()
         .map(|realm| RealmDto::from_model(realm))
@@ -30,6 +56,8 @@ pub async fn get_self_status(
     let status = SelfStatusDto {
         realms: realms_dto,
         me: UserDto::from_model(&user),
+        email_verified: user.email_verified,
+        totp_enabled: user.totp_enabled,
     };
 
     ok(status)
@@ -0,0 +1,86 @@
+use crate::app::NebulaApp;
+use crate::data::scopes::{TokenScope, TokenScopes};
+use crate::data::snowflake::Snowflake;
+use crate::schema::users;
+use crate::service;
+use crate::util::validation::is_sane;
+use crate::web::routing::dto::AccessTokenDto;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct CreateAccessTokenRequest {
+    #[garde(length(min = 1, max = 64), custom(is_sane))]
+    pub name: String,
+    #[garde(length(min = 1))]
+    pub scopes: Vec<TokenScope>,
+    #[serde(default)]
+    #[garde(skip)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AccessTokensObject {
+    pub tokens: Vec<AccessTokenDto>,
+}
+
+/// The secret is only ever returned here, right after creation.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreatedAccessTokenObject {
+    pub token: AccessTokenDto,
+    pub secret: String,
+}
+
+pub async fn list_tokens(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<AccessTokensObject> {
+    let tokens = service::access_token::list_tokens(&app.db, user.id)
+        .await
+        .expect("Failed to query personal access tokens");
+    let dtos = tokens.iter().map(AccessTokenDto::from_model).collect();
+    ok(AccessTokensObject { tokens: dtos })
+}
+
+pub async fn create_token(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<CreateAccessTokenRequest>
+) -> NebulaResponse<CreatedAccessTokenObject> {
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+        return error(StatusCode::BAD_REQUEST, "Expiry must be in the future");
+    }
+
+    let created = service::access_token::create_token(
+        &app.db,
+        user.id,
+        payload.name,
+        TokenScopes::from_slice(&payload.scopes),
+        payload.expires_at
+    )
+        .await
+        .expect("Failed to create personal access token");
+
+    ok(CreatedAccessTokenObject {
+        token: AccessTokenDto::from_model(&created.model),
+        secret: created.token,
+    })
+}
+
+pub async fn delete_token(
+    Path(token_id): Path<Snowflake>,
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<()> {
+    let deleted = service::access_token::delete_token(&app.db, user.id, token_id)
+        .await
+        .expect("Failed to delete personal access token");
+    if !deleted {
+        return error(StatusCode::NOT_FOUND, "Token not found");
+    }
+    no_content()
+}
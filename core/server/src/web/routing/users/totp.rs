@@ -0,0 +1,103 @@
+use crate::app::NebulaApp;
+use crate::schema::users;
+use crate::service;
+use crate::web::routing::error::{error, no_content, ok, NebulaResponse};
+use crate::web::routing::middlewares::validation::ValidJson;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Extension;
+use sea_orm::{ActiveModelTrait, IntoActiveModel, Set};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TotpEnrollmentObject {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecoveryCodesObject {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, garde::Validate)]
+pub struct TotpCodeRequest {
+    #[garde(length(min = 6, max = 32))]
+    pub code: String,
+}
+
+/// Starts enrollment by storing a new secret. It only takes effect once a code
+/// generated from it is confirmed.
+pub async fn enroll_totp(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>
+) -> NebulaResponse<TotpEnrollmentObject> {
+    if user.totp_enabled {
+        return error(StatusCode::CONFLICT, "Two-factor authentication is already enabled");
+    }
+
+    let secret = service::totp::generate_secret();
+    let otpauth_uri = service::totp::otpauth_uri(&secret, &user.email);
+    let mut active_user = user.into_active_model();
+    active_user.totp_secret = Set(Some(secret.clone()));
+    active_user.totp_last_step = Set(None);
+    active_user.update(&app.db)
+        .await
+        .expect("Failed to store TOTP secret");
+
+    ok(TotpEnrollmentObject { secret, otpauth_uri })
+}
+
+pub async fn confirm_totp(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<TotpCodeRequest>
+) -> NebulaResponse<RecoveryCodesObject> {
+    if user.totp_enabled {
+        return error(StatusCode::CONFLICT, "Two-factor authentication is already enabled");
+    }
+    let Some(secret) = user.totp_secret.clone() else {
+        return error(StatusCode::BAD_REQUEST, "Two-factor enrollment was not started");
+    };
+    let Some(step) = service::totp::verify_code(&secret, payload.code.trim(), None) else {
+        return error(StatusCode::BAD_REQUEST, "Invalid authentication code");
+    };
+
+    let (recovery_codes, hashes) = service::totp::generate_recovery_codes();
+    let mut active_user = user.into_active_model();
+    active_user.totp_enabled = Set(true);
+    active_user.totp_last_step = Set(Some(step));
+    active_user.totp_recovery_codes = Set(Some(hashes));
+    active_user.update(&app.db)
+        .await
+        .expect("Failed to enable two-factor authentication");
+
+    ok(RecoveryCodesObject { recovery_codes })
+}
+
+pub async fn disable_totp(
+    Extension(user): Extension<users::Model>,
+    State(app): State<NebulaApp>,
+    ValidJson(payload): ValidJson<TotpCodeRequest>
+) -> NebulaResponse<()> {
+    if !user.totp_enabled {
+        return error(StatusCode::CONFLICT, "Two-factor authentication is not enabled");
+    }
+    let user = service::totp::verify_second_factor(&app.db, user, &payload.code)
+        .await
+        .expect("Failed to verify second factor");
+    let Some(user) = user else {
+        return error(StatusCode::BAD_REQUEST, "Invalid authentication code");
+    };
+
+    let mut active_user = user.into_active_model();
+    active_user.totp_enabled = Set(false);
+    active_user.totp_secret = Set(None);
+    active_user.totp_recovery_codes = Set(None);
+    active_user.totp_last_step = Set(None);
+    active_user.update(&app.db)
+        .await
+        .expect("Failed to disable two-factor authentication");
+
+    no_content()
+}